@@ -0,0 +1,299 @@
+//! 能力注册表
+//! 玩家拾取能力型道具解锁的移动能力统一记录在`Abilities`组件中并随存档保存，
+//! 输入缓冲、动画状态与关卡中的能力锁都通过它判断能力是否可用。
+//! 这里实现二段跳与空中冲刺的动画状态、滑墙减速、下砸，以及需要特定能力才能打开的能力锁。
+
+use std::collections::HashSet;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_tnua::builtins::TnuaBuiltinDash;
+use bevy_tnua::prelude::*;
+use game_derive::{enter, exit};
+
+use crate::animator::*;
+use crate::damagable::{check_hitbox, HasHitbox, HitBox, HitboxOf, Knockback};
+use crate::enemy::perception::Noise;
+use crate::game_layer::GameLayer;
+use crate::gravity::GravityDirection;
+use crate::items::{spawn_pickup, AbilityType, ItemList};
+use crate::level::LevelObjects;
+use crate::player::{AnimationType, Player};
+use crate::save::TransformData;
+use crate::world_flags::{item_flag, lock_flag, SetWorldFlag, WorldFlags};
+
+/// 滑墙时的最大下落速度
+const WALL_SLIDE_SPEED: f32 = 40.;
+/// 空中冲刺距离
+const AIR_DASH_DISTANCE: f32 = 90.;
+/// 空中冲刺速度
+const AIR_DASH_SPEED: f32 = 600.;
+/// 下砸速度
+const GROUND_POUND_SPEED: f32 = 500.;
+/// 下砸伤害
+const GROUND_POUND_DAMAGE: f32 = 250.;
+
+/// 已解锁的能力
+#[derive(Component, Debug, Default, Clone)]
+pub struct Abilities {
+    pub unlocked: HashSet<AbilityType>,
+}
+
+impl Abilities {
+    /// 从存档恢复，兼容用动画参数记录能力的旧存档
+    pub fn from_save(data: Option<TransformData>) -> Self {
+        let Some(data) = data else { return Self::default(); };
+        let mut unlocked = data.abilities;
+        for (param, ability) in [
+            ("can_wall_jump", AbilityType::WallJump),
+            ("can_reverse_gravity", AbilityType::ReverseGravity),
+        ] {
+            if let Some(AnimatorParam::Bool(true)) = data.params.get(param) {
+                unlocked.insert(ability);
+            }
+        }
+        Self { unlocked }
+    }
+
+    /// 是否拥有能力
+    pub fn has(&self, ability: AbilityType) -> bool {
+        self.unlocked.contains(&ability)
+    }
+
+    /// 解锁能力
+    pub fn unlock(&mut self, ability: AbilityType) {
+        self.unlocked.insert(ability);
+    }
+
+    /// 能否贴在墙上：滑墙与蹬墙跳都需要
+    pub fn can_cling(&self) -> bool {
+        self.has(AbilityType::WallSlide) || self.has(AbilityType::WallJump)
+    }
+}
+
+/// 按名字解析能力，用于地图对象属性
+fn parse_ability(name: &str) -> Option<AbilityType> {
+    match name {
+        "WallJump" => Some(AbilityType::WallJump),
+        "ReverseGravity" => Some(AbilityType::ReverseGravity),
+        "DoubleJump" => Some(AbilityType::DoubleJump),
+        "AirDash" => Some(AbilityType::AirDash),
+        "WallSlide" => Some(AbilityType::WallSlide),
+        "GroundPound" => Some(AbilityType::GroundPound),
+        _ => None,
+    }
+}
+
+/// 以trigger为条件、立即生效的转换
+fn trigger_transition(trigger: &str, target: &str) -> Transition {
+    Transition {
+        conditions: vec![Condition {
+            param_name: trigger.to_string(),
+            operator: ConditionOperator::Equals,
+            value: AnimatorParam::Trigger(true),
+        }],
+        target_state: target.to_string(),
+        has_exit_time: false,
+        exit_time: 0.0,
+    }
+}
+
+/// 空中状态使用能力的转换：空中起跳（土狼时间与二段跳）、空中冲刺、下砸
+pub fn air_transitions() -> Vec<Transition> {
+    vec![
+        trigger_transition("air_jump", "Jump"),
+        trigger_transition("air_dash", "AirDash"),
+        trigger_transition("ground_pound", "GroundPound"),
+    ]
+}
+
+/// 能力使用的动画参数
+pub fn add_ability_params(animator: &mut Animator) {
+    for param in ["air_jump", "air_dash", "ground_pound"] {
+        animator.parameters.entry(param.to_string()).or_insert(AnimatorParam::Trigger(false));
+    }
+}
+
+/// 能力专属的动画状态
+pub fn ability_states() -> Vec<AnimationState> {
+    let air_dash_state = AnimationState {
+        name: "AirDash".to_string(),
+        first_index: AnimationType::Slide.config_index().0,
+        last_index: AnimationType::Slide.config_index().1,
+        transitions: vec![Transition {
+            conditions: vec![],
+            target_state: "Fall".to_string(),
+            has_exit_time: true,
+            exit_time: 1.5,
+        }],
+        loop_animation: false,
+        on_enter: Some(__air_dash_enter_handler),
+        audio_path: Some("Audio/SFX/10_Battle_SFX/51_Flee_02.wav".to_string()),
+        ..default()
+    };
+
+    let ground_pound_state = AnimationState {
+        name: "GroundPound".to_string(),
+        first_index: AnimationType::AirAttack3Loop.config_index().0,
+        last_index: AnimationType::AirAttack3Loop.config_index().1,
+        transitions: vec![Transition {
+            conditions: vec![Condition {
+                param_name: "is_grounded".to_string(),
+                operator: ConditionOperator::Equals,
+                value: AnimatorParam::Bool(true),
+            }],
+            target_state: "Idle".to_string(),
+            has_exit_time: false,
+            exit_time: 0.0,
+        }],
+        loop_animation: true,
+        on_enter: Some(__ground_pound_enter_handler),
+        on_exit: Some(__ground_pound_exit_handler),
+        ..default()
+    };
+
+    vec![air_dash_state, ground_pound_state]
+}
+
+/// 进入空中冲刺
+#[enter("air_dash")]
+fn on_air_dash_enter(
+    mut player: Query<(&Animator, &mut TnuaController, &mut LinearVelocity), With<Player>>,
+) {
+    let Ok((animator, mut controller, mut velocity)) = player.get_mut(trigger.entity) else { return; };
+    let facing = if animator.get_bool("is_facing_right") { 1. } else { -1. };
+    // 冲刺期间不下落
+    velocity.y = 0.;
+    controller.action(TnuaBuiltinDash {
+        displacement: Vec3::new(AIR_DASH_DISTANCE, 0., 0.) * facing,
+        speed: AIR_DASH_SPEED,
+        acceleration: AIR_DASH_SPEED * 2.,
+        brake_acceleration: AIR_DASH_SPEED * 2.,
+        allow_in_air: true,
+        ..Default::default()
+    });
+}
+
+/// 进入下砸：向下加速并生成脚下的判定框
+#[enter("ground_pound")]
+fn on_ground_pound_enter(
+    mut commands: Commands,
+    mut player: Query<(&mut Animator, &GravityDirection, &mut LinearVelocity), With<Player>>,
+) {
+    let entity = trigger.entity;
+    let Ok((mut animator, direction, mut velocity)) = player.get_mut(entity) else { return; };
+    animator.set_bool("can_move", false);
+    velocity.x = 0.;
+    velocity.y = -GROUND_POUND_SPEED * direction.sign();
+    // 判定框随玩家上下翻转，始终在脚下
+    commands.spawn((
+        Collider::rectangle(20., 10.),
+        Transform::from_xyz(0., -14., 0.),
+        Sensor,
+        HitBox { damage: GROUND_POUND_DAMAGE },
+        Knockback(80.),
+        CollisionLayers::new(GameLayer::PlayerHitBox, [GameLayer::Enemy, GameLayer::Interactive]),
+        CollisionEventsEnabled,
+        ChildOf(entity),
+        HitboxOf(entity),
+    )).observe(check_hitbox);
+}
+
+/// 下砸落地
+#[exit("ground_pound")]
+fn on_ground_pound_exit(
+    mut commands: Commands,
+    mut player: Query<(&mut Animator, &Transform, Option<&HasHitbox>), With<Player>>,
+) {
+    let Ok((mut animator, transform, hitboxes)) = player.get_mut(trigger.entity) else { return; };
+    animator.set_bool("can_move", true);
+    for hitbox in hitboxes.map(|h| (**h).clone()).unwrap_or_default() {
+        commands.entity(hitbox).despawn();
+    }
+    commands.trigger(Noise::combat(transform.translation.truncate()));
+}
+
+/// 滑墙时限制下落速度
+fn wall_slide_system(
+    player: Single<(&Animator, &Abilities, &GravityDirection, &mut LinearVelocity), With<Player>>,
+) {
+    let (animator, abilities, direction, mut velocity) = player.into_inner();
+    if animator.current_state() != "WallSlide" || !abilities.has(AbilityType::WallSlide) { return; }
+    let up = direction.sign();
+    if velocity.y * up < -WALL_SLIDE_SPEED {
+        velocity.y = -WALL_SLIDE_SPEED * up;
+    }
+}
+
+/// 能力锁：拥有对应能力的玩家碰到时打开，下砸锁需要以下砸撞击
+#[derive(Component, Debug)]
+pub struct AbilityLock {
+    pub ability: AbilityType,
+    /// 打开后设置的世界标记
+    pub flag: String,
+}
+
+/// 生成能力锁与能力道具
+fn spawn_ability_objects(
+    mut commands: Commands,
+    level: Res<LevelObjects>,
+    flags: Res<WorldFlags>,
+    items: Res<ItemList>,
+) {
+    for obj in level.with_class("AbilityLock") {
+        let Some(ability) = obj.get_string("ability").and_then(|name| parse_ability(&name)) else {
+            println!("AbilityLock {} has no valid ability", obj.id);
+            continue;
+        };
+        let flag = lock_flag(obj.id);
+        if flags.is_set(&flag) { continue; }
+        commands.spawn((
+            AbilityLock { ability, flag },
+            Sprite::from_color(Color::srgb(0.45, 0.4, 0.6), obj.size),
+            Transform::from_translation(obj.position.extend(0.)),
+            RigidBody::Static,
+            Collider::rectangle(obj.size.x, obj.size.y),
+            CollisionEventsEnabled,
+            CollisionLayers::new(
+                GameLayer::Ground,
+                [GameLayer::Default, GameLayer::Player, GameLayer::Enemy],
+            ),
+        )).observe(ability_lock_observer);
+    }
+
+    // 地图中放置的能力道具，其余能力道具由首领掉落
+    for obj in level.with_class("AbilityItem") {
+        let Some(item) = obj.get_string("item") else { continue; };
+        if flags.is_set(&item_flag(&item)) { continue; }
+        spawn_pickup(&mut commands, &items, &item, 1, obj.position.extend(0.));
+    }
+}
+
+/// 玩家碰到能力锁
+fn ability_lock_observer(
+    trigger: Trigger<OnCollisionStart>,
+    mut commands: Commands,
+    locks: Query<&AbilityLock>,
+    player: Query<(&Abilities, &Animator), With<Player>>,
+) {
+    let Ok((abilities, animator)) = player.get(trigger.collider) else { return; };
+    let lock = locks.get(trigger.target()).unwrap();
+    if !abilities.has(lock.ability) { return; }
+    if lock.ability == AbilityType::GroundPound && animator.current_state() != "GroundPound" { return; }
+    commands.trigger(SetWorldFlag(lock.flag.clone()));
+    commands.entity(trigger.target()).despawn();
+}
+
+pub struct AbilitiesPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for AbilitiesPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(self.state.clone()), spawn_ability_objects);
+        app.add_systems(FixedUpdate, wall_slide_system.run_if(in_state(self.state.clone())));
+        app.add_observer(on_air_dash_enter);
+        app.add_observer(on_ground_pound_enter);
+        app.add_observer(on_ground_pound_exit);
+    }
+}
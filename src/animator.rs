@@ -117,6 +117,11 @@ impl Animator {
         self.current_state == state
     }
 
+    /// 当前状态名
+    pub fn current_state(&self) -> &str {
+        &self.current_state
+    }
+
     /// 添加状态
     pub fn add_state(&mut self, state: AnimationState) {
         let name = state.name.clone();
@@ -196,6 +201,56 @@ impl Animator {
         self.active_triggers.contains(name)
     }
 
+    /// 当前状态是否会响应该trigger：存在以它为条件的转换，且其他条件与退出时间都已满足
+    pub fn accepts(&self, trigger: &str) -> bool {
+        let Some(state) = self.states.get(&self.current_state) else { return false; };
+        state.transitions.iter().any(|transition| {
+            let uses_trigger = transition.conditions.iter().any(|condition| {
+                condition.param_name == trigger && matches!(condition.value, AnimatorParam::Trigger(true))
+            });
+            uses_trigger
+                && !(transition.has_exit_time && self.normalized_time < transition.exit_time)
+                && transition.conditions.iter()
+                    .filter(|condition| condition.param_name != trigger)
+                    .all(|condition| self.check_condition(condition))
+        })
+    }
+
+    /// 是否有以该trigger为条件、但还没到退出时间的转换，会沿无条件的定时转换查找之后的状态
+    pub fn awaits(&self, trigger: &str) -> bool {
+        let mut name = self.current_state.as_str();
+        let mut normalized_time = self.normalized_time;
+        // 每个状态最多经过一次，避免循环
+        for _ in 0..self.states.len() {
+            let Some(state) = self.states.get(name) else { return false; };
+            let waiting = state.transitions.iter().any(|transition| {
+                transition.has_exit_time
+                    && normalized_time < transition.exit_time
+                    && transition.conditions.iter().any(|condition| {
+                        condition.param_name == trigger && matches!(condition.value, AnimatorParam::Trigger(true))
+                    })
+            });
+            if waiting { return true; }
+            let Some(next) = state.transitions.iter()
+                .find(|transition| transition.has_exit_time && transition.conditions.is_empty()) else { return false; };
+            name = &next.target_state;
+            normalized_time = 0.;
+        }
+        false
+    }
+
+    /// 清空所有trigger，并在下次更新时切换到指定状态，切换时照常执行退出与进入回调
+    pub fn reset_to(&mut self, state_name: &str) {
+        let triggers: Vec<_> = self.active_triggers.iter().cloned().collect();
+        for trigger in &triggers {
+            self.reset_trigger(trigger);
+        }
+        self.consumed_triggers.clear();
+        if self.states.contains_key(state_name) {
+            self.target_state = Some(state_name.to_string());
+        }
+    }
+
     // 重置Trigger
     fn reset_trigger(&mut self, name: &str) {
         if let Some(param) = self.parameters.get_mut(name) {
@@ -0,0 +1,187 @@
+//! Boss战区域
+//! 玩家进入区域后封闭前后门、切换音乐并显示boss血条；玩家死亡时区域重置，击败boss后解封。
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_kira_audio::AudioChannel;
+
+use crate::{
+    background::{play_music, MusicChannel, AMBIENT_MUSIC}, damagable::Damagable, game_layer::GameLayer, gravity::GravityDirection, player::Player, signal::SignalTrigger,
+    world_flags::{SetWorldFlag, WorldFlags},
+};
+
+/// Boss战区域状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArenaState {
+    /// 未开始
+    Idle,
+    /// 战斗中
+    Active,
+    /// 已击败boss
+    Cleared,
+}
+
+/// Boss战区域，本身是触发战斗开始的感知器
+#[derive(Component, Debug, Clone)]
+pub struct BossArena {
+    /// 战斗开始时激活、结束时关闭的信号，入口门监听该信号
+    pub seal_signal: String,
+    /// 击败boss后激活的信号，出口门监听该信号
+    pub victory_signal: String,
+    /// 战斗音乐
+    pub music: String,
+    /// 区域状态
+    pub state: ArenaState,
+}
+
+impl BossArena {
+    /// 新建区域，信号名由区域名生成
+    pub fn new(name: &str, music: &str) -> Self {
+        Self {
+            seal_signal: format!("{}_arena_sealed", name),
+            victory_signal: format!("{}_defeated", name),
+            music: music.to_string(),
+            state: ArenaState::Idle,
+        }
+    }
+}
+
+/// 属于某个Boss战区域的boss
+#[derive(Component, Debug, Clone)]
+pub struct ArenaBoss {
+    /// 所属区域
+    pub arena: Entity,
+    /// 初始位置，重置时回到此处
+    pub spawn: Vec3,
+    /// 初始重力，重置时恢复
+    pub gravity: f32,
+}
+
+/// 区域战斗中的boss，boss血条跟随此组件显示
+#[derive(Component)]
+pub struct ActiveBoss;
+
+/// 区域重置触发器，boss模块可以观察此事件重置自身的行为状态
+#[derive(Event)]
+pub struct ArenaReset {
+    pub boss: Entity,
+}
+
+/// boss被击败触发器，由boss的死亡动画回调发出
+#[derive(Event)]
+pub struct BossDefeated {
+    pub boss: Entity,
+}
+
+/// 区域的boss是否已被击败
+pub fn is_defeated(flags: &WorldFlags, name: &str) -> bool {
+    flags.is_set(&format!("{}_defeated", name))
+}
+
+/// 生成Boss战区域感知器
+pub fn spawn_arena(
+    commands: &mut Commands,
+    arena: BossArena,
+    position: Vec2,
+    size: Vec2,
+) -> Entity {
+    commands.spawn((
+        arena,
+        Collider::rectangle(size.x, size.y),
+        CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player]),
+        Sensor,
+        CollisionEventsEnabled,
+        Transform::from_xyz(position.x, position.y, 0.),
+    )).observe(arena_enter_observer).id()
+}
+
+/// 玩家进入区域，开始战斗
+fn arena_enter_observer(
+    trigger: Trigger<OnCollisionStart>,
+    mut commands: Commands,
+    mut arenas: Query<&mut BossArena>,
+    bosses: Query<(Entity, &ArenaBoss)>,
+    music: Res<AudioChannel<MusicChannel>>,
+    asset_server: Res<AssetServer>,
+) {
+    let arena_entity = trigger.target();
+    let mut arena = arenas.get_mut(arena_entity).unwrap();
+    if arena.state != ArenaState::Idle { return; }
+    arena.state = ArenaState::Active;
+    commands.trigger(SignalTrigger::on(&arena.seal_signal));
+    play_music(&music, &asset_server, &arena.music);
+    for (boss, arena_boss) in &bosses {
+        if arena_boss.arena == arena_entity {
+            commands.entity(boss).insert(ActiveBoss);
+        }
+    }
+}
+
+/// 玩家死亡时重置战斗中的区域
+fn arena_reset_system(
+    mut commands: Commands,
+    player: Single<&Damagable, With<Player>>,
+    mut arenas: Query<&mut BossArena>,
+    mut bosses: Query<(
+        Entity,
+        &ArenaBoss,
+        &mut Transform,
+        &mut Damagable,
+        &mut GravityScale,
+        &mut GravityDirection,
+        &mut LinearVelocity,
+    ), Without<Player>>,
+    music: Res<AudioChannel<MusicChannel>>,
+    asset_server: Res<AssetServer>,
+) {
+    if player.into_inner().is_alive { return; }
+    let mut any_reset = false;
+    for (boss, arena_boss, mut transform, mut damagable, mut gravity, mut direction, mut velocity) in &mut bosses {
+        let Ok(mut arena) = arenas.get_mut(arena_boss.arena) else { continue; };
+        if arena.state != ArenaState::Active { continue; }
+        arena.state = ArenaState::Idle;
+        commands.trigger(SignalTrigger::off(&arena.seal_signal));
+        transform.translation = arena_boss.spawn;
+        damagable.copy(Damagable::new(damagable.max_health));
+        gravity.0 = arena_boss.gravity;
+        *direction = GravityDirection::Down;
+        velocity.0 = Vec2::ZERO;
+        commands.entity(boss).remove::<ActiveBoss>();
+        commands.trigger(ArenaReset { boss });
+        any_reset = true;
+    }
+    if any_reset {
+        play_music(&music, &asset_server, AMBIENT_MUSIC);
+    }
+}
+
+/// 击败boss，解封区域
+fn boss_defeated_observer(
+    trigger: Trigger<BossDefeated>,
+    mut commands: Commands,
+    bosses: Query<&ArenaBoss>,
+    mut arenas: Query<&mut BossArena>,
+    music: Res<AudioChannel<MusicChannel>>,
+    asset_server: Res<AssetServer>,
+) {
+    let Ok(arena_boss) = bosses.get(trigger.boss) else { return; };
+    let mut arena = arenas.get_mut(arena_boss.arena).unwrap();
+    arena.state = ArenaState::Cleared;
+    commands.trigger(SignalTrigger::off(&arena.seal_signal));
+    commands.trigger(SignalTrigger::on(&arena.victory_signal));
+    // 记录boss已被击败，之后不再生成
+    commands.trigger(SetWorldFlag(arena.victory_signal.clone()));
+    commands.entity(trigger.boss).remove::<ActiveBoss>();
+    play_music(&music, &asset_server, AMBIENT_MUSIC);
+}
+
+pub struct ArenaPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for ArenaPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, arena_reset_system.run_if(in_state(self.state.clone())));
+        app.add_observer(boss_defeated_observer);
+    }
+}
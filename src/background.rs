@@ -62,10 +62,23 @@ fn setup_bg(mut commands: Commands, asset_server: Res<AssetServer>) {
     ));
 }
 
+/// 背景音乐音轨，与音效分开以便单独切换
+#[derive(Resource)]
+pub struct MusicChannel;
+
+/// 场景默认背景音乐
+pub const AMBIENT_MUSIC: &str = "Audio/Music/mp3/Dark Ambient 3.mp3";
+
+/// 切换背景音乐
+pub fn play_music(music: &AudioChannel<MusicChannel>, asset_server: &AssetServer, path: &str) {
+    music.stop();
+    music.play(asset_server.load(path.to_string())).looped();
+}
+
 fn play_audio(
     asset_server: Res<AssetServer>, 
-    audio: Res<Audio>) {
-    audio.play(asset_server.load("Audio/Music/mp3/Dark Ambient 3.mp3")).looped();
+    music: Res<AudioChannel<MusicChannel>>) {
+    play_music(&music, &asset_server, AMBIENT_MUSIC);
 }
 pub struct BackgroundPlugin<S: States> {
     pub state: S,
@@ -73,6 +86,7 @@ pub struct BackgroundPlugin<S: States> {
 
 impl<S: States> Plugin for BackgroundPlugin<S> {
     fn build(&self, app: &mut App) {
+        app.add_audio_channel::<MusicChannel>();
         app.add_systems(OnEnter(self.state.clone()), (
             setup_bg.run_if(in_state(self.state.clone())),
             play_audio.run_if(in_state(self.state.clone()))
@@ -1,6 +1,7 @@
 use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
 
-use crate::{items::{ActiveItems, ItemBag, ItemList, ItemType}, player::Player, PausedState};
+use crate::{controls::{ControlSettings, InputDevice, MenuAction}, items::{ActiveItems, ItemBag, ItemList, ItemType}, player::Player, PausedState};
 
 #[derive(Component)]
 #[relationship(relationship_target = HasItemSlot)]
@@ -367,16 +368,17 @@ fn spawn_inventory_ui(
 
 // 处理键盘输入的系统
 fn handle_inventory_input(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    menu_input: Res<ActionState<MenuAction>>,
     actives: Single<(&ItemBag, &mut ActiveItems), With<Player>>,
     scroll_query: Single<(&mut ItemGridPanel, &Children)>,
     mut slot_query: Query<(&mut Visibility, &HasItemSlot), With<ItemPage>>,
     mut next_state: ResMut<NextState<PausedState>>,
     mut slots: Query<&mut BackgroundColor, With<ItemSlot>>,
+    item_list: Res<ItemList>,
 ) {
     let (mut grid, childs) = scroll_query.into_inner();
     if let Some(gridmax) = grid.max {
-        if keyboard_input.just_pressed(KeyCode::ArrowUp) {  
+        if menu_input.just_pressed(&MenuAction::Up) {  
             let updated = up(grid.current);
             let cur_page = get_page(grid.current);
             let updated_page = get_page(updated);
@@ -397,7 +399,7 @@ fn handle_inventory_input(
                 *next_visibility = Visibility::Visible;
             }
         }
-        if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        if menu_input.just_pressed(&MenuAction::Down) {
             let updated = down(grid.current, gridmax);
             let cur_page = get_page(grid.current);
             let updated_page = get_page(updated);
@@ -418,7 +420,7 @@ fn handle_inventory_input(
                 *next_visibility = Visibility::Visible;
             }
         }
-        if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        if menu_input.just_pressed(&MenuAction::Left) {
             let updated = left(grid.current);
             let cur_page = get_page(grid.current);
             let updated_page = get_page(updated);
@@ -439,7 +441,7 @@ fn handle_inventory_input(
                 *next_visibility = Visibility::Visible;
             }
         }
-        if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        if menu_input.just_pressed(&MenuAction::Right) {
             let updated = right(grid.current, gridmax);
             let cur_page = get_page(grid.current);
             let updated_page = get_page(updated);
@@ -460,19 +462,21 @@ fn handle_inventory_input(
                 *next_visibility = Visibility::Visible;
             }
         }
-        if keyboard_input.just_pressed(KeyCode::Enter) {
+        if menu_input.just_pressed(&MenuAction::Confirm) {
             let (bag, mut acts) = actives.into_inner();
             let key = bag.slots.keys().nth(grid.current).unwrap();
+            // 货币不能装备
+            let is_currency = matches!(item_list.infos.get(key).unwrap().item_type, ItemType::Currency);
             if acts.items.contains(key) {
                 if let Some(pos) = acts.items.iter().position(|x| *x == *key) {
                     acts.items.remove(pos);
                 }
-            } else {
+            } else if !is_currency {
                 acts.items.push(key.clone());
             }
         }
     }
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    if menu_input.just_pressed(&MenuAction::Back) {
         next_state.set(PausedState::Running);
     }
 }
@@ -483,6 +487,8 @@ fn update_item_info_display(
     grid_panel: Single<&ItemGridPanel>,
     item_bag: Single<&ItemBag, With<Player>>,
     item_list: Res<ItemList>,
+    settings: Res<ControlSettings>,
+    device: Res<InputDevice>,
     mut name_query: Query<&mut Text, With<ItemInfoName>>,
     mut desc_query: Query<&mut Text, (With<ItemInfoDesc>, Without<ItemInfoName>)>,
     mut type_query: Query<&mut Text, (With<ItemInfoType>, Without<ItemInfoDesc>, Without<ItemInfoName>)>,
@@ -504,10 +510,11 @@ fn update_item_info_display(
     let mut ntext = num_query.single_mut().unwrap();
 
     name.0 = info.name.clone();
-    desc.0 = info.description.clone();
+    desc.0 = settings.prompt(&info.description, *device);
     itype.0 = match info.item_type {
         ItemType::Consumable(_) => "Consumable".to_string(),
         ItemType::Ability(_) => "Ability".to_string(),
+        ItemType::Currency => "Currency".to_string(),
     };
     ntext.0 = format!("{}/{}", num, info.max_stack); 
     for entity in (**icon).iter() {
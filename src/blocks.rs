@@ -1,9 +1,10 @@
 //! 生成场景Sensor，当玩家进入特定场景时作出反应
+//! 门放置在地图的对象层中，boss区域的前后门监听对应`BossArena`的信号
 
-use avian2d::prelude::{Collider, CollisionEventsEnabled, CollisionLayers, OnCollisionStart, RigidBody, Sensor};
+use avian2d::prelude::{Collider, CollisionEventsEnabled, CollisionLayers, OnCollisionStart, Sensor};
 use bevy::prelude::*;
 
-use crate::{game_layer::GameLayer, healthbar::Hint, hint::ItemHint, items::{item_canpick_observer, item_cantpick_observer, ItemBag, ItemList, NotpickedItems}, player::Player, AppState};
+use crate::{cutscene::PlayCutscene, game_layer::GameLayer, healthbar::Hint, items::{spawn_pickup, ItemBag, ItemList}, player::Player, world_flags::{item_flag, WorldFlags}, AppState};
 
 pub struct BlockPlugin<S: States> {
     pub state: S,
@@ -24,106 +25,16 @@ impl<S: States> Plugin for BlockPlugin<S> {
 #[derive(Component)]
 pub struct KingdomThrone;
 
-/// 恶魔boss区域感应器
-#[derive(Component)]
-pub struct FireDemonBlocks;
-
-/// 武师boss区域感应器
-#[derive(Component)]
-pub struct MartialBlocks;
-
-/// 初始门组件
-#[derive(Component)]
-pub struct InitialGate;
-
-/// 生成碰撞体和sensor
+/// 生成钥匙和sensor
 fn setup_blocks(
     mut commands: Commands,
-    asset_server: Res<AssetServer>, 
     items: Res<ItemList>,
+    flags: Res<WorldFlags>,
 ) {
-    let dbl_block: Handle<Image> = asset_server.load("Art/pixilart-drawing2.png");
-    let qua_block: Handle<Image> = asset_server.load("Art/pixilart-drawing4.png");
-    // 初始地点
-    commands.spawn((
-        Sprite {
-            image: dbl_block.clone(),
-            ..default()
-        },
-        RigidBody::Static,
-        Collider::rectangle(16., 32.),
-        CollisionLayers::new(
-            GameLayer::Ground,
-            [GameLayer::Default, GameLayer::Player, GameLayer::Enemy],
-        ),
-        Transform::from_xyz(155., 36.,0.),
-        InitialGate,
-    ));
-
-    // 生成初始门的钥匙
-    commands.spawn((
-        Sprite {
-            image: items.infos.get(&String::from("Key")).unwrap().icon.clone(),
-            ..default()
-        },
-        Collider::rectangle(40.0, 20.0),
-        Transform::from_xyz(165., 22.1, 0.0),
-        ItemHint,
-        NotpickedItems { id: "Key".to_string(), num: 1 },
-        Sensor,
-        CollisionEventsEnabled,
-        CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player])
-    )).observe(item_cantpick_observer).observe(item_canpick_observer);
-
-    // martial 后门
-    commands.spawn((
-        Sprite {
-            image: qua_block.clone(),
-            ..default()
-        },
-        RigidBody::Static,
-        Collider::rectangle(16., 64.),
-        CollisionLayers::new(
-            GameLayer::Ground,
-            [GameLayer::Default, GameLayer::Player, GameLayer::Enemy],
-        ),
-        Transform::from_xyz(619.5, 250.,0.),
-        MartialBlocks,
-    ));
-
-    // martial 感知器
-    commands.spawn((
-        Collider::rectangle(16., 80.),
-        CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player]),
-        Sensor,
-        CollisionEventsEnabled,
-        Transform::from_xyz(22., -140.,0.)
-    )).observe(martial_observer);
-
-    // demon 感知器
-    commands.spawn((
-        Collider::rectangle(16., 100.),
-        CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player]),
-        Sensor,
-        CollisionEventsEnabled,
-        Transform::from_xyz(1054.5, 46.,0.)
-    )).observe(fire_demon_observer);
-
-    // demon 后门
-    commands.spawn((
-        Sprite {
-            image: qua_block.clone(),
-            ..default()
-        },
-        RigidBody::Static,
-        Collider::rectangle(16., 64.),
-        CollisionLayers::new(
-            GameLayer::Ground,
-            [GameLayer::Default, GameLayer::Player, GameLayer::Enemy],
-        ),
-        FireDemonBlocks,
-        Transform::from_xyz(1547., 68.,0.)
-    ));
+    // 生成初始门的钥匙，拾取过则不再生成
+    if !flags.is_set(&item_flag("Key")) {
+        spawn_pickup(&mut commands, &items, "Key", 1, Vec3::new(165., 22.1, 0.0));
+    }
 
     // 最终王座感知器
     commands.spawn((
@@ -136,68 +47,12 @@ fn setup_blocks(
 
 }
 
-/// 如果进入boss区域，关闭大门
-fn fire_demon_observer(
-    trigger: Trigger<OnCollisionStart>,
-    asset_server: Res<AssetServer>, 
-    mut commands: Commands,
-    player: Single<Entity, With<Player>>
-) {
-    let entity = trigger.collider;
-    let tpl_block: Handle<Image> = asset_server.load("Art/pixilart-drawing3.png");
-    if entity.to_bits() != player.into_inner().to_bits() { return; }
-     // demon front
-    commands.spawn((
-        Sprite {
-            image: tpl_block.clone(),
-            ..default()
-        },
-        RigidBody::Static,
-        Collider::rectangle(16., 48.),
-        CollisionLayers::new(
-            GameLayer::Ground,
-            [GameLayer::Default, GameLayer::Player, GameLayer::Enemy],
-        ),
-        Transform::from_xyz(954.5, 46.,0.),
-        FireDemonBlocks,
-    ));
-    commands.entity(trigger.target()).despawn();
-}
-
-/// 如果进入boss区域，关闭大门
-fn martial_observer(
-    trigger: Trigger<OnCollisionStart>,
-    asset_server: Res<AssetServer>, 
-    mut commands: Commands,
-    player: Single<Entity, With<Player>>
-) {
-    let entity = trigger.collider;
-    let dbl_block: Handle<Image> = asset_server.load("Art/pixilart-drawing2.png");
-    if entity.to_bits() != player.into_inner().to_bits() { return; }
-     // demon front
-    commands.spawn((
-        Sprite {
-            image: dbl_block.clone(),
-            ..default()
-        },
-        RigidBody::Static,
-        Collider::rectangle(16., 32.),
-        CollisionLayers::new(
-            GameLayer::Ground,
-            [GameLayer::Default, GameLayer::Player, GameLayer::Enemy],
-        ),
-        Transform::from_xyz(-2., -140.,0.),
-        MartialBlocks
-    ));
-    commands.entity(trigger.target()).despawn();
-}
-
-/// 如果抵达王座，结束游戏播放字幕
+/// 如果抵达王座，播放结局过场动画，之后进入字幕
 fn end_game(
     _trigger: Trigger<OnCollisionStart>,
-    mut next_state: ResMut<NextState<AppState>>,
+    mut commands: Commands,
 ) {
-    next_state.set(AppState::Ending);
+    commands.trigger(PlayCutscene("throne".to_string()));
 }
 
 /// 如果获得卷轴道具，生成前往王座的提示
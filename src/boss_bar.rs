@@ -0,0 +1,248 @@
+//! Boss血条UI
+//! 战斗中的boss（带有`ActiveBoss`组件）在屏幕底部显示名字、血条、受伤拖尾、架势条和阶段标记，
+//! 多个boss同时战斗时依次向上排列。
+
+use bevy::prelude::*;
+
+use crate::{arena::ActiveBoss, damagable::Damagable};
+
+const BOSS_BAR_LEN: f32 = 600.;
+const BOSS_BAR_WID: f32 = 16.;
+const POSTURE_BAR_WID: f32 = 5.;
+/// 受伤后拖尾开始缩短前的延迟
+const TRAIL_DELAY: f32 = 0.6;
+/// 拖尾每秒缩短的百分比
+const TRAIL_SPEED: f32 = 40.;
+
+/// Boss标识组件
+#[derive(Component, Debug, Clone)]
+pub struct Boss {
+    /// 显示的名字
+    pub name: String,
+    /// 阶段分界，以剩余血量比例表示（如0.5表示半血进入下一阶段）
+    pub phase_thresholds: Vec<f32>,
+}
+
+impl Boss {
+    /// 新建boss标识
+    pub fn new(name: &str, phase_thresholds: Vec<f32>) -> Self {
+        Self {
+            name: name.to_string(),
+            phase_thresholds,
+        }
+    }
+}
+
+/// Boss血条容器
+#[derive(Component)]
+struct BossBarContainer;
+
+/// 单个boss的血条行
+#[derive(Component)]
+struct BossBarRow {
+    boss: Entity,
+}
+
+/// Boss血条
+#[derive(Component)]
+struct BossHealthBar {
+    boss: Entity,
+}
+
+/// Boss受伤拖尾，延迟后缩短到当前血量
+#[derive(Component)]
+struct BossTrailBar {
+    boss: Entity,
+    /// 当前长度百分比
+    length: f32,
+    /// 上次受伤后经过的时间
+    time_since_damage: f32,
+    /// 上一帧的血量百分比
+    last_health: f32,
+}
+
+/// Boss架势条
+#[derive(Component)]
+struct BossPostureBar {
+    boss: Entity,
+}
+
+/// 初始化boss血条容器
+fn spawn_boss_bar_container(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.),
+            bottom: Val::Percent(3.),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BossBarContainer,
+    ));
+}
+
+/// boss进入战斗，生成血条
+fn on_add_active_boss(
+    trigger: Trigger<OnAdd, ActiveBoss>,
+    mut commands: Commands,
+    bosses: Query<&Boss>,
+    container: Single<Entity, With<BossBarContainer>>,
+    asset_server: Res<AssetServer>,
+) {
+    let boss = trigger.target();
+    let Ok(info) = bosses.get(boss) else { return; };
+    let font = TextFont {
+        font: asset_server.load("UI/Fonts/m5x7.ttf"),
+        font_size: 30.0,
+        ..default()
+    };
+    let row = commands.spawn((
+        Node {
+            flex_direction: FlexDirection::Column,
+            margin: UiRect::top(Val::Px(6.)),
+            ..default()
+        },
+        BossBarRow { boss },
+        ChildOf(container.into_inner()),
+    )).id();
+    commands.spawn((Text::new(info.name.clone()), font, Label, ChildOf(row)));
+    let bar_bg = commands.spawn((
+        Node {
+            width: Val::Px(BOSS_BAR_LEN),
+            height: Val::Px(BOSS_BAR_WID),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.3, 0.3, 0.3)),
+        ChildOf(row),
+    )).id();
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.9, 0.8, 0.5)),
+        BossTrailBar { boss, length: 100., time_since_damage: 0., last_health: 100. },
+        ChildOf(bar_bg),
+    ));
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.67, 0., 0.)),
+        BossHealthBar { boss },
+        ChildOf(bar_bg),
+    ));
+    // 阶段标记
+    for threshold in &info.phase_thresholds {
+        commands.spawn((
+            Node {
+                width: Val::Px(2.),
+                height: Val::Percent(100.),
+                left: Val::Percent(threshold * 100.),
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.95, 0.95, 0.95)),
+            ChildOf(bar_bg),
+        ));
+    }
+    let posture_bg = commands.spawn((
+        Node {
+            width: Val::Px(BOSS_BAR_LEN),
+            height: Val::Px(POSTURE_BAR_WID),
+            margin: UiRect::top(Val::Px(2.)),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+        ChildOf(row),
+    )).id();
+    commands.spawn((
+        Node {
+            width: Val::Percent(0.),
+            height: Val::Percent(100.),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.85, 0.65, 0.1)),
+        BossPostureBar { boss },
+        ChildOf(posture_bg),
+    ));
+}
+
+/// boss离开战斗或死亡，移除血条
+fn on_remove_active_boss(
+    trigger: Trigger<OnRemove, ActiveBoss>,
+    mut commands: Commands,
+    rows: Query<(Entity, &BossBarRow)>,
+) {
+    for (entity, row) in &rows {
+        if row.boss == trigger.target() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// 更新boss血量与拖尾
+fn update_boss_bars(
+    time: Res<Time>,
+    mut bars: Query<(&mut Node, &BossHealthBar), Without<BossTrailBar>>,
+    mut trails: Query<(&mut Node, &mut BossTrailBar), Without<BossHealthBar>>,
+    bosses: Query<&Damagable, With<ActiveBoss>>,
+) {
+    for (mut node, bar) in &mut bars {
+        let Ok(damagable) = bosses.get(bar.boss) else { continue; };
+        node.width = Val::Percent((damagable.health / damagable.max_health * 100.).max(0.));
+    }
+    for (mut node, mut trail) in &mut trails {
+        let Ok(damagable) = bosses.get(trail.boss) else { continue; };
+        let target = (damagable.health / damagable.max_health * 100.).max(0.);
+        if trail.length <= target {
+            // 回血或重置时拖尾直接跟上
+            trail.length = target;
+            trail.time_since_damage = 0.;
+        } else {
+            if target < trail.last_health {
+                // 连续受伤时重新计算延迟
+                trail.time_since_damage = 0.;
+            }
+            trail.time_since_damage += time.delta_secs();
+            if trail.time_since_damage > TRAIL_DELAY {
+                trail.length = (trail.length - TRAIL_SPEED * time.delta_secs()).max(target);
+            }
+        }
+        trail.last_health = target;
+        node.width = Val::Percent(trail.length);
+    }
+}
+
+/// 更新boss架势条
+fn update_boss_posture(
+    mut bars: Query<(&mut Node, &BossPostureBar)>,
+    bosses: Query<&Damagable, With<ActiveBoss>>,
+) {
+    for (mut node, bar) in &mut bars {
+        let Ok(damagable) = bosses.get(bar.boss) else { continue; };
+        node.width = Val::Percent((damagable.posture / damagable.max_posture * 100.).clamp(0., 100.));
+    }
+}
+
+pub struct BossBarPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for BossBarPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(self.state.clone()), spawn_boss_bar_container);
+        app.add_systems(Update, (
+            update_boss_bars.run_if(in_state(self.state.clone())),
+            update_boss_posture.run_if(in_state(self.state.clone())),
+        ));
+        app.add_observer(on_add_active_boss);
+        app.add_observer(on_remove_active_boss);
+    }
+}
@@ -1,4 +1,7 @@
 //! 相机系统实现
+//! 相机跟随玩家，并向移动方向预看一段距离；视野被限制在玩家所在房间（Tiled中的`CameraBounds`对象）内，
+//! 没有房间时限制在整张地图内。Boss战中相机同时框住玩家与boss，必要时拉远。
+//! 切换房间或进出boss战时，边界与缩放都平滑过渡。
 
 use bevy::{
     prelude::*,
@@ -11,13 +14,28 @@ use bevy::{
     ecs::system::ParamSet
 };
 
+use avian2d::prelude::LinearVelocity;
+
+use crate::arena::ActiveBoss;
 use crate::background;
+use crate::gravity::GravityDirection;
+use crate::level::LevelObjects;
 use crate::player;
 
 #[derive(Component, Reflect)]
 struct InGameCamera;
 
 const PIXEL_PERFECT_LAYERS: RenderLayers = RenderLayers::layer(0);
+/// 默认缩放
+const BASE_SCALE: f32 = 0.3;
+/// Boss战中允许拉远到的最大缩放
+const MAX_LOCK_ON_SCALE: f32 = 0.45;
+/// Boss战中玩家与boss到画面边缘的留白
+const LOCK_ON_MARGIN: f32 = 60.0;
+/// 预看的最大距离
+const MAX_LOOK_AHEAD: f32 = 40.0;
+/// 预看偏移的平滑速度
+const LOOK_AHEAD_SMOOTHING: f32 = 3.0;
 
 /// 初始化相机
 fn setup_camera(mut commands: Commands) {
@@ -34,23 +52,61 @@ fn setup_camera(mut commands: Commands) {
             scaling_mode: ScalingMode::FixedVertical {
                 viewport_height: 800.0,
             },
-            scale: 0.3,
+            scale: BASE_SCALE,
             ..OrthographicProjection::default_2d()
         }),
         CameraFollow {
             dead_zone_width: 0.0,
             soft_zone_width: 0.8,
             damping: 1.0,
-            look_ahead_time: 0.0,
-            screen_offset: Vec2::new(0.0, 0.0),
+            look_ahead_time: 0.3,
+            // 玩家略低于画面中心，多看到一些上方
+            screen_offset: Vec2::new(0.0, -0.05),
             axis_constraints: [false, false, true],
             constraint_values: Vec3::ZERO,
             blend_factor: 0.1,
             previous_target_position: Vec3::ZERO,
+            look_ahead_offset: Vec3::ZERO,
+            transition_speed: 4.0,
+            current_bounds: None,
         },
     ));
 }
 
+/// 相机房间：进入房间后视野限制在房间内
+#[derive(Resource, Default)]
+pub struct CameraRooms {
+    /// 各房间的范围
+    pub rooms: Vec<Rect>,
+    /// 整张地图的范围，不在任何房间内时使用
+    pub map: Rect,
+}
+
+impl CameraRooms {
+    /// 包含该点的最小房间，没有时返回整张地图
+    pub fn bounds_at(&self, point: Vec2) -> Option<Rect> {
+        self.rooms
+            .iter()
+            .filter(|room| room.contains(point))
+            .min_by(|a, b| a.size().length_squared().total_cmp(&b.size().length_squared()))
+            .copied()
+            .or((!self.map.is_empty()).then_some(self.map))
+    }
+}
+
+/// 读取地图中的相机房间
+fn load_camera_rooms(mut commands: Commands, level: Res<LevelObjects>) {
+    let rooms = level
+        .with_class("CameraBounds")
+        .map(|obj| Rect::from_center_size(obj.position, obj.size))
+        .collect();
+    commands.insert_resource(CameraRooms { rooms, map: level.bounds });
+}
+
+/// 相机由过场动画控制，暂停跟随，见`cutscene`
+#[derive(Component)]
+pub struct CameraScripted;
+
 /// 相机跟随组件
 #[derive(Component, Reflect)]
 pub struct CameraFollow {
@@ -63,6 +119,12 @@ pub struct CameraFollow {
     pub constraint_values: Vec3,
     pub blend_factor: f32,
     pub previous_target_position: Vec3,
+    /// 平滑后的预看偏移
+    pub look_ahead_offset: Vec3,
+    /// 切换房间、进出boss战时边界与缩放的过渡速度
+    pub transition_speed: f32,
+    /// 当前（过渡中）的视野边界
+    pub current_bounds: Option<Rect>,
 }
 
 /// 世界坐标到屏幕坐标转换
@@ -141,30 +203,83 @@ fn apply_axis_constraints(position: Vec3, constraints: [bool; 3], constraint_val
     result
 }
 
+/// 边界平滑过渡
+fn lerp_rect(from: Rect, to: Rect, t: f32) -> Rect {
+    Rect {
+        min: from.min.lerp(to.min, t),
+        max: from.max.lerp(to.max, t),
+    }
+}
+
+/// 把视野中心限制在边界内，边界比视野小时居中
+fn clamp_to_bounds(center: Vec3, half_size: Vec2, bounds: Rect) -> Vec3 {
+    let clamp_axis = |value: f32, min: f32, max: f32, half: f32| {
+        if max - min <= half * 2.0 {
+            (min + max) / 2.0
+        } else {
+            value.clamp(min + half, max - half)
+        }
+    };
+    Vec3::new(
+        clamp_axis(center.x, bounds.min.x, bounds.max.x, half_size.x),
+        clamp_axis(center.y, bounds.min.y, bounds.max.y, half_size.y),
+        center.z,
+    )
+}
+
 /// 相机跟随系统
 fn camera_follow_system(
     time: Res<Time>,
-    player_query: Query<&Transform, With<player::Player>>,
-    mut cameras: Query<(&mut Transform, &Camera, &GlobalTransform, &mut CameraFollow), Without<player::Player>>,
+    rooms: Res<CameraRooms>,
+    player_query: Query<(&Transform, &LinearVelocity, &GravityDirection), With<player::Player>>,
+    bosses: Query<&Transform, (With<ActiveBoss>, Without<player::Player>, Without<CameraFollow>)>,
+    mut cameras: Query<
+        (&mut Transform, &mut Projection, &Camera, &GlobalTransform, &mut CameraFollow),
+        (Without<player::Player>, Without<CameraScripted>),
+    >,
 ) {
     let delta_time = time.delta_secs();
 
-    for (mut cam_transform, camera, cam_global_transform, mut follow) in cameras.iter_mut() {
-        let Ok(target_transform) = player_query.single() else {
+    for (mut cam_transform, mut projection, camera, cam_global_transform, mut follow) in cameras.iter_mut() {
+        let Ok((target_transform, velocity, gravity_direction)) = player_query.single() else {
+            continue;
+        };
+        let Projection::Orthographic(ortho) = projection.as_mut() else {
             continue;
         };
+        // 切换房间、进出boss战的过渡系数
+        let transition = 1.0 - (-follow.transition_speed * delta_time).exp();
 
-        // 计算目标速度
-        let current_target_pos = target_transform.translation;
-        let target_velocity = (current_target_pos - follow.previous_target_position) / delta_time;
+        // Boss战中跟随玩家与boss的中点，拉远直到都在画面内
+        let player_pos = target_transform.translation;
+        let mut current_target_pos = player_pos;
+        let mut target_scale = BASE_SCALE;
+        if !bosses.is_empty() && !ortho.area.is_empty() {
+            let (mut min, mut max) = (player_pos.truncate(), player_pos.truncate());
+            for boss in &bosses {
+                min = min.min(boss.translation.truncate());
+                max = max.max(boss.translation.truncate());
+            }
+            current_target_pos = ((min + max) / 2.0).extend(player_pos.z);
+            let span = max - min + Vec2::splat(LOCK_ON_MARGIN * 2.0);
+            let view_per_scale = ortho.area.size() / ortho.scale;
+            target_scale = (span.x / view_per_scale.x)
+                .max(span.y / view_per_scale.y)
+                .clamp(BASE_SCALE, MAX_LOCK_ON_SCALE);
+        }
+        ortho.scale += (target_scale - ortho.scale) * transition;
         follow.previous_target_position = current_target_pos;
 
-        // 预测目标位置
-        let predicted_pos = compute_predicted_position(
-            current_target_pos,
-            target_velocity,
+        // 向移动方向预看，平滑后转身时画面不会跳动
+        let look_ahead = compute_predicted_position(
+            Vec3::ZERO,
+            Vec3::new(velocity.x, 0.0, 0.0),
             follow.look_ahead_time,
-        );
+        )
+        .clamp_length_max(MAX_LOOK_AHEAD);
+        let smoothing = 1.0 - (-LOOK_AHEAD_SMOOTHING * delta_time).exp();
+        follow.look_ahead_offset = follow.look_ahead_offset.lerp(look_ahead, smoothing);
+        let predicted_pos = current_target_pos + follow.look_ahead_offset;
 
         // 死区检查
         if (predicted_pos - cam_transform.translation).length() < follow.dead_zone_width {
@@ -186,7 +301,9 @@ fn camera_follow_system(
             None => continue,
         };
 
-        let offset_screen_pos = screen_pos + follow.screen_offset * camera.logical_viewport_size().unwrap();
+        // 重力反转时上下偏移随之翻转
+        let screen_offset = follow.screen_offset * Vec2::new(1.0, gravity_direction.sign());
+        let offset_screen_pos = screen_pos + screen_offset * camera.logical_viewport_size().unwrap();
         let world_pos = match screen_to_world(
             camera,
             cam_global_transform,
@@ -198,12 +315,22 @@ fn camera_follow_system(
         };
 
         // 应用轴向约束
-        let constrained_pos = apply_axis_constraints(
+        let mut constrained_pos = apply_axis_constraints(
             world_pos,
             follow.axis_constraints,
             follow.constraint_values,
         );
 
+        // 限制在玩家所在的房间内，切换房间时边界平滑过渡
+        if let Some(target_bounds) = rooms.bounds_at(player_pos.truncate()) {
+            let bounds = match follow.current_bounds {
+                Some(bounds) => lerp_rect(bounds, target_bounds, transition),
+                None => target_bounds,
+            };
+            follow.current_bounds = Some(bounds);
+            constrained_pos = clamp_to_bounds(constrained_pos, ortho.area.size() / 2.0, bounds);
+        }
+
         // 混合位置
         cam_transform.translation = cam_transform.translation.lerp(
             constrained_pos,
@@ -240,7 +367,9 @@ pub struct CameraPlugin<S: States> {
 
 impl<S: States> Plugin for CameraPlugin<S> {
     fn build(&self, app: &mut App) {
+        app.init_resource::<CameraRooms>();
         app.add_systems(Startup, setup_camera);
+        app.add_systems(OnEnter(self.state.clone()), load_camera_rooms);
         app.add_systems(Update, update_parallax_effect.run_if(in_state(self.state.clone())));
         app.add_systems(FixedUpdate, camera_follow_system.run_if(in_state(self.state.clone())));
     }
@@ -0,0 +1,94 @@
+//! 存档点
+//! 玩家在存档点按交互键休息：回满血量、保存进度，并让按休息重生的敌人重新出现。
+//! 死亡后从最后一次休息的存档点复活。存档点在Tiled中用`Checkpoint`类的对象放置。
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    controls::Prompt, damagable::Damagable, game_layer::GameLayer, healthbar::Hint, input::Action, level::LevelObjects,
+    locale::Localization, player::Player, save::SaveRequest,
+};
+
+/// 存档点
+#[derive(Component, Debug, Default)]
+pub struct Checkpoint {
+    /// 玩家是否在交互范围内
+    pub in_range: bool,
+}
+
+/// 玩家在存档点休息后触发
+#[derive(Event)]
+pub struct Rested;
+
+/// 存档点的颜色
+fn checkpoint_color(in_range: bool) -> Color {
+    if in_range { Color::srgb(1., 0.7, 0.3) } else { Color::srgb(0.8, 0.4, 0.1) }
+}
+
+/// 生成存档点
+fn spawn_checkpoints(mut commands: Commands, level: Res<LevelObjects>) {
+    for position in level.with_class("Checkpoint").map(|obj| obj.position) {
+        commands.spawn((
+            Checkpoint::default(),
+            Sprite::from_color(checkpoint_color(false), Vec2::new(6., 14.)),
+            Transform::from_translation(position.extend(-0.1)),
+            Collider::rectangle(24., 24.),
+            Sensor,
+            CollisionEventsEnabled,
+            CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player]),
+        )).observe(enter_checkpoint_observer).observe(exit_checkpoint_observer);
+    }
+}
+
+/// 玩家进入存档点范围
+fn enter_checkpoint_observer(
+    trigger: Trigger<OnCollisionStart>,
+    mut checkpoints: Query<(&mut Checkpoint, &mut Sprite)>,
+    mut prompt: Single<&mut Prompt, With<Hint>>,
+    locale: Res<Localization>,
+) {
+    let (mut checkpoint, mut sprite) = checkpoints.get_mut(trigger.target()).unwrap();
+    checkpoint.in_range = true;
+    sprite.color = checkpoint_color(true);
+    prompt.set(locale.tr("ui.rest"));
+}
+
+/// 玩家离开存档点范围
+fn exit_checkpoint_observer(
+    trigger: Trigger<OnCollisionEnd>,
+    mut checkpoints: Query<(&mut Checkpoint, &mut Sprite)>,
+    mut prompt: Single<&mut Prompt, With<Hint>>,
+) {
+    let Ok((mut checkpoint, mut sprite)) = checkpoints.get_mut(trigger.target()) else { return; };
+    checkpoint.in_range = false;
+    sprite.color = checkpoint_color(false);
+    prompt.set("");
+}
+
+/// 在存档点休息
+fn rest_system(
+    mut commands: Commands,
+    mut save: EventWriter<SaveRequest>,
+    player: Single<(&ActionState<Action>, &mut Damagable), With<Player>>,
+    checkpoints: Query<&Checkpoint>,
+) {
+    let (action_state, mut damagable) = player.into_inner();
+    if !action_state.just_pressed(&Action::PickItem) || !damagable.is_alive { return; }
+    if !checkpoints.iter().any(|checkpoint| checkpoint.in_range) { return; }
+    damagable.set_health(damagable.max_health);
+    save.write(SaveRequest::default());
+    commands.trigger(Rested);
+}
+
+pub struct CheckpointPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for CheckpointPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(self.state.clone()), spawn_checkpoints);
+        app.add_systems(Update, rest_system.run_if(in_state(self.state.clone())));
+    }
+}
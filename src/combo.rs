@@ -0,0 +1,316 @@
+//! 攻击连段
+//! 按姿态（地面、空中、下蹲）定义连段，每一段的伤害、击退、判定框与时间都在这里配置。
+//! 玩家动画状态机中的攻击状态由连段数据生成：段与段之间靠攻击键衔接，
+//! 取消窗口打开后可以用滑行或防守取消，连段的最后一段是击退更远的终结技。
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use game_derive::{enter, exit};
+
+use crate::animator::*;
+use crate::damagable::{check_hitbox, HasHitbox, HitBox, HitboxOf, Knockback};
+use crate::game_layer::GameLayer;
+use crate::player::{AnimationType, Player};
+
+/// 出招时的音效
+const SWING_AUDIO: &str = "Audio/SFX/10_Battle_SFX/39_Block_03.wav";
+
+/// 连段中的一段
+#[derive(Debug, Clone, Copy)]
+pub struct ComboStep {
+    /// 出招状态名，前摇与收招状态名在其后加`Prep`与`End`
+    pub name: &'static str,
+    /// 前摇动画帧
+    pub prep: Option<(usize, usize)>,
+    /// 出招动画帧，判定框在此期间存在
+    pub frames: (usize, usize),
+    /// 收招动画帧
+    pub end: Option<(usize, usize)>,
+    /// 伤害
+    pub damage: f32,
+    /// 击退距离
+    pub knockback: f32,
+    /// 判定框大小
+    pub hitbox: Vec2,
+    /// 判定框相对玩家的位置
+    pub offset: Vec2,
+    /// 出招状态进行到该归一化时间后可以被取消
+    pub cancel_after: f32,
+    /// 该段最后一个状态进行到该归一化时间后接受下一段的输入
+    pub chain_after: f32,
+}
+
+/// 连段中的状态阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Prep,
+    Active,
+    End,
+}
+
+impl ComboStep {
+    /// 该段依次经过的状态
+    fn phases(&self) -> Vec<(String, (usize, usize), Phase)> {
+        let mut phases = vec![];
+        if let Some(prep) = self.prep {
+            phases.push((format!("{}Prep", self.name), prep, Phase::Prep));
+        }
+        phases.push((self.name.to_string(), self.frames, Phase::Active));
+        if let Some(end) = self.end {
+            phases.push((format!("{}End", self.name), end, Phase::End));
+        }
+        phases
+    }
+
+    /// 该段的第一个状态名
+    fn first_state(&self) -> String {
+        self.phases()[0].0.clone()
+    }
+}
+
+/// 一个姿态（地面、空中、下蹲）的连段
+#[derive(Debug, Clone, Copy)]
+pub struct ComboChain {
+    pub steps: &'static [ComboStep],
+    /// 连段结束后回到的状态
+    pub exit: &'static str,
+    /// 可以取消进入的状态及所需的trigger
+    pub cancels: &'static [(&'static str, &'static str)],
+}
+
+/// 地面连段：两段斩击接终结技
+pub const GROUND_COMBO: ComboChain = ComboChain {
+    steps: &[
+        ComboStep {
+            name: "Attack1",
+            prep: Some(AnimationType::Attack1Prep.config_index()),
+            frames: AnimationType::Attack1.config_index(),
+            end: Some(AnimationType::Attack1End.config_index()),
+            damage: 200.,
+            knockback: 50.,
+            hitbox: Vec2::new(30., 10.),
+            offset: Vec2::new(10., 0.),
+            cancel_after: 0.5,
+            chain_after: 0.6,
+        },
+        ComboStep {
+            name: "Attack2",
+            prep: Some(AnimationType::Attack2Prep.config_index()),
+            frames: AnimationType::Attack2.config_index(),
+            end: Some(AnimationType::Attack2End.config_index()),
+            damage: 240.,
+            knockback: 60.,
+            hitbox: Vec2::new(30., 12.),
+            offset: Vec2::new(10., 0.),
+            cancel_after: 0.5,
+            chain_after: 0.6,
+        },
+        ComboStep {
+            name: "Attack3",
+            prep: None,
+            frames: AnimationType::Attack3.config_index(),
+            end: None,
+            damage: 360.,
+            knockback: 180.,
+            hitbox: Vec2::new(36., 16.),
+            offset: Vec2::new(12., 0.),
+            cancel_after: 0.8,
+            chain_after: 1.0,
+        },
+    ],
+    exit: "Idle",
+    cancels: &[("slide", "Slide"), ("defense", "Defense")],
+};
+
+/// 空中连段：两段空斩接下劈终结技
+pub const AIR_COMBO: ComboChain = ComboChain {
+    steps: &[
+        ComboStep {
+            name: "AirAttack1",
+            prep: None,
+            frames: AnimationType::AirAttack1.config_index(),
+            end: None,
+            damage: 160.,
+            knockback: 40.,
+            hitbox: Vec2::new(30., 14.),
+            offset: Vec2::new(10., 0.),
+            cancel_after: 0.5,
+            chain_after: 0.6,
+        },
+        ComboStep {
+            name: "AirAttack2",
+            prep: None,
+            frames: AnimationType::AirAttack2.config_index(),
+            end: None,
+            damage: 200.,
+            knockback: 50.,
+            hitbox: Vec2::new(30., 14.),
+            offset: Vec2::new(10., 0.),
+            cancel_after: 0.5,
+            chain_after: 0.6,
+        },
+        ComboStep {
+            name: "AirAttack3",
+            prep: Some(AnimationType::AirAttack3Rdy.config_index()),
+            frames: AnimationType::AirAttack3Loop.config_index(),
+            end: Some(AnimationType::AirAttack3End.config_index()),
+            damage: 300.,
+            knockback: 150.,
+            hitbox: Vec2::new(20., 24.),
+            offset: Vec2::new(6., -8.),
+            cancel_after: 1.0,
+            chain_after: 1.0,
+        },
+    ],
+    exit: "Fall",
+    cancels: &[("defense", "Defense")],
+};
+
+/// 下蹲连段：扫腿接飞踢终结技
+pub const CROUCH_COMBO: ComboChain = ComboChain {
+    steps: &[
+        ComboStep {
+            name: "CrouchKick",
+            prep: None,
+            frames: AnimationType::Kick.config_index(),
+            end: None,
+            damage: 150.,
+            knockback: 30.,
+            hitbox: Vec2::new(28., 8.),
+            offset: Vec2::new(10., -6.),
+            cancel_after: 0.4,
+            chain_after: 0.7,
+        },
+        ComboStep {
+            name: "CrouchDropKick",
+            prep: None,
+            frames: AnimationType::DropKick.config_index(),
+            end: None,
+            damage: 260.,
+            knockback: 160.,
+            hitbox: Vec2::new(30., 10.),
+            offset: Vec2::new(12., -4.),
+            cancel_after: 0.8,
+            chain_after: 1.0,
+        },
+    ],
+    exit: "Crouch",
+    cancels: &[("slide", "Slide"), ("defense", "Defense")],
+};
+
+/// 所有连段
+pub const COMBOS: [ComboChain; 3] = [GROUND_COMBO, AIR_COMBO, CROUCH_COMBO];
+
+/// 以trigger为条件的转换
+fn trigger_transition(trigger: &str, target: &str, exit_time: f32) -> Transition {
+    Transition {
+        conditions: vec![Condition {
+            param_name: trigger.to_string(),
+            operator: ConditionOperator::Equals,
+            value: AnimatorParam::Trigger(true),
+        }],
+        target_state: target.to_string(),
+        has_exit_time: exit_time > 0.,
+        exit_time,
+    }
+}
+
+/// 动画播放完后的转换
+fn timed_transition(target: &str, exit_time: f32) -> Transition {
+    Transition {
+        conditions: vec![],
+        target_state: target.to_string(),
+        has_exit_time: true,
+        exit_time,
+    }
+}
+
+impl ComboChain {
+    /// 从其他状态按攻击键进入连段的转换
+    pub fn entry_transition(&self) -> Transition {
+        trigger_transition("attack", &self.steps[0].first_state(), 0.)
+    }
+
+    /// 生成连段的所有动画状态
+    pub fn states(&self) -> Vec<AnimationState> {
+        let mut states = vec![];
+        for (index, step) in self.steps.iter().enumerate() {
+            let next = self.steps.get(index + 1).map(|next| next.first_state());
+            let phases = step.phases();
+            for (i, (name, frames, phase)) in phases.iter().enumerate() {
+                let mut transitions = vec![];
+                let last = i == phases.len() - 1;
+                if let (true, Some(next)) = (last, &next) {
+                    transitions.push(trigger_transition("attack", next, step.chain_after));
+                }
+                // 前摇不能取消，收招随时可以取消
+                if *phase != Phase::Prep {
+                    let cancel_after = if *phase == Phase::Active { step.cancel_after } else { 0. };
+                    for (trigger, target) in self.cancels {
+                        transitions.push(trigger_transition(trigger, target, cancel_after));
+                    }
+                }
+                if last {
+                    // 留出一点时间接受下一段的输入
+                    let exit_time = if next.is_some() { 1.1 } else { 1.0 };
+                    transitions.push(timed_transition(self.exit, exit_time));
+                } else {
+                    transitions.push(timed_transition(&phases[i + 1].0, 1.0));
+                }
+                states.push(AnimationState {
+                    name: name.clone(),
+                    first_index: frames.0,
+                    last_index: frames.1,
+                    transitions,
+                    loop_animation: false,
+                    on_enter: Some(__combo_enter_handler),
+                    on_exit: Some(__combo_exit_handler),
+                    audio_path: (*phase == Phase::Active).then(|| SWING_AUDIO.to_string()),
+                });
+            }
+        }
+        states
+    }
+}
+
+/// 出招状态对应的连段段落
+fn active_step(state: &str) -> Option<&'static ComboStep> {
+    COMBOS.iter().flat_map(|chain| chain.steps.iter()).find(|step| step.name == state)
+}
+
+/// 进入连段状态：禁止移动，出招状态生成判定框
+#[enter("combo")]
+pub fn on_combo_enter(
+    mut commands: Commands,
+    mut player: Query<&mut Animator, With<Player>>,
+) {
+    let entity = trigger.entity;
+    let Ok(mut animator) = player.get_mut(entity) else { return; };
+    animator.set_bool("can_move", false);
+    let Some(step) = active_step(animator.current_state()) else { return; };
+    commands.spawn((
+        Collider::rectangle(step.hitbox.x, step.hitbox.y),
+        Transform::from_translation(step.offset.extend(0.)),
+        Sensor,
+        HitBox { damage: step.damage },
+        Knockback(step.knockback),
+        CollisionLayers::new(GameLayer::PlayerHitBox, [GameLayer::Enemy, GameLayer::Interactive]),
+        CollisionEventsEnabled,
+        ChildOf(entity),
+        HitboxOf(entity),
+    )).observe(check_hitbox);
+}
+
+/// 退出连段状态：恢复移动，删除判定框
+#[exit("combo")]
+pub fn on_combo_exit(
+    mut commands: Commands,
+    mut player: Query<(&mut Animator, Option<&HasHitbox>), With<Player>>,
+) {
+    let entity = trigger.entity;
+    let Ok((mut animator, hitboxes)) = player.get_mut(entity) else { return; };
+    animator.set_bool("can_move", true);
+    for hitbox in hitboxes.map(|h| (**h).clone()).unwrap_or_default() {
+        commands.entity(hitbox).despawn();
+    }
+}
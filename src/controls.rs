@@ -0,0 +1,375 @@
+//! 按键设置
+//! 玩家动作的键盘与手柄键位保存在`controls.ron`中，启动时读取，改键后写回。
+//! 菜单类界面使用独立的`MenuAction`，不受玩家改键影响，但同样通过leafwing读取。
+//! 同时记录玩家最近使用的输入设备，屏幕提示据此显示键盘或手柄的按键。
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+
+use bevy::{prelude::*, scene::ron};
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::input::Action;
+use crate::player::Player;
+
+/// 按键设置文件
+const CONTROLS_PATH: &str = "controls.ron";
+
+/// 摇杆推动超过该值时视为使用手柄
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// 一个按键绑定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+impl Binding {
+    /// 显示用的按键名
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{:?}", key);
+                name.strip_prefix("Key")
+                    .or(name.strip_prefix("Digit"))
+                    .unwrap_or(&name)
+                    .to_string()
+            }
+            Binding::Mouse(button) => format!("Mouse {:?}", button),
+            Binding::Gamepad(button) => match button {
+                GamepadButton::South => "A",
+                GamepadButton::East => "B",
+                GamepadButton::West => "X",
+                GamepadButton::North => "Y",
+                GamepadButton::LeftTrigger => "LB",
+                GamepadButton::RightTrigger => "RB",
+                GamepadButton::LeftTrigger2 => "LT",
+                GamepadButton::RightTrigger2 => "RT",
+                GamepadButton::LeftThumb => "L3",
+                GamepadButton::RightThumb => "R3",
+                GamepadButton::DPadUp => "D-Up",
+                GamepadButton::DPadDown => "D-Down",
+                GamepadButton::DPadLeft => "D-Left",
+                GamepadButton::DPadRight => "D-Right",
+                GamepadButton::Start => "Start",
+                GamepadButton::Select => "Select",
+                _ => "?",
+            }.to_string(),
+        }
+    }
+
+    /// 屏幕提示中的按键图示：键盘为`[E]`，手柄为`(A)`
+    pub fn glyph(&self) -> String {
+        match self {
+            Binding::Gamepad(_) => format!("({})", self.label()),
+            _ => format!("[{}]", self.label()),
+        }
+    }
+
+    /// 是否是手柄按键
+    pub fn is_gamepad(&self) -> bool {
+        matches!(self, Binding::Gamepad(_))
+    }
+}
+
+/// 玩家最近使用的输入设备
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputDevice {
+    #[default]
+    Keyboard,
+    Gamepad,
+}
+
+/// 带按键占位符的提示文字，如`Jump: {Jump}`，按当前设备渲染到同一实体的`Text`
+#[derive(Component, Debug, Default)]
+pub struct Prompt {
+    template: String,
+    /// 上一次渲染出的文字
+    rendered: String,
+}
+
+impl Prompt {
+    pub fn new(template: &str) -> Self {
+        Self { template: template.to_string(), rendered: String::new() }
+    }
+
+    /// 设置提示模板，空字符串表示清除提示
+    pub fn set(&mut self, template: &str) {
+        self.template = template.to_string();
+    }
+}
+
+/// 菜单界面的动作
+#[derive(Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect, Serialize, Deserialize)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+    /// 打开暂停菜单
+    Pause,
+}
+
+impl MenuAction {
+    /// 所有菜单动作
+    pub const ALL: [Self; 7] = [
+        MenuAction::Up,
+        MenuAction::Down,
+        MenuAction::Left,
+        MenuAction::Right,
+        MenuAction::Confirm,
+        MenuAction::Back,
+        MenuAction::Pause,
+    ];
+
+    fn default_input_map() -> InputMap<MenuAction> {
+        let mut input_map = InputMap::default();
+        input_map.insert(MenuAction::Up, KeyCode::ArrowUp);
+        input_map.insert(MenuAction::Down, KeyCode::ArrowDown);
+        input_map.insert(MenuAction::Left, KeyCode::ArrowLeft);
+        input_map.insert(MenuAction::Right, KeyCode::ArrowRight);
+        input_map.insert(MenuAction::Confirm, KeyCode::Enter);
+        input_map.insert(MenuAction::Back, KeyCode::Escape);
+        input_map.insert(MenuAction::Pause, KeyCode::Escape);
+        // 手柄十字键与左摇杆
+        input_map.insert(MenuAction::Up, GamepadButton::DPadUp);
+        input_map.insert(MenuAction::Down, GamepadButton::DPadDown);
+        input_map.insert(MenuAction::Left, GamepadButton::DPadLeft);
+        input_map.insert(MenuAction::Right, GamepadButton::DPadRight);
+        input_map.insert(MenuAction::Up, GamepadControlDirection::LEFT_UP);
+        input_map.insert(MenuAction::Down, GamepadControlDirection::LEFT_DOWN);
+        input_map.insert(MenuAction::Left, GamepadControlDirection::LEFT_LEFT);
+        input_map.insert(MenuAction::Right, GamepadControlDirection::LEFT_RIGHT);
+        input_map.insert(MenuAction::Confirm, GamepadButton::South);
+        input_map.insert(MenuAction::Back, GamepadButton::East);
+        input_map.insert(MenuAction::Pause, GamepadButton::Start);
+        input_map
+    }
+}
+
+/// 玩家动作的键位设置
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct ControlSettings {
+    /// 键盘与鼠标键位
+    pub bindings: HashMap<Action, Binding>,
+    /// 手柄键位
+    #[serde(default)]
+    pub gamepad: HashMap<Action, Binding>,
+}
+
+impl Default for ControlSettings {
+    fn default() -> Self {
+        let bindings = HashMap::from([
+            (Action::Up, Binding::Key(KeyCode::KeyW)),
+            (Action::Down, Binding::Key(KeyCode::KeyS)),
+            (Action::Left, Binding::Key(KeyCode::KeyA)),
+            (Action::Right, Binding::Key(KeyCode::KeyD)),
+            (Action::Jump, Binding::Key(KeyCode::Space)),
+            (Action::Run, Binding::Key(KeyCode::ShiftLeft)),
+            (Action::Crouch, Binding::Key(KeyCode::KeyC)),
+            (Action::Attack, Binding::Mouse(MouseButton::Left)),
+            (Action::Defense, Binding::Mouse(MouseButton::Right)),
+            (Action::UseItem, Binding::Key(KeyCode::KeyR)),
+            (Action::PickItem, Binding::Key(KeyCode::KeyE)),
+            (Action::ReverseGravity, Binding::Key(KeyCode::KeyG)),
+            (Action::ChangeItem, Binding::Key(KeyCode::Tab)),
+        ]);
+        let gamepad = HashMap::from([
+            (Action::Up, Binding::Gamepad(GamepadButton::DPadUp)),
+            (Action::Down, Binding::Gamepad(GamepadButton::DPadDown)),
+            (Action::Left, Binding::Gamepad(GamepadButton::DPadLeft)),
+            (Action::Right, Binding::Gamepad(GamepadButton::DPadRight)),
+            (Action::Jump, Binding::Gamepad(GamepadButton::South)),
+            (Action::Run, Binding::Gamepad(GamepadButton::East)),
+            (Action::Crouch, Binding::Gamepad(GamepadButton::LeftThumb)),
+            (Action::Attack, Binding::Gamepad(GamepadButton::RightTrigger2)),
+            (Action::Defense, Binding::Gamepad(GamepadButton::LeftTrigger2)),
+            (Action::UseItem, Binding::Gamepad(GamepadButton::West)),
+            (Action::PickItem, Binding::Gamepad(GamepadButton::North)),
+            (Action::ReverseGravity, Binding::Gamepad(GamepadButton::RightTrigger)),
+            (Action::ChangeItem, Binding::Gamepad(GamepadButton::LeftTrigger)),
+        ]);
+        Self { bindings, gamepad }
+    }
+}
+
+impl ControlSettings {
+    /// 获取动作在该设备上的按键
+    pub fn get(&self, action: Action, device: InputDevice) -> Option<Binding> {
+        match device {
+            InputDevice::Keyboard => self.bindings.get(&action).copied(),
+            InputDevice::Gamepad => self.gamepad.get(&action).copied(),
+        }
+    }
+
+    /// 按键所属设备的键位表
+    fn map_of(&mut self, binding: Binding) -> &mut HashMap<Action, Binding> {
+        if binding.is_gamepad() { &mut self.gamepad } else { &mut self.bindings }
+    }
+
+    /// 同一设备上使用该按键的其他动作
+    pub fn conflict(&self, action: Action, binding: Binding) -> Option<Action> {
+        let map = if binding.is_gamepad() { &self.gamepad } else { &self.bindings };
+        map.iter()
+            .find(|(other, b)| **other != action && **b == binding)
+            .map(|(other, _)| *other)
+    }
+
+    /// 改键，与其他动作冲突时交换两者的按键，返回被交换的动作
+    pub fn rebind(&mut self, action: Action, binding: Binding) -> Option<Action> {
+        let conflict = self.conflict(action, binding);
+        let map = self.map_of(binding);
+        let old = map.insert(action, binding);
+        if let (Some(other), Some(old)) = (conflict, old) {
+            map.insert(other, old);
+        }
+        conflict
+    }
+
+    /// 把模板中的`{动作名}`替换为该设备上的按键图示，如`Jump: {Jump}`
+    pub fn prompt(&self, template: &str, device: InputDevice) -> String {
+        let glyph = |action| self.get(action, device).map(|b| b.glyph()).unwrap_or("[-]".to_string());
+        let mut text = template.to_string();
+        for action in Action::ALL {
+            let pattern = format!("{{{:?}}}", action);
+            if text.contains(&pattern) {
+                text = text.replace(&pattern, &glyph(action));
+            }
+        }
+        // 移动提示使用上左下右四个动作当前的按键
+        let movement = [Action::Up, Action::Left, Action::Down, Action::Right].map(glyph).join(" ");
+        let pause = match device {
+            InputDevice::Keyboard => "[Esc]",
+            InputDevice::Gamepad => "(Start)",
+        };
+        text.replace("{Move}", &movement).replace("{Pause}", pause)
+    }
+
+    /// 生成玩家的键位表，手柄左摇杆始终用于移动
+    pub fn input_map(&self) -> InputMap<Action> {
+        let mut input_map = InputMap::default();
+        for (action, binding) in self.bindings.iter().chain(&self.gamepad) {
+            match *binding {
+                Binding::Key(key) => input_map.insert(*action, key),
+                Binding::Mouse(button) => input_map.insert(*action, button),
+                Binding::Gamepad(button) => input_map.insert(*action, button),
+            };
+        }
+        input_map.insert_dual_axis(Action::Move, GamepadStick::LEFT);
+        input_map
+    }
+
+    /// 读取按键设置，没有设置文件或无法解析时使用默认键位
+    pub fn load() -> Self {
+        let mut contents = String::new();
+        let Ok(mut file) = File::open(CONTROLS_PATH) else { return Self::default(); };
+        if file.read_to_string(&mut contents).is_err() {
+            println!("Could not read {}", CONTROLS_PATH);
+            return Self::default();
+        }
+        match ron::from_str::<ControlSettings>(&contents) {
+            Ok(mut settings) => {
+                // 补全新增的动作
+                let default = Self::default();
+                for (action, binding) in default.bindings {
+                    settings.bindings.entry(action).or_insert(binding);
+                }
+                for (action, binding) in default.gamepad {
+                    settings.gamepad.entry(action).or_insert(binding);
+                }
+                settings
+            }
+            Err(e) => {
+                println!("Failed to parse {}: {}", CONTROLS_PATH, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// 保存按键设置
+    pub fn save(&self) {
+        let config = ron::ser::PrettyConfig::default();
+        let ron_string = ron::ser::to_string_pretty(self, config).unwrap();
+        let mut file = match File::create(CONTROLS_PATH) {
+            Ok(file) => file,
+            Err(e) => {
+                println!("Could not create {}: {}", CONTROLS_PATH, e);
+                return;
+            }
+        };
+        if let Err(e) = file.write_all(ron_string.as_bytes()) {
+            println!("Could not write {}: {}", CONTROLS_PATH, e);
+        }
+    }
+}
+
+/// 按键设置修改后更新玩家的键位表
+fn apply_control_settings(
+    settings: Res<ControlSettings>,
+    mut players: Query<&mut InputMap<Action>, With<Player>>,
+) {
+    if !settings.is_changed() { return; }
+    for mut input_map in &mut players {
+        *input_map = settings.input_map();
+    }
+}
+
+/// 根据最近的输入切换当前设备
+fn detect_input_device(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut device: ResMut<InputDevice>,
+) {
+    let gamepad_used = gamepads.iter().any(|gamepad| {
+        gamepad.get_just_pressed().next().is_some() || gamepad.left_stick().length() > STICK_THRESHOLD
+    });
+    let next = if gamepad_used {
+        InputDevice::Gamepad
+    } else if keys.get_just_pressed().next().is_some() || mouse.get_just_pressed().next().is_some() {
+        InputDevice::Keyboard
+    } else {
+        return;
+    };
+    // 只在设备变化时修改，避免每帧触发提示刷新
+    if *device != next {
+        *device = next;
+    }
+}
+
+/// 渲染提示文字，设备或键位改变时重新渲染
+fn render_prompts(
+    device: Res<InputDevice>,
+    settings: Res<ControlSettings>,
+    mut prompts: Query<(&mut Prompt, &mut Text)>,
+) {
+    let refresh = device.is_changed() || settings.is_changed();
+    for (mut prompt, mut text) in &mut prompts {
+        // 文字被其他系统直接改写时，设备切换不覆盖它
+        if !prompt.is_changed() && !(refresh && text.0 == prompt.rendered) {
+            continue;
+        }
+        text.0 = settings.prompt(&prompt.template, *device);
+        prompt.bypass_change_detection().rendered = text.0.clone();
+    }
+}
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ControlSettings::load());
+        app.add_plugins(InputManagerPlugin::<MenuAction>::default());
+        app.init_resource::<ActionState<MenuAction>>();
+        app.insert_resource(MenuAction::default_input_map());
+        app.init_resource::<InputDevice>();
+        app.add_systems(Update, (apply_control_settings, detect_input_device));
+        app.add_systems(PostUpdate, render_prompts);
+    }
+}
@@ -0,0 +1,169 @@
+//! 按键设置界面
+//! 上下选择动作，确认后按下新的按键完成改键；与其他动作冲突时交换两者的按键。
+//! 每一行同时显示键盘与手柄的按键，按下哪种设备的按键就修改哪种设备的键位。
+//! 每次改键后立即写入设置文件。
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::controls::{Binding, ControlSettings, InputDevice, MenuAction};
+use crate::input::Action;
+use crate::PausedState;
+
+/// 恢复默认键位选项的序号
+const RESET_INDEX: usize = Action::ALL.len();
+
+/// 按键设置界面
+#[derive(Component)]
+pub struct ControlsUI {
+    /// 当前选中的行
+    pub current: usize,
+    /// 是否正在等待新的按键
+    pub waiting: bool,
+}
+
+/// 设置界面中的一行
+#[derive(Component)]
+pub struct ControlRow(pub usize);
+
+/// 提示信息
+#[derive(Component)]
+pub struct ControlMessage;
+
+/// 生成按键设置界面
+fn spawn_controls_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = TextFont {
+        font: asset_server.load("UI/Fonts/m5x7.ttf"),
+        font_size: 40.0,
+        ..default()
+    };
+    let ui_entity = commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0., 0., 0.).with_alpha(0.8)),
+        ControlsUI { current: 0, waiting: false },
+    )).id();
+    for index in 0..=RESET_INDEX {
+        let row = commands.spawn((Text::new(""), font.clone(), Label, ControlRow(index))).id();
+        commands.entity(ui_entity).add_child(row);
+    }
+    let message = commands.spawn((
+        Text::new("[Enter]/(A) Rebind    [Esc]/(B) Back"),
+        font.clone(),
+        Label,
+        ControlMessage,
+        Node { margin: UiRect::top(Val::Px(20.)), ..default() },
+    )).id();
+    commands.entity(ui_entity).add_child(message);
+}
+
+/// 处理设置界面输入
+fn handle_controls_input(
+    menu_input: Res<ActionState<MenuAction>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut settings: ResMut<ControlSettings>,
+    ui: Single<&mut ControlsUI>,
+    mut message: Single<&mut Text, With<ControlMessage>>,
+    mut next_state: ResMut<NextState<PausedState>>,
+) {
+    let mut ui = ui.into_inner();
+    if ui.waiting {
+        // Esc与手柄的Select用于取消，不能被绑定
+        let cancel = keys.just_pressed(KeyCode::Escape)
+            || gamepads.iter().any(|gamepad| gamepad.just_pressed(GamepadButton::Select));
+        if cancel {
+            ui.waiting = false;
+            message.0 = "Cancelled".to_string();
+            return;
+        }
+        let binding = keys
+            .get_just_pressed()
+            .next()
+            .map(|key| Binding::Key(*key))
+            .or(mouse.get_just_pressed().next().map(|button| Binding::Mouse(*button)))
+            .or(gamepads
+                .iter()
+                .find_map(|gamepad| gamepad.get_just_pressed().next().map(|button| Binding::Gamepad(*button))));
+        let Some(binding) = binding else { return; };
+        let action = Action::ALL[ui.current];
+        message.0 = match settings.rebind(action, binding) {
+            Some(other) => format!("{} was used by {:?}, swapped", binding.label(), other),
+            None => format!("{:?}: {}", action, binding.label()),
+        };
+        settings.save();
+        ui.waiting = false;
+        return;
+    }
+
+    if menu_input.just_pressed(&MenuAction::Up) {
+        ui.current = ui.current.saturating_sub(1);
+    } else if menu_input.just_pressed(&MenuAction::Down) {
+        ui.current = (ui.current + 1).min(RESET_INDEX);
+    } else if menu_input.just_pressed(&MenuAction::Confirm) {
+        if ui.current == RESET_INDEX {
+            *settings = ControlSettings::default();
+            settings.save();
+            message.0 = "Default controls restored".to_string();
+        } else {
+            ui.waiting = true;
+            message.0 = format!("Press a key or button for {:?}  [Esc]/(Select) Cancel", Action::ALL[ui.current]);
+        }
+    } else if menu_input.just_pressed(&MenuAction::Back) {
+        next_state.set(PausedState::Paused);
+    }
+}
+
+/// 更新每一行的显示
+fn update_control_rows(
+    settings: Res<ControlSettings>,
+    ui: Single<&ControlsUI>,
+    mut rows: Query<(&ControlRow, &mut Text, &mut TextColor)>,
+) {
+    let ui = ui.into_inner();
+    for (row, mut text, mut color) in &mut rows {
+        let label = if row.0 == RESET_INDEX {
+            "Reset Defaults".to_string()
+        } else {
+            let action = Action::ALL[row.0];
+            if ui.waiting && ui.current == row.0 {
+                format!("{:?}: ...", action)
+            } else {
+                let label = |device| settings.get(action, device).map(|b| b.label()).unwrap_or("-".to_string());
+                format!("{:?}: {} / {}", action, label(InputDevice::Keyboard), label(InputDevice::Gamepad))
+            }
+        };
+        if row.0 == ui.current {
+            text.0 = format!("[ {} ]", label);
+            color.0 = Color::srgb(1., 0.85, 0.4);
+        } else {
+            text.0 = label;
+            color.0 = Color::WHITE;
+        }
+    }
+}
+
+/// 离开设置界面
+fn despawn_controls_ui(mut commands: Commands, ui: Single<Entity, With<ControlsUI>>) {
+    commands.entity(ui.into_inner()).despawn();
+}
+
+pub struct ControlsUIPlugin;
+
+impl Plugin for ControlsUIPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(PausedState::Controls), spawn_controls_ui);
+        app.add_systems(OnExit(PausedState::Controls), despawn_controls_ui);
+        app.add_systems(Update, (
+            handle_controls_input,
+            update_control_rows,
+        ).chain().run_if(in_state(PausedState::Controls)));
+    }
+}
@@ -0,0 +1,470 @@
+//! 过场动画
+//! 过场动画以资源文件（`*.cutscene.ron`）描述：每一段是按开始时间排列的步骤，
+//! 可以移动相机、移动角色、触发动画、显示对话框、淡入淡出以及进入结局。
+//! 播放期间相机由过场动画控制，可选地锁定玩家输入。
+//! 过场动画由地图中的`CutsceneTrigger`感知器、boss被击败（以区域的胜利信号名索引）或代码中的`PlayCutscene`触发。
+
+use std::collections::HashMap;
+
+use avian2d::prelude::*;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::{prelude::*, scene::ron};
+use bevy_tnua::prelude::*;
+use serde::Deserialize;
+
+use crate::animator::Animator;
+use crate::arena::{ArenaBoss, BossArena, BossDefeated};
+use crate::camera::{CameraFollow, CameraScripted};
+use crate::dialogue::DialogueUi;
+use crate::game_layer::GameLayer;
+use crate::input::InputLocked;
+use crate::level::LevelObjects;
+use crate::player::Player;
+use crate::world_flags::{cutscene_flag, SetWorldFlag, WorldFlags};
+use crate::AppState;
+
+/// 过场动画资源路径
+const CUTSCENES: &str = "Cutscenes/story.cutscene.ron";
+/// 角色移动的走路速度上限
+const MAX_WALK_SPEED: f32 = 120.;
+
+fn default_true() -> bool {
+    true
+}
+
+/// 过场动画中的角色
+#[derive(Debug, Clone, Deserialize)]
+pub enum ActorRef {
+    Player,
+    /// 带有同名`CutsceneActor`的实体
+    Named(String),
+}
+
+/// 可被过场动画引用的角色名
+#[derive(Component, Debug, Clone)]
+pub struct CutsceneActor(pub String);
+
+/// 过场动画中的动作
+#[derive(Debug, Clone, Deserialize)]
+pub enum CutsceneAction {
+    /// 相机平移到指定位置
+    CameraPan { to: (f32, f32), duration: f32 },
+    /// 相机回到跟随玩家
+    CameraRelease,
+    /// 角色移动到指定位置，有控制器的角色走过去
+    MoveActor { actor: ActorRef, to: (f32, f32), duration: f32 },
+    /// 设置角色的动画trigger
+    Animate { actor: ActorRef, trigger: String },
+    /// 显示对话框，说话人与文字为本地化键名，文字支持按键占位符
+    Dialogue { speaker: Option<String>, text: String, duration: f32 },
+    /// 画面淡入淡出，`to`为黑幕的不透明度
+    Fade { to: f32, duration: f32 },
+    /// 进入结局
+    Ending,
+}
+
+impl CutsceneAction {
+    /// 动作持续的时间
+    fn duration(&self) -> f32 {
+        match self {
+            CutsceneAction::CameraPan { duration, .. }
+            | CutsceneAction::MoveActor { duration, .. }
+            | CutsceneAction::Dialogue { duration, .. }
+            | CutsceneAction::Fade { duration, .. } => *duration,
+            _ => 0.,
+        }
+    }
+}
+
+/// 时间轴上的一步
+#[derive(Debug, Clone, Deserialize)]
+pub struct CutsceneStep {
+    /// 开始时间
+    pub at: f32,
+    pub action: CutsceneAction,
+}
+
+/// 一段过场动画
+#[derive(Debug, Clone, Deserialize)]
+pub struct Cutscene {
+    /// 播放期间锁定玩家输入
+    #[serde(default = "default_true")]
+    pub lock_input: bool,
+    /// 只播放一次，播放过的记录在世界标记中
+    #[serde(default = "default_true")]
+    pub once: bool,
+    pub steps: Vec<CutsceneStep>,
+}
+
+impl Cutscene {
+    /// 总时长
+    fn length(&self) -> f32 {
+        self.steps
+            .iter()
+            .map(|step| step.at + step.action.duration())
+            .fold(0., f32::max)
+    }
+}
+
+/// 所有过场动画，以名字索引
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct CutsceneSet(pub HashMap<String, Cutscene>);
+
+/// 读取`*.cutscene.ron`
+#[derive(Default)]
+struct CutsceneLoader;
+
+impl AssetLoader for CutsceneLoader {
+    type Asset = CutsceneSet;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["cutscene.ron"]
+    }
+}
+
+/// 已加载的过场动画
+#[derive(Resource)]
+pub struct Cutscenes(pub Handle<CutsceneSet>);
+
+/// 播放过场动画触发器
+#[derive(Event, Debug, Clone)]
+pub struct PlayCutscene(pub String);
+
+/// 步骤的播放状态
+#[derive(Debug, Clone, Copy)]
+enum StepState {
+    Pending,
+    /// 进行中，记录开始时的位置或不透明度
+    Running(Vec3),
+    Done,
+}
+
+/// 正在播放的过场动画
+struct Playing {
+    cutscene: Cutscene,
+    elapsed: f32,
+    steps: Vec<StepState>,
+}
+
+/// 过场动画播放器
+#[derive(Resource, Default)]
+pub struct CutscenePlayer {
+    playing: Option<Playing>,
+    /// 等待播放的过场动画
+    queue: Vec<Cutscene>,
+}
+
+/// 淡入淡出的黑幕
+#[derive(Component)]
+struct FadeOverlay;
+
+/// 地图中触发过场动画的感知器
+#[derive(Component)]
+struct CutsceneTrigger(String);
+
+/// 生成黑幕
+fn setup_fade_overlay(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.)),
+        FadeOverlay,
+        GlobalZIndex(10),
+    ));
+}
+
+fn load_cutscenes(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Cutscenes(asset_server.load(CUTSCENES)));
+}
+
+/// 生成地图中的过场动画感知器
+fn spawn_cutscene_triggers(mut commands: Commands, level: Res<LevelObjects>, flags: Res<WorldFlags>) {
+    for obj in level.with_class("CutsceneTrigger") {
+        let Some(name) = obj.get_string("cutscene") else {
+            println!("CutsceneTrigger {} has no cutscene", obj.id);
+            continue;
+        };
+        if flags.is_set(&cutscene_flag(&name)) { continue; }
+        commands.spawn((
+            CutsceneTrigger(name),
+            Collider::rectangle(obj.size.x, obj.size.y),
+            CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player]),
+            Sensor,
+            CollisionEventsEnabled,
+            Transform::from_translation(obj.position.extend(0.)),
+        )).observe(cutscene_trigger_observer);
+    }
+}
+
+/// 玩家进入过场动画感知器
+fn cutscene_trigger_observer(
+    trigger: Trigger<OnCollisionStart>,
+    mut commands: Commands,
+    triggers: Query<&CutsceneTrigger>,
+) {
+    let name = triggers.get(trigger.target()).unwrap().0.clone();
+    commands.trigger(PlayCutscene(name));
+    commands.entity(trigger.target()).despawn();
+}
+
+/// boss被击败时播放以区域胜利信号命名的过场动画
+fn boss_defeated_observer(
+    trigger: Trigger<BossDefeated>,
+    mut commands: Commands,
+    bosses: Query<&ArenaBoss>,
+    arenas: Query<&BossArena>,
+    cutscenes: Res<Cutscenes>,
+    sets: Res<Assets<CutsceneSet>>,
+) {
+    let Ok(arena_boss) = bosses.get(trigger.boss) else { return; };
+    let Ok(arena) = arenas.get(arena_boss.arena) else { return; };
+    let Some(set) = sets.get(&cutscenes.0) else { return; };
+    if set.0.contains_key(&arena.victory_signal) {
+        commands.trigger(PlayCutscene(arena.victory_signal.clone()));
+    }
+}
+
+/// 开始播放过场动画，正在播放时排队
+fn play_cutscene_observer(
+    trigger: Trigger<PlayCutscene>,
+    mut commands: Commands,
+    mut player: ResMut<CutscenePlayer>,
+    flags: Res<WorldFlags>,
+    cutscenes: Res<Cutscenes>,
+    sets: Res<Assets<CutsceneSet>>,
+) {
+    let name = &trigger.0;
+    let Some(cutscene) = sets.get(&cutscenes.0).and_then(|set| set.0.get(name)) else {
+        println!("Cutscene {} not found", name);
+        return;
+    };
+    if cutscene.once {
+        let flag = cutscene_flag(name);
+        if flags.is_set(&flag) { return; }
+        commands.trigger(SetWorldFlag(flag));
+    }
+    player.queue.push(cutscene.clone());
+}
+
+/// 角色是否是引用的角色
+fn is_actor(actor: &ActorRef, name: Option<&CutsceneActor>, is_player: bool) -> bool {
+    match actor {
+        ActorRef::Player => is_player,
+        ActorRef::Named(target) => name.is_some_and(|name| &name.0 == target),
+    }
+}
+
+/// 以水平速度走动，保留角色原有的走路参数，速度为零时停下
+fn walk(controller: &mut TnuaController, velocity: f32) {
+    let basis = controller
+        .concrete_basis::<TnuaBuiltinWalk>()
+        .map(|(basis, _)| basis.clone())
+        .unwrap_or_default();
+    controller.basis(TnuaBuiltinWalk {
+        desired_velocity: Vec3::new(velocity, 0., 0.),
+        ..basis
+    });
+}
+
+/// 推进过场动画的时间轴
+fn run_cutscene(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut player: ResMut<CutscenePlayer>,
+    mut next_state: ResMut<NextState<AppState>>,
+    camera: Single<(Entity, &mut Transform), With<CameraFollow>>,
+    mut actors: Query<
+        (
+            Entity,
+            &mut Transform,
+            Option<&CutsceneActor>,
+            Option<&mut TnuaController>,
+            Option<&mut Animator>,
+            Has<Player>,
+        ),
+        Without<CameraFollow>,
+    >,
+    mut dialogue: DialogueUi,
+    mut fade: Single<&mut BackgroundColor, With<FadeOverlay>>,
+) {
+    let (camera_entity, mut camera_transform) = camera.into_inner();
+    let player = player.as_mut();
+
+    // 开始队列中的下一段
+    if player.playing.is_none() {
+        if player.queue.is_empty() { return; }
+        let cutscene = player.queue.remove(0);
+        if cutscene.lock_input {
+            for (entity, .., is_player) in &actors {
+                if is_player {
+                    commands.entity(entity).insert(InputLocked);
+                }
+            }
+        }
+        let steps = vec![StepState::Pending; cutscene.steps.len()];
+        player.playing = Some(Playing { cutscene, elapsed: 0., steps });
+    }
+    let playing = player.playing.as_mut().unwrap();
+    playing.elapsed += time.delta_secs();
+
+    for (index, step) in playing.cutscene.steps.iter().enumerate() {
+        if playing.elapsed < step.at { continue; }
+        let state = &mut playing.steps[index];
+        let duration = step.action.duration();
+        let progress = if duration > 0. { ((playing.elapsed - step.at) / duration).min(1.) } else { 1. };
+
+        // 开始该步骤
+        if let StepState::Pending = state {
+            let from = match &step.action {
+                CutsceneAction::CameraPan { .. } => {
+                    commands.entity(camera_entity).insert(CameraScripted);
+                    camera_transform.translation
+                }
+                CutsceneAction::CameraRelease => {
+                    commands.entity(camera_entity).remove::<CameraScripted>();
+                    Vec3::ZERO
+                }
+                CutsceneAction::MoveActor { actor, .. } => actors
+                    .iter()
+                    .find(|(_, _, name, .., is_player)| is_actor(actor, *name, *is_player))
+                    .map_or(Vec3::ZERO, |(_, transform, ..)| transform.translation),
+                CutsceneAction::Animate { actor, trigger } => {
+                    for (_, _, name, _, animator, is_player) in &mut actors {
+                        if !is_actor(actor, name, is_player) { continue; }
+                        if let Some(mut animator) = animator {
+                            animator.set_trigger(trigger);
+                        }
+                    }
+                    Vec3::ZERO
+                }
+                CutsceneAction::Dialogue { speaker: name, text: line, .. } => {
+                    dialogue.show_line(name.as_deref(), line, None);
+                    Vec3::ZERO
+                }
+                CutsceneAction::Fade { .. } => Vec3::splat(fade.0.alpha()),
+                CutsceneAction::Ending => {
+                    next_state.set(AppState::Ending);
+                    Vec3::ZERO
+                }
+            };
+            *state = StepState::Running(from);
+        }
+
+        let StepState::Running(from) = *state else { continue; };
+        // 缓入缓出
+        let eased = progress * progress * (3. - 2. * progress);
+        match &step.action {
+            CutsceneAction::CameraPan { to, .. } => {
+                let to = Vec2::from(*to).extend(camera_transform.translation.z);
+                camera_transform.translation = from.lerp(to, eased);
+            }
+            CutsceneAction::MoveActor { actor, to, duration } => {
+                for (_, mut transform, name, controller, animator, is_player) in &mut actors {
+                    if !is_actor(actor, name, is_player) { continue; }
+                    let Some(mut controller) = controller else {
+                        // 没有控制器的角色直接插值
+                        let to = Vec2::from(*to).extend(transform.translation.z);
+                        transform.translation = from.lerp(to, eased);
+                        continue;
+                    };
+                    let velocity = if progress < 1. {
+                        ((to.0 - from.x) / duration).clamp(-MAX_WALK_SPEED, MAX_WALK_SPEED)
+                    } else {
+                        0.
+                    };
+                    walk(&mut controller, velocity);
+                    if let Some(mut animator) = animator {
+                        animator.set_bool("is_moving", velocity != 0.);
+                        // 转向移动方向
+                        let facing_right = animator.get_bool("is_facing_right");
+                        if velocity != 0. && (velocity > 0.) != facing_right {
+                            animator.set_bool("is_facing_right", velocity > 0.);
+                            transform.scale.x *= -1.;
+                        }
+                    }
+                }
+            }
+            CutsceneAction::Dialogue { text: line, .. } => {
+                if progress >= 1. && dialogue.line() == line.as_str() {
+                    dialogue.hide();
+                }
+            }
+            CutsceneAction::Fade { to, .. } => {
+                let alpha = from.x + (to - from.x) * progress;
+                fade.0 = Color::BLACK.with_alpha(alpha);
+            }
+            _ => {}
+        }
+        if progress >= 1. {
+            *state = StepState::Done;
+        }
+    }
+
+    // 锁定输入时玩家不动，除非正在被过场动画移动
+    if playing.cutscene.lock_input {
+        let moving_player = playing.cutscene.steps.iter().zip(&playing.steps).any(|(step, state)| {
+            matches!(
+                (&step.action, state),
+                (CutsceneAction::MoveActor { actor: ActorRef::Player, .. }, StepState::Running(_))
+            )
+        });
+        if !moving_player {
+            for (.., controller, animator, is_player) in &mut actors {
+                if !is_player { continue; }
+                if let Some(mut controller) = controller {
+                    walk(&mut controller, 0.);
+                }
+                if let Some(mut animator) = animator {
+                    animator.set_bool("is_moving", false);
+                }
+            }
+        }
+    }
+
+    // 播放结束，交还相机与输入
+    if playing.elapsed >= playing.cutscene.length() {
+        player.playing = None;
+        commands.entity(camera_entity).remove::<CameraScripted>();
+        for (entity, .., is_player) in &actors {
+            if is_player {
+                commands.entity(entity).remove::<InputLocked>();
+            }
+        }
+        dialogue.hide();
+    }
+}
+
+pub struct CutscenePlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for CutscenePlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<CutsceneSet>();
+        app.init_asset_loader::<CutsceneLoader>();
+        app.init_resource::<CutscenePlayer>();
+        app.add_systems(Startup, (load_cutscenes, setup_fade_overlay));
+        app.add_systems(OnEnter(self.state.clone()), spawn_cutscene_triggers);
+        app.add_systems(Update, run_cutscene.run_if(in_state(self.state.clone())));
+        app.add_observer(play_cutscene_observer);
+        app.add_observer(boss_defeated_observer);
+    }
+}
@@ -1,12 +1,15 @@
 //! 生命、受伤系统
 
-use crate::{animator::*, save::load};
+use crate::{animator::*, enemy::perception::Noise, save::load};
 use avian2d::prelude::*;
 use bevy::prelude::*;
 use bevy_kira_audio::{Audio, AudioControl};
 use bevy_tnua::{builtins::*, prelude::*};
 use serde::{Serialize, Deserialize};
 
+/// 弹反窗口
+const PARRY_WINDOW: f32 = 0.15;
+
 /// 生命、受伤组件
 #[derive(Component, Clone, Debug, Reflect, Serialize, Deserialize)]
 pub struct Damagable {
@@ -105,6 +108,11 @@ impl Damagable {
         self.time_since_defend = 0.;
     }
 
+    /// 是否处于弹反窗口（刚开始防守的一小段时间）
+    pub fn is_parrying(&self) -> bool {
+        self.is_defending && self.time_since_defend <= PARRY_WINDOW
+    }
+
     /// 受到攻击
     pub fn take_hit(&mut self, damage: f32) {
         if self.is_alive && !self.is_invincible {
@@ -114,7 +122,7 @@ impl Damagable {
                 self.set_posture(self.posture + damage / 3.);
                 self.set_invincible(true);
             }
-            if self.is_defending && self.time_since_defend > 0.15 {
+            if self.is_defending && !self.is_parrying() {
                 self.set_posture(self.posture + damage);
             }
         }
@@ -166,12 +174,21 @@ fn check_death(time: Res<Time>, mut query: Query<(&mut Animator, &mut Damagable,
             damagable.time_since_death += time.delta_secs();
         }
         if damagable.time_since_death > 5. {
-            let trans_data = load().unwrap();
+            // 还没有存档时回到起点
+            let saved = load();
+            let has_save = saved.is_some();
+            let trans_data = saved.unwrap_or_default();
             transform.translation.x = trans_data.translation[0];
             transform.translation.y = trans_data.translation[1];
             transform.scale.x = trans_data.scale[0];
             transform.scale.y = trans_data.scale[1];
-            animator.parameters = trans_data.params.clone();
+            if has_save {
+                animator.parameters = trans_data.params.clone();
+            } else {
+                // 默认存档没有动画参数，沿用当前的参数
+                animator.set_bool("is_alive", true);
+                animator.set_bool("can_move", true);
+            }
             animator.set_trigger("revival");
             damagable.copy(trans_data.damagable.clone());
         }
@@ -195,10 +212,15 @@ pub struct HitBox {
     pub damage: f32,
 }
 
+/// hitbox的击退距离，没有时使用默认距离
+#[derive(Component)]
+pub struct Knockback(pub f32);
+
 /// 检查受攻击
 pub fn check_hitbox(
     trigger: Trigger<OnCollisionStart>,
-    hitbox_query: Query<(&GlobalTransform, &HitBox)>,
+    mut commands: Commands,
+    hitbox_query: Query<(&GlobalTransform, &HitBox, Option<&Knockback>)>,
     mut damaged_query: Query<(
         &mut Damagable,
         &mut Animator,
@@ -209,18 +231,22 @@ pub fn check_hitbox(
 ) {
     let hitbox_entity = trigger.target();
     let damaged_entity = trigger.collider;
-    let (hitbox_trans, hitbox) = hitbox_query.get(hitbox_entity).unwrap();
-    let (mut damagable, mut animator, mut controller, damaged_trans) = damaged_query.get_mut(damaged_entity).unwrap();
+    let (hitbox_trans, hitbox, knockback) = hitbox_query.get(hitbox_entity).unwrap();
+    // 碰到的不是可受伤实体（如机关），交给对应的观察者处理
+    let Ok((mut damagable, mut animator, mut controller, damaged_trans)) = damaged_query.get_mut(damaged_entity) else {
+        return;
+    };
     let delta_x = damaged_trans.translation().x - hitbox_trans.translation().x;
     let dir = if delta_x >= 0. { 1. } else { -1. };
     if !damagable.is_invincible && !damagable.is_defending && damagable.is_alive {
         animator.set_trigger("hit");
         controller.action(TnuaBuiltinKnockback {
-            shove: Vec3::new(50., 0., 0.) * dir,
+            shove: Vec3::new(knockback.map_or(50., |k| k.0), 0., 0.) * dir,
             ..Default::default()
         });
         audio.play(asset_server.load(
             "Audio/SFX/12_Player_Movement_SFX/61_Hit_03.wav"));
+        commands.trigger(Noise::combat(hitbox_trans.translation().truncate()));
     } else if damagable.is_defending {
         controller.action(TnuaBuiltinKnockback {
             shove: Vec3::new(10., 0., 0.) * dir,
@@ -243,3 +269,36 @@ impl<S: States> Plugin for DamagePlugin<S> {
         ));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::save::set_save_path;
+
+    #[test]
+    fn revives_without_save_file() {
+        set_save_path("missing_test_save.ron");
+        let mut world = World::new();
+        world.init_resource::<Time>();
+
+        let mut animator = Animator::new();
+        animator.add_state(AnimationState { name: "Die".to_string(), ..default() });
+        animator.add_parameter("is_alive", AnimatorParam::Bool(false));
+        animator.add_parameter("can_move", AnimatorParam::Bool(false));
+        animator.add_parameter("revival", AnimatorParam::Trigger(false));
+        let mut damagable = Damagable::new(100.);
+        damagable.is_alive = false;
+        damagable.time_since_death = 6.;
+        let player = world.spawn((animator, damagable, Transform::default())).id();
+
+        world.run_system_once(check_death).unwrap();
+
+        let animator = world.get::<Animator>(player).unwrap();
+        assert!(animator.is_active("revival"));
+        assert!(animator.get_bool("is_alive"));
+        assert!(animator.get_bool("can_move"));
+        assert!(world.get::<Damagable>(player).unwrap().is_alive);
+    }
+}
@@ -0,0 +1,621 @@
+//! 对话系统
+//! NPC的对话以对话树资源（`*.dialogue.ron`）描述，按NPC的id索引。每棵树有若干入口，交谈时选择第一个条件满足的入口，
+//! 条件可以检查世界标记与背包中的道具。节点显示说话人、头像与逐字出现的文字，进入节点或选择选项时可以设置世界标记，
+//! 或通过`PickItemTrigger`给予道具。对话中的文字都是本地化键名。
+//! 玩家靠近NPC时按拾取键交谈，对话期间锁定输入。过场动画中的对话也使用这里的对话框。
+
+use std::collections::HashMap;
+
+use avian2d::prelude::*;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::ecs::system::SystemParam;
+use bevy::{prelude::*, scene::ron};
+use leafwing_input_manager::prelude::ActionState;
+use serde::Deserialize;
+
+use crate::controls::{ControlSettings, InputDevice, MenuAction, Prompt};
+use crate::cutscene::CutsceneActor;
+use crate::game_layer::GameLayer;
+use crate::healthbar::Hint;
+use crate::input::{Action, InputLocked};
+use crate::items::{ItemBag, PickItemTrigger};
+use crate::level::LevelObjects;
+use crate::locale::Localization;
+use crate::player::Player;
+use crate::world_flags::{SetWorldFlag, WorldFlags};
+
+/// 对话树资源路径
+const DIALOGUES: &str = "Dialogue/npcs.dialogue.ron";
+/// 逐字显示的速度（字/秒）
+const TYPE_SPEED: f32 = 40.;
+/// NPC精灵图
+const NPC_SHEET: &str = "Art/Knight/noBKG_KnightIdle_strip.png";
+/// NPC待机动画帧数
+const NPC_FRAMES: usize = 15;
+/// NPC待机动画每帧时长
+const NPC_FRAME_TIME: f32 = 0.1;
+
+/// 对话条件
+#[derive(Debug, Clone, Deserialize)]
+pub enum DialogueCondition {
+    /// 世界标记已设置
+    Flag(String),
+    /// 世界标记未设置
+    NotFlag(String),
+    /// 背包中至少有指定数量的道具
+    HasItem(String, u32),
+    /// 背包中没有该道具
+    LacksItem(String),
+}
+
+impl DialogueCondition {
+    fn check(&self, flags: &WorldFlags, bag: &ItemBag) -> bool {
+        match self {
+            DialogueCondition::Flag(flag) => flags.is_set(flag),
+            DialogueCondition::NotFlag(flag) => !flags.is_set(flag),
+            DialogueCondition::HasItem(item, num) => bag.get(item) >= *num,
+            DialogueCondition::LacksItem(item) => bag.get(item) == 0,
+        }
+    }
+}
+
+/// 条件是否全部满足
+fn all_met(conditions: &[DialogueCondition], flags: &WorldFlags, bag: &ItemBag) -> bool {
+    conditions.iter().all(|condition| condition.check(flags, bag))
+}
+
+/// 对话效果
+#[derive(Debug, Clone, Deserialize)]
+pub enum DialogueEffect {
+    /// 设置世界标记
+    SetFlag(String),
+    /// 给予玩家道具
+    GiveItem(String, u32),
+}
+
+/// 执行对话效果
+fn apply_effects(commands: &mut Commands, effects: &[DialogueEffect], player: Entity) {
+    for effect in effects {
+        match effect {
+            DialogueEffect::SetFlag(flag) => commands.trigger(SetWorldFlag(flag.clone())),
+            DialogueEffect::GiveItem(item, num) => commands.trigger(PickItemTrigger {
+                picker: player,
+                item: item.clone(),
+                num: *num,
+            }),
+        }
+    }
+}
+
+/// 对话选项
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogueChoice {
+    /// 选项文字
+    pub text: String,
+    /// 显示该选项的条件
+    #[serde(default)]
+    pub conditions: Vec<DialogueCondition>,
+    /// 选择后的效果
+    #[serde(default)]
+    pub effects: Vec<DialogueEffect>,
+    /// 下一个节点，没有时结束对话
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+/// 对话节点
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogueNode {
+    /// 说话人，没有时为NPC本人
+    #[serde(default)]
+    pub speaker: Option<String>,
+    pub text: String,
+    /// 进入节点时的效果
+    #[serde(default)]
+    pub effects: Vec<DialogueEffect>,
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+    /// 没有选项时的下一个节点，没有时结束对话
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+/// 对话树入口
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogueEntry {
+    #[serde(default)]
+    pub conditions: Vec<DialogueCondition>,
+    /// 起始节点
+    pub node: String,
+}
+
+/// 一个NPC的对话树
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogueTree {
+    /// 按顺序检查的入口
+    pub entries: Vec<DialogueEntry>,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+/// 所有对话树，以NPC的id索引
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct DialogueSet(pub HashMap<String, DialogueTree>);
+
+/// 读取`*.dialogue.ron`
+#[derive(Default)]
+struct DialogueLoader;
+
+impl AssetLoader for DialogueLoader {
+    type Asset = DialogueSet;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dialogue.ron"]
+    }
+}
+
+/// 已加载的对话树
+#[derive(Resource)]
+struct Dialogues(Handle<DialogueSet>);
+
+/// 可交谈的NPC
+#[derive(Component, Debug)]
+pub struct Npc {
+    /// 对话树id，同时是过场动画中的角色名
+    pub id: String,
+    portrait: ImageNode,
+}
+
+impl Npc {
+    /// NPC名字的本地化键名
+    fn name_key(&self) -> String {
+        format!("npc.{}.name", self.id)
+    }
+}
+
+/// NPC待机动画
+#[derive(Component)]
+struct NpcAnimation(Timer);
+
+/// 玩家身边可交谈的NPC
+#[derive(Component, Debug)]
+pub struct NearNpc(pub Entity);
+
+/// 对话框
+#[derive(Component)]
+pub struct DialogueBox;
+
+/// 对话框中的头像
+#[derive(Component)]
+pub struct DialoguePortrait;
+
+/// 对话框中的说话人
+#[derive(Component)]
+pub struct DialogueSpeaker;
+
+/// 对话框中的文字
+#[derive(Component)]
+pub struct DialogueText;
+
+/// 对话框中的选项列表
+#[derive(Component)]
+pub struct DialogueChoices;
+
+/// 对话框字体
+#[derive(Resource)]
+pub struct DialogueFont(TextFont);
+
+/// 逐字显示的文字，模板是本地化键名并支持按键占位符
+#[derive(Component, Debug, Default)]
+pub struct Typewriter {
+    template: String,
+    /// 已显示的字数
+    shown: f32,
+    /// 完整文字的字数
+    len: usize,
+}
+
+impl Typewriter {
+    /// 从头显示新的文字
+    pub fn start(&mut self, template: &str) {
+        self.template = template.to_string();
+        self.shown = 0.;
+        self.len = template.chars().count();
+    }
+
+    /// 当前文字的模板
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// 立即显示全部文字
+    pub fn finish(&mut self) {
+        self.shown = self.len as f32;
+    }
+
+    /// 文字是否已全部显示
+    pub fn is_finished(&self) -> bool {
+        self.shown >= self.len as f32
+    }
+}
+
+/// 对话框的界面，过场动画也通过它显示对话
+#[derive(SystemParam)]
+pub struct DialogueUi<'w, 's> {
+    commands: Commands<'w, 's>,
+    locale: Res<'w, Localization>,
+    font: Res<'w, DialogueFont>,
+    root: Single<'w, &'static mut Visibility, With<DialogueBox>>,
+    portrait: Single<'w, (&'static mut ImageNode, &'static mut Node), With<DialoguePortrait>>,
+    speaker: Single<'w, &'static mut Text, With<DialogueSpeaker>>,
+    text: Single<'w, &'static mut Typewriter, With<DialogueText>>,
+    choices: Single<'w, Entity, With<DialogueChoices>>,
+}
+
+impl DialogueUi<'_, '_> {
+    /// 显示一句对话，没有头像时隐藏头像
+    pub fn show_line(&mut self, speaker: Option<&str>, text: &str, portrait: Option<&ImageNode>) {
+        let speaker = speaker.map(|key| self.locale.tr(key).to_string()).unwrap_or_default();
+        self.speaker.0 = speaker;
+        self.text.start(text);
+        let (image, node) = &mut *self.portrait;
+        match portrait {
+            Some(portrait) => {
+                **image = portrait.clone();
+                node.display = Display::Flex;
+            }
+            None => node.display = Display::None,
+        }
+        self.show_choices(&[], 0);
+        **self.root = Visibility::Visible;
+    }
+
+    /// 当前显示的文字
+    pub fn line(&self) -> &str {
+        self.text.template()
+    }
+
+    /// 关闭对话框
+    pub fn hide(&mut self) {
+        **self.root = Visibility::Hidden;
+        self.text.start("");
+        self.show_choices(&[], 0);
+    }
+
+    /// 重新生成选项列表，选中的选项高亮
+    fn show_choices(&mut self, choices: &[String], selected: usize) {
+        let container = *self.choices;
+        self.commands.entity(container).despawn_related::<Children>();
+        for (index, key) in choices.iter().enumerate() {
+            let (marker, color) = if index == selected {
+                ("> ", Color::srgb(0.9, 0.75, 0.4))
+            } else {
+                ("  ", Color::WHITE)
+            };
+            self.commands.spawn((
+                Text::new(format!("{}{}", marker, self.locale.tr(key))),
+                self.font.0.clone(),
+                TextColor(color),
+                ChildOf(container),
+            ));
+        }
+    }
+}
+
+/// 正在进行的对话
+struct Conversation {
+    npc: Entity,
+    node: String,
+    /// 当前节点中满足条件的选项序号
+    choices: Vec<usize>,
+    selected: usize,
+    /// 开始交谈的那一帧，避免同一次按键直接跳过第一句
+    fresh: bool,
+}
+
+/// 对话状态
+#[derive(Resource, Default)]
+struct DialogueState {
+    conversation: Option<Conversation>,
+}
+
+/// 生成对话框
+fn setup_dialogue_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = TextFont {
+        font: asset_server.load("UI/Fonts/m5x7.ttf"),
+        font_size: 30.0,
+        ..default()
+    };
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(70.),
+            left: Val::Percent(15.),
+            bottom: Val::Percent(5.),
+            padding: UiRect::all(Val::Px(12.)),
+            column_gap: Val::Px(12.),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0., 0., 0.).with_alpha(0.7)),
+        DialogueBox,
+        GlobalZIndex(11),
+        Visibility::Hidden,
+    )).with_children(|parent| {
+        parent.spawn((
+            Node {
+                width: Val::Px(96.),
+                height: Val::Px(96.),
+                display: Display::None,
+                ..default()
+            },
+            ImageNode::default(),
+            DialoguePortrait,
+        ));
+        parent.spawn(Node {
+            flex_direction: FlexDirection::Column,
+            flex_grow: 1.,
+            row_gap: Val::Px(6.),
+            ..default()
+        }).with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                font.clone(),
+                TextColor(Color::srgb(0.9, 0.75, 0.4)),
+                DialogueSpeaker,
+            ));
+            parent.spawn((Text::new(""), font.clone(), DialogueText, Typewriter::default()));
+            parent.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                DialogueChoices,
+            ));
+        });
+    });
+    commands.insert_resource(DialogueFont(font));
+}
+
+fn load_dialogues(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Dialogues(asset_server.load(DIALOGUES)));
+}
+
+/// 生成NPC
+fn spawn_npcs(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    level: Res<LevelObjects>,
+) {
+    let texture = asset_server.load(NPC_SHEET);
+    let layout = texture_atlas_layouts.add(
+        TextureAtlasLayout::from_grid(UVec2::splat(64), NPC_FRAMES as u32, 1, None, None)
+    );
+    let atlas = TextureAtlas { layout, index: 0 };
+
+    // NPC在地图中用`Npc`类的对象放置
+    for obj in level.with_class("Npc") {
+        let Some(id) = obj.get_string("npc") else { continue; };
+        commands.spawn((
+            Npc {
+                id: id.clone(),
+                portrait: ImageNode::from_atlas_image(texture.clone(), atlas.clone()),
+            },
+            CutsceneActor(id),
+            Sprite::from_atlas_image(texture.clone(), atlas.clone()),
+            NpcAnimation(Timer::from_seconds(NPC_FRAME_TIME, TimerMode::Repeating)),
+            Transform::from_translation(obj.position.extend(-0.1)).with_scale(Vec3::splat(0.8)),
+            Collider::rectangle(40., 40.),
+            Sensor,
+            CollisionEventsEnabled,
+            CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player]),
+        )).observe(enter_npc_observer).observe(exit_npc_observer);
+    }
+}
+
+/// NPC待机动画
+fn animate_npcs(time: Res<Time>, mut npcs: Query<(&mut NpcAnimation, &mut Sprite)>) {
+    for (mut animation, mut sprite) in &mut npcs {
+        animation.0.tick(time.delta());
+        if !animation.0.just_finished() { continue; }
+        if let Some(atlas) = &mut sprite.texture_atlas {
+            atlas.index = (atlas.index + 1) % NPC_FRAMES;
+        }
+    }
+}
+
+/// 玩家进入NPC交谈范围
+fn enter_npc_observer(
+    trigger: Trigger<OnCollisionStart>,
+    mut commands: Commands,
+    locale: Res<Localization>,
+    mut prompt: Single<&mut Prompt, With<Hint>>,
+) {
+    commands.entity(trigger.collider).insert(NearNpc(trigger.target()));
+    prompt.set(locale.tr("ui.talk"));
+}
+
+/// 玩家离开NPC交谈范围
+fn exit_npc_observer(
+    trigger: Trigger<OnCollisionEnd>,
+    mut commands: Commands,
+    player: Query<&NearNpc>,
+    mut prompt: Single<&mut Prompt, With<Hint>>,
+) {
+    let Ok(near) = player.get(trigger.collider) else { return; };
+    if near.0 != trigger.target() { return; }
+    commands.entity(trigger.collider).remove::<NearNpc>();
+    prompt.set("");
+}
+
+/// 进入节点：执行节点效果，显示文字与满足条件（`met`）的选项，节点不存在时返回false
+fn enter_node(
+    conversation: &mut Conversation,
+    id: &str,
+    tree: &DialogueTree,
+    npc: &Npc,
+    player: Entity,
+    met: impl Fn(&[DialogueCondition]) -> bool,
+    ui: &mut DialogueUi,
+) -> bool {
+    let Some(node) = tree.nodes.get(id) else {
+        println!("Dialogue node {} of {} not found", id, npc.id);
+        return false;
+    };
+    apply_effects(&mut ui.commands, &node.effects, player);
+    conversation.node = id.to_string();
+    conversation.choices = node
+        .choices
+        .iter()
+        .enumerate()
+        .filter(|(_, choice)| met(&choice.conditions))
+        .map(|(index, _)| index)
+        .collect();
+    conversation.selected = 0;
+
+    let speaker = node.speaker.clone().unwrap_or_else(|| npc.name_key());
+    ui.show_line(Some(speaker.as_str()), &node.text, Some(&npc.portrait));
+    let choices: Vec<String> = conversation.choices.iter().map(|&i| node.choices[i].text.clone()).collect();
+    ui.show_choices(&choices, 0);
+    true
+}
+
+/// 开始交谈、推进对话与选择选项
+fn dialogue_input(
+    mut state: ResMut<DialogueState>,
+    menu_input: Res<ActionState<MenuAction>>,
+    player: Single<(Entity, &ActionState<Action>, &ItemBag, Option<&NearNpc>, Has<InputLocked>), With<Player>>,
+    npcs: Query<&Npc>,
+    flags: Res<WorldFlags>,
+    dialogues: Res<Dialogues>,
+    sets: Res<Assets<DialogueSet>>,
+    mut ui: DialogueUi,
+) {
+    let (entity, action_state, bag, near, locked) = player.into_inner();
+    let met = |conditions: &[DialogueCondition]| all_met(conditions, &flags, bag);
+    let Some(set) = sets.get(&dialogues.0) else { return; };
+    let confirm = menu_input.just_pressed(&MenuAction::Confirm) || action_state.just_pressed(&Action::PickItem);
+
+    let Some(conversation) = state.conversation.as_mut() else {
+        // 过场动画等锁定输入时不能交谈
+        if locked || !action_state.just_pressed(&Action::PickItem) { return; }
+        let Some(near) = near else { return; };
+        let npc = npcs.get(near.0).unwrap();
+        let Some(tree) = set.0.get(&npc.id) else {
+            println!("Dialogue of {} not found", npc.id);
+            return;
+        };
+        let Some(entry) = tree.entries.iter().find(|entry| met(&entry.conditions)) else { return; };
+        let mut conversation = Conversation {
+            npc: near.0,
+            node: String::new(),
+            choices: vec![],
+            selected: 0,
+            fresh: true,
+        };
+        if enter_node(&mut conversation, &entry.node, tree, npc, entity, met, &mut ui) {
+            ui.commands.entity(entity).insert(InputLocked);
+            state.conversation = Some(conversation);
+        }
+        return;
+    };
+    if conversation.fresh {
+        conversation.fresh = false;
+        return;
+    }
+
+    let npc = npcs.get(conversation.npc).unwrap();
+    let tree = &set.0[&npc.id];
+    let node = &tree.nodes[&conversation.node];
+
+    // 切换选项
+    let count = conversation.choices.len();
+    if count > 0 {
+        let selected = if menu_input.just_pressed(&MenuAction::Up) {
+            (conversation.selected + count - 1) % count
+        } else if menu_input.just_pressed(&MenuAction::Down) {
+            (conversation.selected + 1) % count
+        } else {
+            conversation.selected
+        };
+        if selected != conversation.selected {
+            conversation.selected = selected;
+            let choices: Vec<String> = conversation.choices.iter().map(|&i| node.choices[i].text.clone()).collect();
+            ui.show_choices(&choices, selected);
+        }
+    }
+
+    if !confirm { return; }
+    // 文字还没显示完时先显示全部
+    if !ui.text.is_finished() {
+        ui.text.finish();
+        return;
+    }
+    let next = match conversation.choices.get(conversation.selected) {
+        Some(&index) => {
+            let choice = &node.choices[index];
+            apply_effects(&mut ui.commands, &choice.effects, entity);
+            choice.next.clone()
+        }
+        None => node.next.clone(),
+    };
+    let continued = next.is_some_and(|next| enter_node(conversation, &next, tree, npc, entity, met, &mut ui));
+    if !continued {
+        state.conversation = None;
+        ui.hide();
+        ui.commands.entity(entity).remove::<InputLocked>();
+    }
+}
+
+/// 逐字显示文字，文字按当前语言与输入设备渲染
+fn typewriter_system(
+    time: Res<Time>,
+    locale: Res<Localization>,
+    settings: Res<ControlSettings>,
+    device: Res<InputDevice>,
+    mut query: Query<(&mut Typewriter, &mut Text)>,
+) {
+    for (mut typewriter, mut text) in &mut query {
+        let full = settings.prompt(locale.tr(&typewriter.template), *device);
+        typewriter.len = full.chars().count();
+        if !typewriter.is_finished() {
+            typewriter.shown = (typewriter.shown + time.delta_secs() * TYPE_SPEED).min(typewriter.len as f32);
+        }
+        let shown: String = full.chars().take(typewriter.shown as usize).collect();
+        if text.0 != shown {
+            text.0 = shown;
+        }
+    }
+}
+
+pub struct DialoguePlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for DialoguePlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<DialogueSet>();
+        app.init_asset_loader::<DialogueLoader>();
+        app.init_resource::<DialogueState>();
+        app.add_systems(Startup, (load_dialogues, setup_dialogue_ui));
+        app.add_systems(OnEnter(self.state.clone()), spawn_npcs);
+        app.add_systems(
+            Update,
+            (dialogue_input, animate_npcs).run_if(in_state(self.state.clone())),
+        );
+        app.add_systems(Update, typewriter_system);
+    }
+}
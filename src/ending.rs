@@ -1,6 +1,9 @@
 //! 结局滚动字幕
 
 use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::controls::MenuAction;
 
 /// 字幕文字
 #[derive(Component)]
@@ -103,7 +106,7 @@ fn scroll_credits_system(
     time: Res<Time>,
     mut query: Query<(&mut Node, &ScrollingCredits)>,
     window_query: Query<&Window>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    menu_input: Res<ActionState<MenuAction>>,
     mut exit_events: EventWriter<AppExit>,
 ) {
     let window = window_query.single().unwrap();
@@ -122,7 +125,7 @@ fn scroll_credits_system(
         // 更新位置
         style.top = Val::Px(new_top);
     }
-    if keyboard_input.just_pressed(KeyCode::Enter) {
+    if menu_input.just_pressed(&MenuAction::Confirm) {
         exit_events.write(AppExit::Success);
     }
 }
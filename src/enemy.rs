@@ -2,8 +2,17 @@
 
 use bevy::prelude::*;
 
+pub(crate) mod archetype;
+pub(crate) mod perception;
+pub(crate) mod patrol;
+pub(crate) mod flying;
+pub(crate) mod coordination;
+pub(crate) mod profile;
+pub(crate) mod phase;
+pub(crate) mod telegraph;
+pub(crate) mod spawner;
+mod ground;
 mod skeleton;
-mod castle_skeleton;
 mod flying_eye;
 pub(crate) mod fire_demon;
 pub(crate) mod martial;
@@ -13,8 +22,16 @@ pub struct EnemyPlugin<S: States> {
 
 impl<S:States> Plugin for EnemyPlugin<S> {
     fn build(&self, app: &mut App) {
+        app.add_plugins(archetype::ArchetypePlugin { state : self.state.clone() });
+        app.add_plugins(perception::PerceptionPlugin { state : self.state.clone() });
+        app.add_plugins(patrol::PatrolPlugin { state : self.state.clone() });
+        app.add_plugins(flying::FlyingPlugin { state : self.state.clone() });
+        app.add_plugins(coordination::CoordinationPlugin { state : self.state.clone() });
+        app.add_plugins(profile::ProfilePlugin { state : self.state.clone() });
+        app.add_plugins(phase::PhasePlugin { state : self.state.clone() });
+        app.add_plugins(telegraph::TelegraphPlugin { state : self.state.clone() });
+        app.add_plugins(spawner::SpawnerPlugin { state : self.state.clone() });
         app.add_plugins(skeleton::SkeletonPlugin { state : self.state.clone() });
-        app.add_plugins(castle_skeleton::CastleSkeletonPlugin { state : self.state.clone() });
         app.add_plugins(flying_eye::FlyingEyesPlugin { state : self.state.clone() });
         app.add_plugins(fire_demon::FireDemonPlugin { state : self.state.clone() });
         app.add_plugins(martial::MartialPlugin { state : self.state.clone() });
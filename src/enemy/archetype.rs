@@ -0,0 +1,437 @@
+//! 敌人通用框架
+//! 每种敌人用一份`EnemyConfig`描述贴图、碰撞体、数值、感知范围、攻击判定与行为树，
+//! 生成、接触检测、转向、警觉度以及攻击/硬直/死亡回调由这里统一处理，视觉与听觉见`perception`，
+//! 敌人模块只需提供配置、动画状态机和少量自定义行为。
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use big_brain::prelude::*;
+use game_derive::enter;
+use game_derive::exit;
+
+use crate::animator::*;
+use crate::controller::ControllerBundle;
+use crate::damagable::{check_hitbox, Damagable, HasHitbox, HitBox, HitboxOf};
+use crate::enemy::flying::{Flyer, FlyingConfig};
+use crate::enemy::perception::{Perception, PerceptionConfig};
+use crate::enemy::profile::AiProfileHandle;
+use crate::enemy::telegraph::TelegraphConfig;
+use crate::game_layer::GameLayer;
+use crate::gravity::GravityDirection;
+use crate::loot::DropLoot;
+use crate::navigation::NavAgent;
+use crate::physics::PhysicsBundle;
+
+/// 悬崖检测点到身体前沿的距离
+const LEDGE_PROBE_AHEAD: f32 = 4.;
+/// 悬崖检测比悬浮高度多出的深度
+const LEDGE_PROBE_DEPTH: f32 = 16.;
+
+/// 攻击判定配置
+#[derive(Debug, Clone, Copy)]
+pub struct HitboxConfig {
+    /// 生效的动画状态名
+    pub state: &'static str,
+    /// 判定框尺寸
+    pub size: Vec2,
+    /// 相对敌人的偏移（面朝右时）
+    pub offset: Vec2,
+    /// 伤害
+    pub damage: f32,
+}
+
+/// 胶囊碰撞体配置
+#[derive(Debug, Clone, Copy)]
+pub struct CapsuleConfig {
+    /// 半径
+    pub radius: f32,
+    /// 上端点高度
+    pub top: f32,
+    /// 下端点深度
+    pub bottom: f32,
+}
+
+/// 警觉度配置
+#[derive(Debug, Clone, Copy)]
+pub struct NoticeConfig {
+    /// 看到玩家时每秒增加的警觉度
+    pub add_per_sec: f32,
+    /// 看不到玩家时每秒减少的警觉度
+    pub sub_per_sec: f32,
+    /// 警觉度上限
+    pub max: f32,
+}
+
+/// 敌人配置
+#[derive(Debug)]
+pub struct EnemyConfig {
+    /// 敌人名
+    pub name: &'static str,
+    /// 精灵表路径
+    pub sheet: &'static str,
+    /// 精灵表单帧尺寸
+    pub tile_size: UVec2,
+    /// 精灵表列数
+    pub columns: u32,
+    /// 精灵表行数
+    pub rows: u32,
+    /// 缩放
+    pub scale: f32,
+    /// 贴图是否默认朝左
+    pub sprite_faces_left: bool,
+    /// 碰撞体
+    pub collider: CapsuleConfig,
+    /// 地面检测宽度
+    pub sensor_width: f32,
+    /// 悬浮高度
+    pub float_height: f32,
+    /// 最大生命
+    pub max_health: f32,
+    /// 巡逻速度
+    pub walk_speed: f32,
+    /// 发现玩家后的速度
+    pub noticed_speed: f32,
+    /// 警觉度
+    pub notice: NoticeConfig,
+    /// 视觉与听觉感知
+    pub perception: PerceptionConfig,
+    /// 各攻击状态的判定
+    pub hitboxes: &'static [HitboxConfig],
+    /// 攻击前摇的预警
+    pub telegraphs: &'static [TelegraphConfig],
+    /// 常驻的身体判定（如飞行眼睛的撞击）
+    pub body_hitbox: Option<HitboxConfig>,
+    /// 飞行配置，为空时是地面敌人
+    pub flying: Option<FlyingConfig>,
+    /// 进入硬直时是否立即停下
+    pub halt_on_stun: bool,
+    /// 攻击结束后是否恢复移动
+    pub resume_after_attack: bool,
+    /// 动画状态机
+    pub animator: fn() -> Animator,
+    /// 行为树
+    pub thinker: fn() -> ThinkerBuilder,
+    /// AI配置资源路径，设置后用配置生成的行为树代替`thinker`
+    pub profile: Option<&'static str>,
+    /// 掉落表名，为空时不掉落
+    pub loot: Option<&'static str>,
+}
+
+/// 敌人组件，保存该敌人的配置
+#[derive(Component)]
+pub struct Enemy {
+    pub config: &'static EnemyConfig,
+}
+
+impl Enemy {
+    /// 根据警觉度获取移动速度，不能移动时为0
+    pub fn speed(&self, animator: &Animator, notice: &Notice) -> f32 {
+        if animator.get_bool("can_move") {
+            if notice.notice > 50. {
+                return self.config.noticed_speed;
+            }
+            return self.config.walk_speed;
+        }
+        0.0
+    }
+}
+
+/// 警觉度
+#[derive(Component, Debug, Reflect)]
+pub struct Notice {
+    pub add_per_sec: f32,
+    pub sub_per_sec: f32,
+    pub notice: f32,
+    pub max: f32,
+}
+
+impl Notice {
+    pub fn new(notice: f32, add_per_sec: f32, sub_per_sec: f32) -> Self {
+        Self {
+            notice,
+            add_per_sec,
+            sub_per_sec,
+            max: 100.,
+        }
+    }
+}
+
+/// 按配置生成敌人
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    config: &'static EnemyConfig,
+    position: Vec2,
+) -> Entity {
+    let texture = asset_server.load(config.sheet);
+    let layout = TextureAtlasLayout::from_grid(config.tile_size, config.columns, config.rows, None, None);
+    let texture_atlas_layout = texture_atlas_layouts.add(layout);
+    let animator = (config.animator)();
+    let collider_layer = CollisionLayers::new(
+        GameLayer::Enemy,
+        [
+            GameLayer::Default,
+            GameLayer::Ground,
+            GameLayer::PlayerHitBox,
+        ],
+    );
+    let mut notice = Notice::new(0.0, config.notice.add_per_sec, config.notice.sub_per_sec);
+    notice.max = config.notice.max;
+
+    let entity = commands.spawn((
+        Sprite {
+            image: texture,
+            texture_atlas: Some(TextureAtlas {
+                layout: texture_atlas_layout,
+                index: animator.first_index,
+            }),
+            ..default()
+        },
+        Transform::from_xyz(position.x, position.y, 0.0).with_scale(
+            Vec3::splat(config.scale)
+        ),
+        Enemy { config },
+        ControllerBundle::new(config.sensor_width),
+        PhysicsBundle {
+            collider: Collider::capsule_endpoints(
+                config.collider.radius,
+                Vec2::Y * config.collider.top,
+                Vec2::NEG_Y * config.collider.bottom,
+            ),
+            layer: collider_layer,
+            // 飞行敌人不受重力
+            gravity: GravityScale(if config.flying.is_some() { 0. } else { 30. }),
+            ..default()
+        },
+        Damagable::new(config.max_health),
+        animator,
+        notice,
+        Perception::new(&config.perception),
+    )).id();
+
+    match config.profile {
+        Some(path) => commands.entity(entity).insert(AiProfileHandle(asset_server.load(path))),
+        None => commands.entity(entity).insert((config.thinker)()),
+    };
+
+    match &config.flying {
+        Some(flying) => commands.entity(entity).insert(Flyer::new(flying)),
+        // 地面敌人沿导航图寻路
+        None => commands.entity(entity).insert(NavAgent::default()),
+    };
+    if let Some(hitbox) = config.body_hitbox {
+        spawn_hitbox(commands, entity, &hitbox);
+    }
+    entity
+}
+
+/// 生成攻击判定
+fn spawn_hitbox(commands: &mut Commands, entity: Entity, hitbox: &HitboxConfig) {
+    commands.spawn((
+        Collider::rectangle(hitbox.size.x, hitbox.size.y),
+        Transform::from_xyz(hitbox.offset.x, hitbox.offset.y, 0.),
+        Sensor,
+        HitBox { damage: hitbox.damage },
+        CollisionLayers::new(GameLayer::EnemyHitBox, [GameLayer::Player]),
+        CollisionEventsEnabled,
+        ChildOf(entity),
+        HitboxOf(entity),
+    )).observe(check_hitbox);
+}
+
+/// 死亡状态，移除敌人并按掉落表掉落
+#[exit("death")]
+fn on_death_exit(mut commands: Commands, enemy: Query<(&Enemy, &Transform)>) {
+    let entity = trigger.entity;
+    if let Ok((enemy, transform)) = enemy.get(entity) {
+        if let Some(table) = enemy.config.loot {
+            commands.trigger(DropLoot { table, position: transform.translation.truncate() });
+        }
+    }
+    commands.entity(entity).despawn();
+}
+
+/// 进入攻击状态，按当前状态生成判定
+#[enter("attack")]
+fn on_attack_enter(
+    mut commands: Commands,
+    enemy: Query<(&Enemy, &Animator)>,
+) {
+    let entity = trigger.entity;
+    let Ok((enemy, animator)) = enemy.get(entity) else { return; };
+    for hitbox in enemy.config.hitboxes {
+        if animator.in_state(hitbox.state.to_string()) {
+            spawn_hitbox(&mut commands, entity, hitbox);
+        }
+    }
+}
+
+/// 退出攻击状态，移除判定
+#[exit("attack")]
+fn on_attack_exit(
+    mut commands: Commands,
+    mut enemy: Query<(&Enemy, &HasHitbox, &mut Animator)>,
+) {
+    let entity = trigger.entity;
+    let Ok((enemy, hitboxes, mut animator)) = enemy.get_mut(entity) else { return; };
+    let vec = (**hitboxes).clone();
+    for hitbox in vec {
+        commands.entity(hitbox).despawn();
+    }
+    if enemy.config.resume_after_attack {
+        animator.set_bool("can_move", true);
+    }
+}
+
+/// 进入硬直状态
+#[enter("stun")]
+fn on_stun_enter(
+    mut enemy: Query<(&Enemy, &mut Animator, &mut LinearVelocity)>,
+) {
+    let entity = trigger.entity;
+    let (enemy, mut animator, mut vel) = enemy.get_mut(entity).unwrap();
+    animator.set_bool("can_move", false);
+    if enemy.config.halt_on_stun {
+        vel.x = 0.;
+    }
+}
+
+/// 退出硬直状态
+#[exit("stun")]
+fn on_stun_exit(
+    mut enemy: Query<&mut Animator, With<Enemy>>,
+) {
+    let entity = trigger.entity;
+    let mut animator = enemy.get_mut(entity).unwrap();
+    animator.set_bool("can_move", true);
+}
+
+/// 检查接触
+fn check_contact(
+    spatial_query: SpatialQuery,
+    mut query: Query<(&Enemy, &Transform, &GravityDirection, &mut Animator, &Collider)>,
+) {
+    for (enemy, transform, gravity_direction, mut animator, collider) in &mut query {
+        // 地面与天花板随重力方向翻转
+        let up = gravity_direction.up_2d();
+        let origin = Vec2::new(transform.translation.x, transform.translation.y);
+        let rotation = transform.rotation.z;
+        let direction_x = if animator.get_float("facing_direction") > 0.0 {
+            Dir2::X
+        } else {
+            Dir2::NEG_X
+        };
+        let max_distance_y = 0.2;
+        let max_distance_x = 10.;
+        let max_hits = 1;
+
+        let config_y = ShapeCastConfig::from_max_distance(max_distance_y);
+        let config_x = ShapeCastConfig::from_max_distance(max_distance_x);
+        let filter = SpatialQueryFilter::default().with_mask(GameLayer::Ground);
+
+        let hits_ground = spatial_query.shape_hits(
+            &collider,
+            origin,
+            rotation,
+            -up,
+            max_hits,
+            &config_y,
+            &filter,
+        );
+        let hits_wall = spatial_query.shape_hits(
+            &collider,
+            origin,
+            rotation,
+            direction_x,
+            max_hits,
+            &config_x,
+            &filter,
+        );
+        let hits_ceiling = spatial_query.shape_hits(
+            &collider,
+            origin,
+            rotation,
+            up,
+            max_hits,
+            &config_y,
+            &filter,
+        );
+
+        // 悬崖检测：从身体前方向下检测地面
+        let half_width = enemy.config.collider.radius * enemy.config.scale;
+        let probe_origin = origin + direction_x * (half_width + LEDGE_PROBE_AHEAD);
+        let hits_ledge = spatial_query.cast_ray(
+            probe_origin,
+            -up,
+            enemy.config.float_height + LEDGE_PROBE_DEPTH,
+            true,
+            &filter,
+        );
+
+        animator.set_bool("is_grounded", hits_ground.len() > 0);
+        animator.set_bool("is_on_wall", hits_wall.len() > 0);
+        animator.set_bool("is_on_ceiling", hits_ceiling.len() > 0);
+        animator.set_bool("is_at_ledge", hits_ground.len() > 0 && hits_ledge.is_none());
+    }
+}
+
+/// 检查调转方向
+fn on_flip_direction(mut query: Query<(&Enemy, &mut Transform, &Animator)>) {
+    for (enemy, mut transform, animator) in &mut query {
+        let mut facing_direction = animator.get_float("facing_direction");
+        if enemy.config.sprite_faces_left {
+            facing_direction = -facing_direction;
+        }
+        if transform.scale.x * facing_direction < 0. {
+            transform.scale.x *= -1.;
+        }
+    }
+}
+
+/// 检查移动
+fn on_move(mut query: Query<(&LinearVelocity, &mut Animator), With<Enemy>>) {
+    for (vel, mut animator) in query.iter_mut() {
+        let is_moving = vel.x != 0.;
+        animator.set_bool("is_moving", is_moving);
+    }
+}
+
+/// 更新警觉度，受到攻击时立刻警觉，离玩家越近增长越快
+fn notice_system(time: Res<Time>, mut query: Query<(&mut Notice, &Animator, &Perception), With<Enemy>>) {
+    for (mut notice, animator, perception) in &mut query {
+        if animator.is_active("hit") {
+            notice.notice = 100.;
+        }
+        if animator.get_bool("is_noticing") {
+            notice.notice += notice.add_per_sec * (1. + perception.proximity) * time.delta_secs();
+        } else {
+            notice.notice -= notice.sub_per_sec * time.delta_secs();
+        }
+
+        notice.notice = notice.notice.clamp(0.0, notice.max);
+    }
+}
+
+pub struct ArchetypePlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for ArchetypePlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, notice_system.run_if(in_state(self.state.clone())));
+        app.add_systems(
+            FixedUpdate,
+            (
+                check_contact.run_if(in_state(self.state.clone())),
+                on_move.run_if(in_state(self.state.clone())),
+                on_flip_direction.run_if(in_state(self.state.clone())),
+            ),
+        );
+        app.add_observer(on_death_exit);
+        app.add_observer(on_attack_enter);
+        app.add_observer(on_attack_exit);
+        app.add_observer(on_stun_enter);
+        app.add_observer(on_stun_exit);
+    }
+}
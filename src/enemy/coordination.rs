@@ -0,0 +1,152 @@
+//! 敌人群体协调
+//! 多个敌人同时发现玩家时，由协调器分发有限的攻击令牌：
+//! 持有令牌的敌人从玩家两侧包抄攻击，其余敌人在外围站位等待，攻击结束、开始逃跑或无法靠近玩家时归还令牌，
+//! 持有时间超过上限的令牌由协调器收回。
+
+use big_brain::prelude::BigBrainSet;
+use bevy::prelude::*;
+
+use crate::animator::Animator;
+use crate::enemy::archetype::Notice;
+use crate::player::Player;
+
+/// 归还令牌后再次申请的冷却时间
+const TOKEN_COOLDOWN: f32 = 1.5;
+/// 令牌的最长持有时间
+const MAX_HOLD_TIME: f32 = 4.;
+/// 持有令牌时相对玩家的站位距离
+const ATTACK_OFFSET: f32 = 16.;
+/// 等待时相对玩家的站位距离
+const WAIT_DISTANCE: f32 = 80.;
+/// 同一侧等待的敌人之间的间隔
+const WAIT_SPACING: f32 = 30.;
+
+/// 协调器配置
+#[derive(Resource, Debug)]
+pub struct EncounterCoordinator {
+    /// 同时持有的攻击令牌数量
+    pub max_tokens: usize,
+    /// 警觉度超过该值时加入战斗
+    pub engage_notice: f32,
+}
+
+impl Default for EncounterCoordinator {
+    fn default() -> Self {
+        Self {
+            max_tokens: 2,
+            engage_notice: 50.,
+        }
+    }
+}
+
+/// 参与群体协调的敌人
+#[derive(Component, Debug, Default, Reflect)]
+pub struct Engagement {
+    /// 是否正在与玩家战斗
+    pub engaged: bool,
+    /// 是否持有攻击令牌
+    pub has_token: bool,
+    /// 站位相对玩家的水平偏移
+    pub offset: f32,
+    /// 再次申请令牌的剩余冷却
+    pub cooldown: f32,
+    /// 已持有令牌的时间
+    pub held: f32,
+}
+
+impl Engagement {
+    /// 正在外围等待令牌
+    pub fn is_waiting(&self) -> bool {
+        self.engaged && !self.has_token
+    }
+
+    /// 攻击结束、逃跑或无法靠近玩家时归还令牌
+    pub fn release(&mut self) {
+        if self.has_token {
+            self.has_token = false;
+            self.cooldown = TOKEN_COOLDOWN;
+        }
+    }
+}
+
+/// 分发攻击令牌并分配站位
+fn coordinate_system(
+    time: Res<Time>,
+    coordinator: Res<EncounterCoordinator>,
+    player: Single<&Transform, With<Player>>,
+    mut enemies: Query<(Entity, &Transform, &Notice, &Animator, &mut Engagement)>,
+) {
+    let player = player.translation.truncate();
+    let mut engaged = vec![];
+    for (entity, transform, notice, animator, mut engagement) in &mut enemies {
+        engagement.cooldown = (engagement.cooldown - time.delta_secs()).max(0.);
+        if engagement.has_token {
+            engagement.held += time.delta_secs();
+            if engagement.held > MAX_HOLD_TIME {
+                engagement.release();
+            }
+        }
+        if notice.notice > coordinator.engage_notice && animator.get_bool("is_alive") {
+            engagement.engaged = true;
+            let delta = transform.translation.x - player.x;
+            engaged.push((entity, delta));
+        } else {
+            engagement.engaged = false;
+            engagement.has_token = false;
+        }
+    }
+    // 离玩家近的敌人优先
+    engaged.sort_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()));
+
+    let mut holders = engaged
+        .iter()
+        .filter(|(entity, _)| enemies.get(*entity).unwrap().4.has_token)
+        .count();
+    for (entity, _) in &engaged {
+        if holders >= coordinator.max_tokens {
+            break;
+        }
+        let mut engagement = enemies.get_mut(*entity).unwrap().4;
+        if !engagement.has_token && engagement.cooldown <= 0. {
+            engagement.has_token = true;
+            engagement.held = 0.;
+            holders += 1;
+        }
+    }
+
+    // 持有令牌的敌人尽量分到玩家两侧，等待的敌人在各自一侧排开
+    let mut attackers = [0, 0];
+    let mut waiters = [0, 0];
+    let index = |side: f32| if side > 0. { 0 } else { 1 };
+    for (entity, delta) in engaged {
+        let mut engagement = enemies.get_mut(entity).unwrap().4;
+        let mut side = if delta >= 0. { 1. } else { -1. };
+        if engagement.has_token {
+            if attackers[index(side)] > attackers[index(-side)] {
+                side = -side;
+            }
+            attackers[index(side)] += 1;
+            engagement.offset = side * ATTACK_OFFSET;
+        } else {
+            let rank = waiters[index(side)];
+            waiters[index(side)] += 1;
+            engagement.offset = side * (WAIT_DISTANCE + rank as f32 * WAIT_SPACING);
+        }
+    }
+}
+
+pub struct CoordinationPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for CoordinationPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EncounterCoordinator>();
+        app.add_systems(
+            PreUpdate,
+            coordinate_system
+                .before(BigBrainSet::Actions)
+                .run_if(in_state(self.state.clone())),
+        );
+    }
+}
@@ -1,113 +1,149 @@
 //! 火焰恶魔boss
 
-use avian2d::prelude::*;
 use bevy::prelude::*;
-use big_brain::prelude::*;
-use game_derive::enter;
 use game_derive::exit;
 
 use crate::animator::Condition;
+use crate::arena::{is_defeated, spawn_arena, ArenaBoss, ArenaReset, BossArena, BossDefeated};
 use crate::animator::*;
-use crate::blocks::FireDemonBlocks;
-use crate::controller::ControllerBundle;
-use crate::damagable::{check_hitbox, Damagable, HasHitbox, HitBox, HitboxOf};
-use crate::game_layer::GameLayer;
-use crate::hint::ItemHint;
-use crate::items::{item_canpick_observer, item_cantpick_observer, ItemList, NotpickedItems};
-use crate::physics::PhysicsBundle;
-use crate::player::Player;
+use crate::enemy::archetype::{
+    __attack_enter_handler, __attack_exit_handler, __stun_enter_handler, __stun_exit_handler,
+    spawn_enemy, CapsuleConfig, Enemy, EnemyConfig, HitboxConfig, Notice, NoticeConfig,
+};
+use crate::enemy::perception::PerceptionConfig;
+use crate::enemy::telegraph::{TelegraphConfig, TelegraphCue};
+use crate::enemy::phase::{BossPhase, BossPhases, PhaseTrigger};
+use crate::items::{spawn_pickup, ItemList};
+use crate::loot::{DropLoot, RespawnUnclaimed};
+use crate::world_flags::WorldFlags;
+use crate::boss_bar::Boss;
 mod behaviour;
 use behaviour::*;
 
 #[derive(Component, Reflect)]
 struct FireDemon;
 
+/// 火焰恶魔，贴图默认朝左
+const FIRE_DEMON: EnemyConfig = EnemyConfig {
+    name: "Demon Slime",
+    sheet: "Art/boss_demon_slime_FREE_v1.0/spritesheets/demon_slime_sheet.png",
+    tile_size: UVec2::new(288, 160),
+    columns: 7,
+    rows: 9,
+    scale: 1.,
+    sprite_faces_left: true,
+    collider: CapsuleConfig { radius: 15., top: 1., bottom: 60. },
+    sensor_width: 29.8,
+    float_height: 78.,
+    max_health: 600.,
+    walk_speed: 20.,
+    noticed_speed: 40.,
+    notice: NoticeConfig { add_per_sec: 50., sub_per_sec: 10., max: 100. },
+    perception: PerceptionConfig {
+        view_distance: 200.,
+        view_angle: 0.8,
+        hearing_radius: 200.,
+        memory: 6.,
+        through_ground: false,
+    },
+    hitboxes: &[
+        HitboxConfig {
+            state: "Attack",
+            size: Vec2::new(50., 20.),
+            offset: Vec2::new(-80., -70.),
+            damage: 40.,
+        },
+        HitboxConfig {
+            state: "Boom",
+            size: Vec2::new(80., 100.),
+            offset: Vec2::new(0., -20.),
+            damage: 10.,
+        },
+    ],
+    // 拍击标出落点，投掷火球前全身闪橙色，爆炸前闪橙色并标出爆炸范围
+    telegraphs: &[
+        TelegraphConfig {
+            state: "AttackPrep",
+            cues: &[TelegraphCue::Area {
+                size: Vec2::new(50., 20.),
+                offset: Vec2::new(-80., -70.),
+                color: Color::srgba(1., 0.3, 0.1, 0.35),
+            }],
+            sound: Some("Audio/SFX/8_Atk_Magic_SFX/30_Earth_02.wav"),
+        },
+        TelegraphConfig {
+            state: "FireballPrep",
+            cues: &[TelegraphCue::Flash { color: Color::srgb(1.0, 0.5, 0.1) }],
+            sound: None,
+        },
+        TelegraphConfig {
+            state: "BoomPrep",
+            cues: &[
+                TelegraphCue::Flash { color: Color::srgb(1., 0.5, 0.1) },
+                TelegraphCue::Area {
+                    size: Vec2::new(80., 100.),
+                    offset: Vec2::new(0., -20.),
+                    color: Color::srgba(1., 0.5, 0.1, 0.3),
+                },
+            ],
+            sound: Some("Audio/SFX/8_Atk_Magic_SFX/45_Charge_05.wav"),
+        },
+    ],
+    body_hitbox: None,
+    flying: None,
+    halt_on_stun: true,
+    resume_after_attack: false,
+    animator: setup_animator,
+    thinker: fire_demon_thinker,
+    profile: None,
+    loot: Some("fire_demon"),
+};
+
+/// 火焰恶魔半血后暴怒
+const FIRE_DEMON_PHASES: &[BossPhase] = &[BossPhase {
+    name: "Enraged",
+    trigger: PhaseTrigger::Health(0.5),
+    thinker: fire_demon_phase_two_thinker,
+    transition_time: 1.5,
+    animation: Some("boom"),
+    music: Some("Audio/Music/09 Forbiddens Saga (SNES style)/Battle-Forbidden_SNES.mp3"),
+}];
+
 fn setup_enemy(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     items: Res<ItemList>,
+    flags: Res<WorldFlags>,
 ) {
-    let texture =
-        asset_server.load("Art/boss_demon_slime_FREE_v1.0/spritesheets/demon_slime_sheet.png");
-    let layout = TextureAtlasLayout::from_grid(UVec2::new(288, 160), 7, 9, None, None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
-
-    spawn_enemy(
-        &mut commands,
-        Vec2::new(1430.0, 144.1),
-        texture.clone(),
-        texture_atlas_layout.clone(),
-    );
+    spawn_pickup(&mut commands, &items, "HealthPotion", 5, Vec3::new(1480.0, 54.1, 0.0));
 
-    commands.spawn((
-        Sprite {
-            image: items.infos.get(&String::from("HealthPotion")).unwrap().icon.clone(),
-            ..default()
-        },
-        Collider::rectangle(20.0, 20.0),
-        Transform::from_xyz(1480.0, 54.1, 0.0),
-        ItemHint,
-        NotpickedItems { id: "HealthPotion".to_string(), num: 5 },
-        Sensor,
-        CollisionEventsEnabled,
-        CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player])
-    )).observe(item_cantpick_observer).observe(item_canpick_observer);
-    
-}
+    // 已被击败时不再生成，掉落的能力道具还没拾取则放回原处
+    if is_defeated(&flags, "fire_demon") {
+        commands.trigger(RespawnUnclaimed { table: "fire_demon", position: Vec2::new(1430.0, 22.1) });
+        return;
+    }
 
-fn spawn_enemy(
-    commands: &mut Commands,
-    position: Vec2,
-    texture: Handle<Image>,
-    texture_atlas_layout: Handle<TextureAtlasLayout>,
-) {
-    let animator = setup_animator();
-    let collider_layer = CollisionLayers::new(
-        GameLayer::Enemy,
-        [
-            GameLayer::Default,
-            GameLayer::Ground,
-            GameLayer::PlayerHitBox,
-        ],
+    let arena = spawn_arena(
+        &mut commands,
+        BossArena::new("fire_demon", "Audio/Music/02 Conflict/Battle-Conflict.mp3"),
+        Vec2::new(1054.5, 46.),
+        Vec2::new(16., 100.),
     );
-    let move_and_attack = Steps::build()
-        .label("MoveAndAttack")
-        .step(MoveToPlayer)
-        .step(Attack);
-
-    //let patrol = Steps::build().label("Patrol").step(Patrol);
 
-    let thinker = Thinker::build()
-        .label("Thinker")
-        .picker(Highest)
-        .when(NoticeScorer, move_and_attack);
-
-    commands.spawn((
-        Sprite {
-            image: texture,
-            texture_atlas: Some(TextureAtlas {
-                layout: texture_atlas_layout,
-                index: animator.first_index,
-            }),
-            ..default()
-        },
-        Transform::from_xyz(position.x, position.y, 0.0),
+    let position = Vec2::new(1430.0, 144.1);
+    let entity = spawn_enemy(&mut commands, &asset_server, &mut texture_atlas_layouts, &FIRE_DEMON, position);
+    let phases = BossPhases::new(FIRE_DEMON_PHASES);
+    commands.entity(entity).insert((
         FireDemon,
-        ControllerBundle::new(29.8),
-        PhysicsBundle {
-            collider: Collider::capsule_endpoints(
-                15.0, 
-                Vec2::Y * 1.0, 
-                Vec2::NEG_Y * 60.0
-            ),
-            layer: collider_layer,
-            ..default()
+        FireballCooldown::default(),
+        Boss::new(FIRE_DEMON.name, phases.health_thresholds()),
+        phases,
+        ArenaBoss {
+            arena,
+            spawn: position.extend(0.0),
+            gravity: 30.0,
         },
-        Damagable::new(600.),
-        animator,
-        Notice::new(0.0, 50.0, 10.0),
-        thinker,
     ));
 }
 
@@ -116,6 +152,7 @@ enum AnimationType {
     AttackPrep,
     Attack,
     AttackEnd,
+    BoomPrep,
     Boom,
     Death,
     Idle,
@@ -129,7 +166,8 @@ impl AnimationType {
             Self::AttackPrep => (0, 8),
             Self::Attack => (9, 11),
             Self::AttackEnd => (12, 14),
-            Self::Boom => (15, 24),
+            Self::BoomPrep => (15, 18),
+            Self::Boom => (19, 24),
             Self::Death => (15, 36),
             Self::Idle => (37, 42),
             Self::Hurt => (43, 47),
@@ -170,7 +208,17 @@ fn setup_animator() -> Animator {
                     operator: ConditionOperator::Equals,
                     value: AnimatorParam::Trigger(true),
                 }],
-                target_state: "Boom".to_string(),
+                target_state: "BoomPrep".to_string(),
+                has_exit_time: false,
+                exit_time: 0.0,
+            },
+            Transition {
+                conditions: vec![Condition {
+                    param_name: "fireball_prep".to_string(),
+                    operator: ConditionOperator::Equals,
+                    value: AnimatorParam::Bool(true),
+                }],
+                target_state: "FireballPrep".to_string(),
                 has_exit_time: false,
                 exit_time: 0.0,
             },
@@ -221,7 +269,48 @@ fn setup_animator() -> Animator {
                     operator: ConditionOperator::Equals,
                     value: AnimatorParam::Trigger(true),
                 }],
-                target_state: "Boom".to_string(),
+                target_state: "BoomPrep".to_string(),
+                has_exit_time: false,
+                exit_time: 0.0,
+            },
+            Transition {
+                conditions: vec![Condition {
+                    param_name: "fireball_prep".to_string(),
+                    operator: ConditionOperator::Equals,
+                    value: AnimatorParam::Bool(true),
+                }],
+                target_state: "FireballPrep".to_string(),
+                has_exit_time: false,
+                exit_time: 0.0,
+            },
+            Transition {
+                conditions: vec![Condition {
+                    param_name: "is_alive".to_string(),
+                    operator: ConditionOperator::Equals,
+                    value: AnimatorParam::Bool(false),
+                }],
+                target_state: "Death".to_string(),
+                has_exit_time: false,
+                exit_time: 0.0,
+            },
+        ],
+        loop_animation: true,
+        ..default()
+    };
+
+    // 投掷火球前蓄力，沿用待机动画
+    let fireball_prep_state = AnimationState {
+        name: "FireballPrep".to_string(),
+        first_index: AnimationType::Idle.config_index().0,
+        last_index: AnimationType::Idle.config_index().1,
+        transitions: vec![
+            Transition {
+                conditions: vec![Condition {
+                    param_name: "fireball_prep".to_string(),
+                    operator: ConditionOperator::Equals,
+                    value: AnimatorParam::Bool(false),
+                }],
+                target_state: "Idle".to_string(),
                 has_exit_time: false,
                 exit_time: 0.0,
             },
@@ -296,6 +385,32 @@ fn setup_animator() -> Animator {
         ..default()
     };
 
+    let boom_prep_state = AnimationState {
+        name: "BoomPrep".to_string(),
+        first_index: AnimationType::BoomPrep.config_index().0,
+        last_index: AnimationType::BoomPrep.config_index().1,
+        transitions: vec![
+            Transition {
+                conditions: vec![],
+                target_state: "Boom".to_string(),
+                has_exit_time: true,
+                exit_time: 1.0,
+            },
+            Transition {
+                conditions: vec![Condition {
+                    param_name: "is_alive".to_string(),
+                    operator: ConditionOperator::Equals,
+                    value: AnimatorParam::Bool(false),
+                }],
+                target_state: "Death".to_string(),
+                has_exit_time: false,
+                exit_time: 0.0,
+            },
+        ],
+        loop_animation: false,
+        ..default()
+    };
+
     let boom_state = AnimationState {
         name: "Boom".to_string(),
         first_index: AnimationType::Boom.config_index().0,
@@ -309,8 +424,8 @@ fn setup_animator() -> Animator {
             },
         ],
         loop_animation: false,
-        on_enter: Some(__boom_enter_handler),
-        on_exit: Some(__boom_exit_handler),
+        on_enter: Some(__attack_enter_handler),
+        on_exit: Some(__attack_exit_handler),
         ..default()
     };
 
@@ -378,6 +493,7 @@ fn setup_animator() -> Animator {
     animator.add_parameter("can_move", AnimatorParam::Bool(true));
     animator.add_parameter("attack", AnimatorParam::Trigger(false));
     animator.add_parameter("boom", AnimatorParam::Trigger(false));
+    animator.add_parameter("fireball_prep", AnimatorParam::Bool(false));
     animator.add_parameter("is_alive", AnimatorParam::Bool(true));
     animator.add_parameter("hit", AnimatorParam::Trigger(false));
     animator.add_parameter("is_grounded", AnimatorParam::Bool(true));
@@ -389,7 +505,9 @@ fn setup_animator() -> Animator {
 
     animator.add_state(idle_state);
     animator.add_state(walk_state);
+    animator.add_state(fireball_prep_state);
     animator.add_state(attack_state);
+    animator.add_state(boom_prep_state);
     animator.add_state(boom_state);
     animator.add_state(attack_prep_state);
     animator.add_state(attack_end_state);
@@ -409,202 +527,25 @@ fn setup_animator() -> Animator {
 #[exit("death")]
 fn on_fire_demon_death(
     mut commands: Commands,
-    demon: Single<(Entity, &Transform), With<FireDemon>>,
-    blocks: Query<Entity, With<FireDemonBlocks>>,
-    items: Res<ItemList>, 
+    demon: Single<(Entity, &Enemy, &Transform), With<FireDemon>>,
 ) {
-    let (entity, transform) = demon.into_inner();
+    let (entity, enemy, transform) = demon.into_inner();
+    commands.trigger(BossDefeated { boss: entity });
+    commands.trigger(DropLoot { table: enemy.config.loot.unwrap(), position: transform.translation.truncate() });
     commands.entity(entity).despawn();
-    commands.spawn((
-        Sprite {
-            image: items.infos.get(&String::from("FireGlove")).unwrap().icon.clone(),
-            ..default()
-        },
-        Collider::rectangle(20.0, 20.0),
-        Transform::from_xyz(transform.translation.x, 22.1, 0.0),
-        ItemHint,
-        NotpickedItems { id: "FireGlove".to_string(), num: 1 },
-        Sensor,
-        CollisionEventsEnabled,
-        CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player])
-    )).observe(item_cantpick_observer).observe(item_canpick_observer);
-    for block in blocks {
-        commands.entity(block).despawn();
-    }
-} 
-
-#[enter("attack")]
-fn on_attack_enter(
-    mut commands: Commands
-) {
-    let entity = trigger.entity;
-    commands.spawn((
-            Collider::rectangle(50., 20.),
-            Transform::from_xyz(-80., -70., 0.),
-            Sensor,
-            HitBox { damage: 40. },
-            CollisionLayers::new(GameLayer::EnemyHitBox, [GameLayer::Player]),
-            CollisionEventsEnabled,
-            ChildOf(entity),
-            HitboxOf(entity),
-        )).observe(check_hitbox);
-}
-
-#[exit("attack")]
-fn on_attack_exit(
-    mut commands: Commands,
-    enemy: Query<&HasHitbox, With<FireDemon>>,
-) {
-    let entity = trigger.entity;
-    let hitboxes = enemy.get(entity).unwrap();
-    let vec = (**hitboxes).clone();
-    for hitbox in vec {
-        commands.entity(hitbox).despawn();
-    }
-}
-
-#[enter("boom")]
-fn on_boom_enter(
-    mut commands: Commands
-) {
-    let entity = trigger.entity;
-    commands.spawn((
-            Collider::rectangle(80., 100.),
-            Transform::from_xyz(0., -20., 0.),
-            Sensor,
-            HitBox { damage: 10. },
-            CollisionLayers::new(GameLayer::EnemyHitBox, [GameLayer::Player]),
-            CollisionEventsEnabled,
-            ChildOf(entity),
-            HitboxOf(entity),
-        )).observe(check_hitbox);
-}
-
-#[exit("boom")]
-fn on_boom_exit(
-    mut commands: Commands,
-    enemy: Query<&HasHitbox, With<FireDemon>>,
-) {
-    let entity = trigger.entity;
-    let hitboxes = enemy.get(entity).unwrap();
-    let vec = (**hitboxes).clone();
-    for hitbox in vec {
-        commands.entity(hitbox).despawn();
-    }
-}
-
-#[enter("stun")]
-fn on_stun_enter(
-    mut player: Query<(&mut Animator, &mut LinearVelocity), With<FireDemon>>,
-) {
-    let entity = trigger.entity;
-    let (mut animator, mut vel) = player.get_mut(entity).unwrap();
-    animator.set_bool("can_move", false);
-    vel.x = 0.;
 }
 
-#[exit("stun")]
-fn on_stun_exit(
-    mut player: Query<&mut Animator, With<FireDemon>>,
+/// 区域重置时清空警觉度，状态机回到待机，阶段由`BossPhases`重置
+fn on_arena_reset(
+    trigger: Trigger<ArenaReset>,
+    mut demons: Query<(&mut Notice, &mut Animator), With<FireDemon>>,
 ) {
-    let entity = trigger.entity;
-    let mut animator = player.get_mut(entity).unwrap();
-    animator.set_bool("can_move", true);
-}
-
-
-
-fn check_contact(
-    spatial_query: SpatialQuery,
-    mut query: Query<(&Transform, &mut Animator, &Collider), With<FireDemon>>,
-    entity: Single<Entity, With<Player>>,
-) {
-    let collider_notice = Collider::rectangle(1., 100.);
-
-    for (transform, mut animator, collider) in &mut query {
-        let origin = Vec2::new(transform.translation.x, transform.translation.y);
-        let rotation = transform.rotation.z;
-        let direction_x = if animator.get_float("facing_direction") > 0.0 {
-            Dir2::X
-        } else {
-            Dir2::NEG_X
-        };
-        let max_distance_y = 0.2;
-        let max_distance_x = 10.;
-        let max_distance_notice = 100.;
-        let max_hits = 1;
-
-        let config_y = ShapeCastConfig::from_max_distance(max_distance_y);
-        let config_x = ShapeCastConfig::from_max_distance(max_distance_x);
-        let notice_config = ShapeCastConfig::from_max_distance(max_distance_notice);
-        let filter = SpatialQueryFilter::default().with_mask(GameLayer::Ground);
-        let notice_filter =
-            SpatialQueryFilter::default().with_mask([GameLayer::Player, GameLayer::Ground]);
-
-        let hits_ground = spatial_query.shape_hits(
-            &collider,
-            origin,
-            rotation,
-            Dir2::NEG_Y,
-            max_hits,
-            &config_y,
-            &filter,
-        );
-        let hits_wall = spatial_query.shape_hits(
-            &collider,
-            origin,
-            rotation,
-            direction_x,
-            max_hits,
-            &config_x,
-            &filter,
-        );
-        let hits_ceiling = spatial_query.shape_hits(
-            &collider,
-            origin,
-            rotation,
-            Dir2::Y,
-            max_hits,
-            &config_y,
-            &filter,
-        );
-        let hits_notice = spatial_query.shape_hits(
-            &collider_notice,
-            origin,
-            rotation,
-            direction_x,
-            max_hits,
-            &notice_config,
-            &notice_filter,
-        );
-
-        animator.set_bool("is_grounded", hits_ground.len() > 0);
-        animator.set_bool("is_on_wall", hits_wall.len() > 0);
-        animator.set_bool("is_on_ceiling", hits_ceiling.len() > 0);
-        if let Some(noticed_entity) = hits_notice.get(0) {
-            animator.set_bool(
-                "is_noticing",
-                noticed_entity.entity.to_bits() == entity.to_bits(),
-            );
-        } else {
-            animator.set_bool("is_noticing", false);
-        }
-    }
-}
-
-fn on_flip_direction(mut query: Query<(&mut Transform, &Animator), With<FireDemon>>) {
-    for (mut transform, animator) in &mut query {
-        let facing_direction = animator.get_float("facing_direction");
-        if transform.scale.x * facing_direction > 0. {
-            transform.scale.x *= -1.;
-        }
-    }
-}
-
-fn on_move(mut query: Query<(&LinearVelocity, &mut Animator), With<FireDemon>>) {
-    for (vel, mut animator) in query.iter_mut() {
-        let is_moving = vel.x != 0.;
-        animator.set_bool("is_moving", is_moving);
+    if let Ok((mut notice, mut animator)) = demons.get_mut(trigger.boss) {
+        notice.notice = 0.;
+        animator.reset_to("Idle");
+        animator.set_bool("is_moving", false);
+        animator.set_bool("fireball_prep", false);
+        animator.set_bool("can_move", true);
     }
 }
 
@@ -616,20 +557,7 @@ impl<S: States> Plugin for FireDemonPlugin<S> {
     fn build(&self, app: &mut App) {
         app.add_plugins(FireDemonBehaviourPlugin { state: self.state.clone() });
         app.add_systems(OnEnter(self.state.clone()), setup_enemy.run_if(in_state(self.state.clone())));
-        app.add_systems(
-            FixedUpdate,
-            (
-                check_contact.run_if(in_state(self.state.clone())),
-                on_move.run_if(in_state(self.state.clone())),
-                on_flip_direction.run_if(in_state(self.state.clone())),
-            ),
-        );
         app.add_observer(on_fire_demon_death);
-        app.add_observer(on_attack_enter);
-        app.add_observer(on_attack_exit);
-        app.add_observer(on_boom_enter);
-        app.add_observer(on_boom_exit);
-        app.add_observer(on_stun_enter);
-        app.add_observer(on_stun_exit);
+        app.add_observer(on_arena_reset);
     }
 }
@@ -1,6 +1,8 @@
 use crate::animator::*;
-use crate::enemy::fire_demon::FireDemon;
+use crate::enemy::archetype::{Enemy, Notice};
 use crate::player::Player;
+use crate::projectile::{spawn_projectile, ProjectileConfig, ProjectileTeam};
+use crate::replay::GameRng;
 use avian2d::prelude::LinearVelocity;
 use bevy::prelude::*;
 use bevy_tnua::builtins::*;
@@ -9,56 +11,67 @@ use bevy_tnua::prelude::*;
 use big_brain::prelude::*;
 use rand::Rng;
 
-const WALK_SPEED: f32 = 20.0;
-const NOTICED_SPEED: f32 = 40.0;
 const FLOAT_HEIGHT: f32 = 78.;
 
-fn get_speed(animator: &Animator, notice: &Notice) -> f32 {
-    if animator.get_bool("can_move") {
-        if notice.notice > 50. {
-            return NOTICED_SPEED;
-        }
-        return WALK_SPEED;
-    }
-    0.0
-}
+/// 火球
+const FIREBALL: ProjectileConfig = ProjectileConfig {
+    image: "Art/Kyrise's 16x16 RPG Icon Pack - V1.3/icons/32x32/crystal_01a.png",
+    color: Color::srgb(1.0, 0.5, 0.1),
+    size: Vec2::new(16., 16.),
+    speed: 180.,
+    gravity: 0.,
+    lifetime: 3.,
+    damage: 25.,
+    pierce: 0,
+    homing: 1.2,
+    deflectable: true,
+};
+/// 投掷火球前的蓄力时间
+const FIREBALL_WINDUP: f32 = 0.4;
+/// 火球冷却时间
+const FIREBALL_COOLDOWN: f32 = 3.;
+/// 火球出手位置相对身体的偏移
+const FIREBALL_OFFSET: Vec2 = Vec2::new(40., -30.);
+/// 连发火球之间的夹角
+const FIREBALL_SPREAD: f32 = 0.3;
+/// 第二阶段火球冷却时间
+const ENRAGED_FIREBALL_COOLDOWN: f32 = 1.5;
 
-#[derive(Component, Debug, Reflect)]
-pub struct Notice {
-    pub add_per_sec: f32,
-    pub sub_per_sec: f32,
-    pub notice: f32,
-}
+/// 火焰恶魔行为树：发现玩家后追击并攻击
+pub fn fire_demon_thinker() -> ThinkerBuilder {
+    let move_and_attack = Steps::build()
+        .label("MoveAndAttack")
+        .step(MoveToPlayer)
+        .step(Attack);
 
-impl Notice {
-    pub fn new(notice: f32, add_per_sec: f32, sub_per_sec: f32) -> Self {
-        Self {
-            notice,
-            add_per_sec,
-            sub_per_sec,
-        }
-    }
+    let throw_fireball = Steps::build()
+        .label("ThrowFireball")
+        .step(ThrowFireball::new(1, FIREBALL_COOLDOWN));
+
+    Thinker::build()
+        .label("Thinker")
+        .picker(Highest)
+        .when(RangedScorer, throw_fireball)
+        .when(NoticeScorer, move_and_attack)
 }
 
-pub fn notice_system(time: Res<Time>, mut query: Query<(&mut Notice, &Animator), With<FireDemon>>) {
-    for (mut notice, animator) in &mut query {
-        if animator.is_active("hit") {
-            notice.notice = 100.;
-        }
-        if animator.get_bool("is_noticing") {
-            notice.notice += notice.add_per_sec * time.delta_secs();
-        } else {
-            notice.notice -= notice.sub_per_sec * time.delta_secs();
-        }
+/// 火焰恶魔第二阶段行为树：连续攻击，火球三连发且冷却更短
+pub fn fire_demon_phase_two_thinker() -> ThinkerBuilder {
+    let move_and_attack = Steps::build()
+        .label("EnragedMoveAndAttack")
+        .step(MoveToPlayer)
+        .step(Attack)
+        .step(Attack);
 
-        if notice.notice >= 100. {
-            notice.notice = 100.;
-        }
+    let throw_fireball = Steps::build()
+        .label("FireballBurst")
+        .step(ThrowFireball::new(3, ENRAGED_FIREBALL_COOLDOWN));
 
-        if notice.notice <= 0. {
-            notice.notice = 0.
-        }
-    }
+    Thinker::build()
+        .label("EnragedThinker")
+        .picker(Highest)
+        .when(RangedScorer, throw_fireball)
+        .when(NoticeScorer, move_and_attack)
 }
 
 #[derive(Clone, Component, Debug, Reflect, ActionBuilder)]
@@ -78,6 +91,7 @@ pub fn move_to_player_action_system(
             &LinearVelocity,
             &mut Animator,
             &Notice,
+            &Enemy,
         ),
         Without<Player>,
     >,
@@ -85,7 +99,7 @@ pub fn move_to_player_action_system(
 ) {
     for (Actor(actor), mut action_state, _move_to, span) in &mut action_query {
         let _guard = span.span().enter();
-        let (actor_pos, mut controller, vel, mut animator, notice) =
+        let (actor_pos, mut controller, vel, mut animator, notice, enemy) =
             actor_query.get_mut(*actor).expect("actor has no position");
         match *action_state {
             ActionState::Requested => {
@@ -106,7 +120,7 @@ pub fn move_to_player_action_system(
                 let distance = delta.length();
 
                 if distance > FAR_MAX_DISTANCE {
-                    let vx = get_speed(&animator, &notice) * facing_direction;
+                    let vx = enemy.speed(&animator, notice) * facing_direction;
                     if controller.is_airborne().unwrap() {
                         controller.basis(TnuaBuiltinWalk {
                             desired_velocity: Vec3::new(vel.x, 0., 0.),
@@ -127,7 +141,7 @@ pub fn move_to_player_action_system(
                         });
                     }
                 } else if distance > MID_FAR_MAX_DISTANCE {
-                    let vx = 4.0 * get_speed(&animator, &notice) * facing_direction;
+                    let vx = 4.0 * enemy.speed(&animator, notice) * facing_direction;
                     if controller.is_airborne().unwrap() {
                         controller.basis(TnuaBuiltinWalk {
                             desired_velocity: Vec3::new(vel.x, 0., 0.),
@@ -148,7 +162,7 @@ pub fn move_to_player_action_system(
                         });
                     }
                 } else if distance > MID_MAX_DISTANCE {
-                    let vx = 3.0 * get_speed(&animator, &notice) * facing_direction;
+                    let vx = 3.0 * enemy.speed(&animator, notice) * facing_direction;
                     if controller.is_airborne().unwrap() {
                         controller.basis(TnuaBuiltinWalk {
                             desired_velocity: Vec3::new(vel.x, 0., 0.),
@@ -206,6 +220,7 @@ pub struct Attack;
 pub fn attack_action_system(
     mut enemy_query: Query<(&Transform, &mut Animator, &mut TnuaController), Without<Player>>,
     player_pos: Single<&Transform, With<Player>>,
+    mut rng: ResMut<GameRng>,
     mut query: Query<(&Actor, &mut ActionState, &Attack, &ActionSpan)>,
 ) {
     for (Actor(actor), mut state, _attack, span) in &mut query {
@@ -237,8 +252,7 @@ pub fn attack_action_system(
                     animator.set_trigger("attack");
                     *state = ActionState::Executing;
                 } else {
-                    let mut rng = rand::rng();
-                    let random: f32 = rng.random();
+                    let random: f32 = rng.rng.random();
                     if random > 0.5 {
                         animator.set_trigger("boom");
                         *state = ActionState::Executing;
@@ -288,7 +302,8 @@ pub fn attack_action_system(
                     });
                 }
                 if !animator.is_active("attack") &&
-                   !animator.is_active("boom")
+                   !animator.is_active("boom") &&
+                   !animator.in_state("BoomPrep".to_string())
                  {
                     *state = ActionState::Success;
                 }
@@ -300,6 +315,123 @@ pub fn attack_action_system(
         }
     }
 }
+/// 火球冷却，剩余秒数
+#[derive(Component, Debug, Default, Reflect)]
+pub struct FireballCooldown(pub f32);
+
+fn fireball_cooldown_system(time: Res<Time>, mut query: Query<&mut FireballCooldown>) {
+    for mut cooldown in &mut query {
+        cooldown.0 = (cooldown.0 - time.delta_secs()).max(0.);
+    }
+}
+
+/// 站定蓄力后向玩家投掷火球
+#[derive(Clone, Component, Debug, ActionBuilder, Reflect)]
+pub struct ThrowFireball {
+    /// 一次投掷的火球数量，呈扇形散开
+    count: u32,
+    /// 投掷后的冷却
+    cooldown: f32,
+    timer: f32,
+}
+
+impl ThrowFireball {
+    pub fn new(count: u32, cooldown: f32) -> Self {
+        Self { count, cooldown, timer: 0. }
+    }
+}
+
+pub fn throw_fireball_action_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    player_pos: Single<&Transform, With<Player>>,
+    mut enemy_query: Query<
+        (&Transform, &mut Animator, &mut TnuaController, &mut FireballCooldown),
+        Without<Player>,
+    >,
+    mut query: Query<(&Actor, &mut ActionState, &mut ThrowFireball, &ActionSpan)>,
+) {
+    for (Actor(actor), mut state, mut throw, span) in &mut query {
+        let _guard = span.span().enter();
+
+        let (actor_pos, mut animator, mut controller, mut cooldown) = enemy_query
+            .get_mut(*actor)
+            .expect("actor has no fireball cooldown");
+        let delta = (player_pos.translation - actor_pos.translation).truncate();
+
+        match *state {
+            ActionState::Requested => {
+                throw.timer = 0.;
+                controller.basis(TnuaBuiltinWalk {
+                    desired_velocity: Vec3::ZERO,
+                    float_height: FLOAT_HEIGHT,
+                    air_acceleration: 600.,
+                    acceleration: 600.,
+                    max_slope: float_consts::FRAC_PI_4,
+                    ..Default::default()
+                });
+                animator.set_bool("fireball_prep", true);
+                *state = ActionState::Executing;
+            }
+            ActionState::Executing => {
+                let facing_direction = delta.x.signum();
+                if facing_direction != 0. {
+                    animator.set_float("facing_direction", facing_direction);
+                }
+                throw.timer += time.delta_secs();
+                if throw.timer < FIREBALL_WINDUP {
+                    continue;
+                }
+                let position = actor_pos.translation.truncate()
+                    + Vec2::new(FIREBALL_OFFSET.x * facing_direction, FIREBALL_OFFSET.y);
+                let aim = player_pos.translation.truncate() - position;
+                for i in 0..throw.count {
+                    let angle = (i as f32 - (throw.count as f32 - 1.) / 2.) * FIREBALL_SPREAD;
+                    spawn_projectile(
+                        &mut commands,
+                        &asset_server,
+                        &FIREBALL,
+                        *actor,
+                        ProjectileTeam::Enemy,
+                        position,
+                        Vec2::from_angle(angle).rotate(aim),
+                    );
+                }
+                cooldown.0 = throw.cooldown;
+                animator.set_bool("fireball_prep", false);
+                *state = ActionState::Success;
+            }
+            ActionState::Cancelled => {
+                animator.set_bool("fireball_prep", false);
+                *state = ActionState::Failure;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 玩家在远处且火球冷却完毕时投掷火球
+#[derive(Clone, Component, Debug, ScorerBuilder, Reflect)]
+pub struct RangedScorer;
+
+pub fn ranged_scorer_system(
+    player_pos: Single<&Transform, With<Player>>,
+    enemies: Query<(&Transform, &Notice, &FireballCooldown)>,
+    mut query: Query<(&Actor, &mut Score), With<RangedScorer>>,
+) {
+    for (Actor(actor), mut score) in &mut query {
+        let Ok((transform, notice, cooldown)) = enemies.get(*actor) else { continue; };
+        let distance = player_pos.translation.truncate().distance(transform.translation.truncate());
+        let in_range = distance > MID_FAR_MAX_DISTANCE && distance <= FAR_MAX_DISTANCE;
+        if notice.notice > 50. && in_range && cooldown.0 <= 0. {
+            score.set(1.);
+        } else {
+            score.set(0.);
+        }
+    }
+}
+
 #[derive(Clone, Component, Debug, ScorerBuilder, Reflect)]
 pub struct NoticeScorer;
 
@@ -319,15 +451,19 @@ pub struct FireDemonBehaviourPlugin<S: States> {
 
 impl<S: States> Plugin for FireDemonBehaviourPlugin<S> {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, notice_system.run_if(in_state(self.state.clone())));
         app.add_systems(
             PreUpdate,
             (
                 attack_action_system.run_if(in_state(self.state.clone())),
-                move_to_player_action_system.run_if(in_state(self.state.clone()))
+                move_to_player_action_system.run_if(in_state(self.state.clone())),
+                throw_fireball_action_system.run_if(in_state(self.state.clone())),
             )
                 .in_set(BigBrainSet::Actions),
         );
-        app.add_systems(First, (notice_scorer_system.run_if(in_state(self.state.clone())), ));
+        app.add_systems(First, (
+            notice_scorer_system.run_if(in_state(self.state.clone())),
+            ranged_scorer_system.run_if(in_state(self.state.clone())),
+        ));
+        app.add_systems(Update, fireball_cooldown_system.run_if(in_state(self.state.clone())));
     }
 }
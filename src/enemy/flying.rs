@@ -0,0 +1,144 @@
+//! 飞行移动
+//! 飞行敌人不受重力，由行为树设置目标点，这里用转向行为（追赶、抵达、避障）计算速度。
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::animator::Animator;
+use crate::game_layer::GameLayer;
+
+/// 抵达目标时开始减速的距离
+const SLOW_RADIUS: f32 = 40.;
+/// 避障射线相对前进方向的偏角
+const WHISKER_ANGLE: f32 = 0.6;
+
+/// 飞行配置
+#[derive(Debug, Clone, Copy)]
+pub struct FlyingConfig {
+    /// 最大加速度
+    pub acceleration: f32,
+    /// 追击时悬停在玩家上方的高度
+    pub hover_height: f32,
+    /// 避障检测距离
+    pub avoid_distance: f32,
+}
+
+/// 飞行组件，行为树设置目标点与速度
+#[derive(Component, Debug, Reflect)]
+pub struct Flyer {
+    pub acceleration: f32,
+    pub hover_height: f32,
+    pub avoid_distance: f32,
+    /// 目标点
+    pub target: Option<Vec2>,
+    /// 是否在目标点减速停下
+    pub arrive: bool,
+    /// 期望速度大小
+    pub speed: f32,
+}
+
+impl Flyer {
+    pub fn new(config: &FlyingConfig) -> Self {
+        Self {
+            acceleration: config.acceleration,
+            hover_height: config.hover_height,
+            avoid_distance: config.avoid_distance,
+            target: None,
+            arrive: false,
+            speed: 0.,
+        }
+    }
+
+    /// 全速飞向目标
+    pub fn seek(&mut self, target: Vec2, speed: f32) {
+        self.target = Some(target);
+        self.arrive = false;
+        self.speed = speed;
+    }
+
+    /// 飞向目标并在目标点停下
+    pub fn arrive(&mut self, target: Vec2, speed: f32) {
+        self.target = Some(target);
+        self.arrive = true;
+        self.speed = speed;
+    }
+
+    /// 原地悬停
+    pub fn stop(&mut self) {
+        self.target = None;
+        self.speed = 0.;
+    }
+}
+
+/// 追赶：朝目标全速飞行的期望速度
+pub fn seek(position: Vec2, target: Vec2, speed: f32) -> Vec2 {
+    (target - position).normalize_or_zero() * speed
+}
+
+/// 抵达：接近目标时减速的期望速度
+pub fn arrive(position: Vec2, target: Vec2, speed: f32) -> Vec2 {
+    let delta = target - position;
+    let distance = delta.length();
+    let scale = (distance / SLOW_RADIUS).min(1.);
+    delta.normalize_or_zero() * speed * scale
+}
+
+/// 避障：沿前进方向和两侧发射射线，碰到地形时沿法线推开，越近推力越大
+pub fn avoid_obstacles(
+    spatial_query: &SpatialQuery,
+    position: Vec2,
+    heading: Vec2,
+    distance: f32,
+) -> Vec2 {
+    let Ok(forward) = Dir2::new(heading) else { return Vec2::ZERO; };
+    let filter = SpatialQueryFilter::default().with_mask(GameLayer::Ground);
+    let mut push = Vec2::ZERO;
+    for angle in [0., WHISKER_ANGLE, -WHISKER_ANGLE] {
+        let direction = Dir2::new(Vec2::from_angle(angle).rotate(*forward)).unwrap_or(forward);
+        if let Some(hit) = spatial_query.cast_ray(position, direction, distance, true, &filter) {
+            push += hit.normal * (1. - hit.distance / distance);
+        }
+    }
+    push
+}
+
+/// 根据目标点更新飞行速度
+fn steering_system(
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    mut query: Query<(&Transform, &Flyer, &mut LinearVelocity, &mut Animator)>,
+) {
+    for (transform, flyer, mut velocity, mut animator) in &mut query {
+        let position = transform.translation.truncate();
+        let mut desired = match flyer.target {
+            Some(target) if animator.get_bool("can_move") => {
+                if flyer.arrive {
+                    arrive(position, target, flyer.speed)
+                } else {
+                    seek(position, target, flyer.speed)
+                }
+            }
+            _ => Vec2::ZERO,
+        };
+        if desired != Vec2::ZERO {
+            desired += avoid_obstacles(&spatial_query, position, desired, flyer.avoid_distance) * flyer.speed;
+            desired = desired.clamp_length_max(flyer.speed);
+        }
+        let max_change = flyer.acceleration * time.delta_secs();
+        velocity.0 += (desired - velocity.0).clamp_length_max(max_change);
+
+        if flyer.target.is_some() && velocity.x.abs() > 5. {
+            animator.set_float("facing_direction", velocity.x.signum());
+        }
+    }
+}
+
+pub struct FlyingPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for FlyingPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, steering_system.run_if(in_state(self.state.clone())));
+    }
+}
@@ -1,127 +1,109 @@
 //! 飞行眼睛行为树
+//! 发现玩家后飞到玩家斜上方悬停，闪红预警后俯冲撞击，再拉开距离回到空中。
 use crate::animator::*;
-use crate::enemy::flying_eye::FlyingEyes;
+use crate::enemy::archetype::{Enemy, Notice};
+use crate::enemy::flying::Flyer;
+use crate::enemy::perception::Perception;
 use crate::player::Player;
-use avian2d::prelude::LinearVelocity;
 use bevy::prelude::*;
-use bevy_tnua::builtins::*;
-use bevy_tnua::math::*;
-use bevy_tnua::prelude::*;
 use big_brain::prelude::*;
 
-const WALK_SPEED: f32 = 20.0;
-const NOTICED_SPEED: f32 = 40.0;
+/// 悬停点相对玩家的水平偏移
+const HOVER_OFFSET_X: f32 = 40.;
+/// 到达悬停点的距离
+const HOVER_RADIUS: f32 = 10.;
+/// 俯冲前悬停的时间
+const HOVER_TIME: f32 = 0.6;
+/// 俯冲预警时间
+const TELEGRAPH_TIME: f32 = 0.5;
+/// 俯冲速度
+const DIVE_SPEED: f32 = 220.;
+/// 俯冲穿过玩家后继续前进的距离
+const DIVE_OVERSHOOT: f32 = 20.;
+/// 俯冲最长时间
+const DIVE_TIME: f32 = 0.8;
+/// 撤退时间
+const RETREAT_TIME: f32 = 0.8;
+/// 巡逻时前方目标点的距离
+const PATROL_LOOK_AHEAD: f32 = 50.;
 
-fn get_speed(animator: &Animator, notice: &Notice) -> f32 {
-    if animator.get_bool("can_move") {
-        if notice.notice > 50. {
-            return NOTICED_SPEED;
-        }
-        return WALK_SPEED;
-    }
-    0.0
-}
+/// 飞行眼睛行为树：发现玩家后悬停、俯冲、撤退，否则巡逻
+pub fn flying_thinker() -> ThinkerBuilder {
+    let move_and_attack = Steps::build()
+        .label("MoveAndAttack")
+        .step(MoveToPlayer::default())
+        .step(DiveBomb::default())
+        .step(Retreat::default());
 
-#[derive(Component, Reflect, Debug)]
-pub struct Notice {
-    pub add_per_sec: f32,
-    pub sub_per_sec: f32,
-    pub notice: f32,
-}
+    let patrol = Steps::build().label("Patrol").step(Patrol);
 
-impl Notice {
-    pub fn new(notice: f32, add_per_sec: f32, sub_per_sec: f32) -> Self {
-        Self {
-            notice,
-            add_per_sec,
-            sub_per_sec,
-        }
-    }
+    Thinker::build()
+        .label("Thinker")
+        .picker(Highest)
+        .when(NoticeScorer, move_and_attack)
+        .when(PatrolScorer, patrol)
 }
 
-pub fn notice_system(time: Res<Time>, mut query: Query<(&mut Notice, &Animator), With<FlyingEyes>>) {
-    for (mut notice, animator) in &mut query {
-        if animator.is_active("hit") {
-            notice.notice = 100.;
-        }
-        if animator.get_bool("is_noticing") {
-            notice.notice += notice.add_per_sec * time.delta_secs();
-        } else {
-            notice.notice -= notice.sub_per_sec * time.delta_secs();
-        }
-
-        if notice.notice >= 100. {
-            notice.notice = 100.;
-        }
-
-        if notice.notice <= 0. {
-            notice.notice = 0.
-        }
-    }
+/// 飞到玩家斜上方悬停
+#[derive(Clone, Component, Reflect, Debug, Default, ActionBuilder)]
+pub struct MoveToPlayer {
+    /// 已经悬停的时间
+    hover_time: f32,
 }
 
-#[derive(Clone, Component, Reflect, Debug, ActionBuilder)]
-pub struct MoveToPlayer;
-
-const MAX_DISTANCE: f32 = 30.;
-
 pub fn move_to_player_action_system(
+    time: Res<Time>,
     player_pos: Single<&Transform, With<Player>>,
     mut actor_query: Query<
         (
             &Transform,
-            &mut TnuaController,
-            &mut Animator,
+            &mut Flyer,
+            &Animator,
             &Notice,
+            &Enemy,
+            &Perception,
         ),
         Without<Player>,
     >,
-    mut action_query: Query<(&Actor, &mut ActionState, &MoveToPlayer, &ActionSpan)>,
+    mut action_query: Query<(&Actor, &mut ActionState, &mut MoveToPlayer, &ActionSpan)>,
 ) {
-    for (Actor(actor), mut action_state, _move_to, span) in &mut action_query {
+    for (Actor(actor), mut action_state, mut move_to, span) in &mut action_query {
         let _guard = span.span().enter();
-        let (actor_pos, mut controller, mut animator, notice) =
+        let (actor_pos, mut flyer, animator, notice, enemy, perception) =
             actor_query.get_mut(*actor).expect("actor has no position");
         match *action_state {
             ActionState::Requested => {
+                move_to.hover_time = 0.;
                 *action_state = ActionState::Executing;
             }
             ActionState::Executing => {
-                let delta = (player_pos.translation - actor_pos.translation).truncate();
-                let facing_direction = if delta.x.trunc() > 0. {
-                    1.
-                } else if delta.x.trunc() < 0. {
-                    -1.
-                } else {
-                    0.
+                // 看不到玩家时前往最后出现的位置，没有记忆则放弃
+                let Some(target) = perception.target(player_pos.translation.truncate()) else {
+                    flyer.stop();
+                    *action_state = ActionState::Failure;
+                    continue;
                 };
-                if facing_direction != 0. {
-                    animator.set_float("facing_direction", facing_direction);
-                }
-                let distance = delta.length();
-                if distance > MAX_DISTANCE {
-                    let vx = get_speed(&animator, &notice) * facing_direction;
-                    controller.basis(TnuaBuiltinWalk {
-                        desired_velocity: Vec3::new(vx, 0., 0.),
-                        float_height: 18.,
-                        air_acceleration: 600.,
-                        acceleration: 600.,
-                        max_slope: float_consts::FRAC_PI_4,
-                        ..Default::default()
-                    });
+                let position = actor_pos.translation.truncate();
+                let side = if position.x >= target.x { 1. } else { -1. };
+                let hover_point = target + Vec2::new(side * HOVER_OFFSET_X, flyer.hover_height);
+                flyer.arrive(hover_point, enemy.speed(animator, notice));
+
+                if position.distance(hover_point) < HOVER_RADIUS {
+                    move_to.hover_time += time.delta_secs();
                 } else {
-                    controller.basis(TnuaBuiltinWalk {
-                        desired_velocity: Vec3::new(0., 0., 0.),
-                        float_height: 18.,
-                        air_acceleration: 600.,
-                        acceleration: 600.,
-                        max_slope: float_consts::FRAC_PI_4,
-                        ..Default::default()
-                    });
-                    *action_state = ActionState::Success;
+                    move_to.hover_time = 0.;
+                }
+                if move_to.hover_time >= HOVER_TIME {
+                    // 到达记忆中的位置仍看不到玩家，搜寻失败
+                    *action_state = if perception.can_see_player {
+                        ActionState::Success
+                    } else {
+                        ActionState::Failure
+                    };
                 }
             }
             ActionState::Cancelled => {
+                flyer.stop();
                 *action_state = ActionState::Failure;
             }
             _ => {}
@@ -129,68 +111,119 @@ pub fn move_to_player_action_system(
     }
 }
 
-#[derive(Clone, Component, Reflect, Debug, ActionBuilder)]
-pub struct Attack;
+/// 预警后俯冲撞击玩家
+#[derive(Clone, Component, Reflect, Debug, Default, ActionBuilder)]
+pub struct DiveBomb {
+    timer: f32,
+    /// 俯冲终点，预警结束时锁定
+    target: Option<Vec2>,
+}
 
-pub fn attack_action_system(
-    mut enemy_query: Query<(&Transform, &mut Animator, &mut TnuaController, &LinearVelocity), Without<Player>>,
+pub fn dive_bomb_action_system(
+    time: Res<Time>,
     player_pos: Single<&Transform, With<Player>>,
-    mut query: Query<(&Actor, &mut ActionState, &Attack, &ActionSpan)>,
+    mut enemy_query: Query<(&Transform, &mut Flyer, &mut Animator), Without<Player>>,
+    mut query: Query<(&Actor, &mut ActionState, &mut DiveBomb, &ActionSpan)>,
 ) {
-    for (Actor(actor), mut state, _attack, span) in &mut query {
+    for (Actor(actor), mut state, mut dive, span) in &mut query {
         let _guard = span.span().enter();
 
-        let (actor_pos, mut animator, mut controller, vel) = enemy_query
+        let (actor_pos, mut flyer, mut animator) = enemy_query
             .get_mut(*actor)
-            .expect("actor did't have notice");
+            .expect("actor has no flyer");
+        let position = actor_pos.translation.truncate();
+        let player = player_pos.translation.truncate();
 
         match *state {
             ActionState::Requested => {
-                let delta = (player_pos.translation - actor_pos.translation).truncate();
-                let distance = delta.length();
-                let facing_direction = if delta.x.trunc() > 0. {
-                    1.
-                } else if delta.x.trunc() < 0. {
-                    -1.
-                } else {
-                    0.
-                };
-                if distance < MAX_DISTANCE {
-                    animator.set_trigger("attack");
-                    
-                    controller.basis(TnuaBuiltinWalk {
-                        desired_velocity: Vec3::new(360. * facing_direction, 0., 0.),
-                        float_height: 18.,
-                        air_acceleration: 600.,
-                        acceleration: 600.,
-                        max_slope: float_consts::FRAC_PI_4,
-                        ..Default::default()
-                    });
-                    *state = ActionState::Executing;
-                } else {
-                    *state = ActionState::Failure;
+                // 原地悬停并进入预警状态
+                dive.timer = 0.;
+                dive.target = None;
+                flyer.stop();
+                animator.set_bool("dive_prep", true);
+                let facing_direction = (player.x - position.x).signum();
+                if facing_direction != 0. {
+                    animator.set_float("facing_direction", facing_direction);
                 }
+                *state = ActionState::Executing;
             }
             ActionState::Executing => {
-                if !animator.in_state("Attack".to_string()) {
+                dive.timer += time.delta_secs();
+                match dive.target {
+                    None if dive.timer >= TELEGRAPH_TIME => {
+                        // 锁定玩家当前位置，穿过玩家继续俯冲一段
+                        let direction = (player - position).normalize_or_zero();
+                        let target = player + direction * DIVE_OVERSHOOT;
+                        dive.target = Some(target);
+                        animator.set_bool("dive_prep", false);
+                        animator.set_trigger("attack");
+                        flyer.seek(target, DIVE_SPEED);
+                    }
+                    None => {}
+                    Some(target) => {
+                        if position.distance(target) < HOVER_RADIUS
+                            || dive.timer >= TELEGRAPH_TIME + DIVE_TIME
+                        {
+                            flyer.stop();
+                            *state = ActionState::Success;
+                        }
+                    }
+                }
+            }
+            ActionState::Cancelled => {
+                animator.set_bool("dive_prep", false);
+                flyer.stop();
+                *state = ActionState::Failure;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 俯冲后远离玩家回到空中
+#[derive(Clone, Component, Reflect, Debug, Default, ActionBuilder)]
+pub struct Retreat {
+    timer: f32,
+}
+
+pub fn retreat_action_system(
+    time: Res<Time>,
+    player_pos: Single<&Transform, With<Player>>,
+    mut enemy_query: Query<(&Transform, &mut Flyer, &Enemy), Without<Player>>,
+    mut query: Query<(&Actor, &mut ActionState, &mut Retreat, &ActionSpan)>,
+) {
+    for (Actor(actor), mut state, mut retreat, span) in &mut query {
+        let _guard = span.span().enter();
+
+        let (actor_pos, mut flyer, enemy) = enemy_query
+            .get_mut(*actor)
+            .expect("actor has no flyer");
+
+        match *state {
+            ActionState::Requested => {
+                retreat.timer = 0.;
+                let position = actor_pos.translation.truncate();
+                let player = player_pos.translation.truncate();
+                let away = if position.x >= player.x { 1. } else { -1. };
+                let target = player + Vec2::new(away * HOVER_OFFSET_X * 2., flyer.hover_height);
+                flyer.arrive(target, enemy.config.noticed_speed);
+                *state = ActionState::Executing;
+            }
+            ActionState::Executing => {
+                retreat.timer += time.delta_secs();
+                if retreat.timer >= RETREAT_TIME {
                     *state = ActionState::Success;
                 }
-                controller.basis(TnuaBuiltinWalk {
-                    desired_velocity: Vec3::new(vel.x, 0., 0.),
-                    float_height: 18.,
-                    air_acceleration: 600.,
-                    acceleration: 600.,
-                    max_slope: float_consts::FRAC_PI_4,
-                    ..Default::default()
-                });
             }
             ActionState::Cancelled => {
+                flyer.stop();
                 *state = ActionState::Failure;
             }
             _ => {}
         }
     }
 }
+
 #[derive(Clone, Component, Reflect, Debug, ScorerBuilder)]
 pub struct NoticeScorer;
 
@@ -211,9 +244,11 @@ pub struct Patrol;
 pub fn patrol_action_system(
     mut enemy_query: Query<
         (
-            &mut TnuaController,
+            &Transform,
+            &mut Flyer,
             &mut Animator,
             &Notice,
+            &Enemy,
         ),
         Without<Player>,
     >,
@@ -222,7 +257,7 @@ pub fn patrol_action_system(
     for (Actor(actor), mut state, _patrol, span) in &mut query {
         let _guard = span.span().enter();
 
-        let (mut controller, mut animator, notice) = enemy_query
+        let (transform, mut flyer, mut animator, notice, enemy) = enemy_query
             .get_mut(*actor)
             .expect("actor did't have notice");
 
@@ -231,19 +266,15 @@ pub fn patrol_action_system(
                 *state = ActionState::Executing;
             }
             ActionState::Executing => {
-                let facing_direction = animator.get_float("facing_direction");
+                let mut facing_direction = animator.get_float("facing_direction");
                 if animator.get_bool("is_on_wall") {
-                    animator.set_float("facing_direction", -facing_direction);
+                    facing_direction = -facing_direction;
+                    animator.set_float("facing_direction", facing_direction);
                 }
-                let vx = facing_direction * get_speed(&*animator, &notice);
-                controller.basis(TnuaBuiltinWalk {
-                    desired_velocity: Vec3::new(vx, 0., 0.),
-                    float_height: 18.,
-                    air_acceleration: 600.,
-                    acceleration: 600.,
-                    max_slope: float_consts::FRAC_PI_4,
-                    ..Default::default()
-                });
+                // 保持高度水平巡逻
+                let target = transform.translation.truncate()
+                    + Vec2::new(facing_direction * PATROL_LOOK_AHEAD, 0.);
+                flyer.seek(target, enemy.speed(&animator, notice));
                 *state = ActionState::Success;
             }
             ActionState::Cancelled => {
@@ -274,18 +305,18 @@ pub struct FlyingEyesBehaviourPlugin<S: States> {
 
 impl<S: States> Plugin for FlyingEyesBehaviourPlugin<S> {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, notice_system.run_if(in_state(self.state.clone())));
         app.add_systems(
             PreUpdate,
             (
-                attack_action_system.run_if(in_state(self.state.clone())),
+                dive_bomb_action_system.run_if(in_state(self.state.clone())),
+                retreat_action_system.run_if(in_state(self.state.clone())),
                 move_to_player_action_system.run_if(in_state(self.state.clone())),
                 patrol_action_system.run_if(in_state(self.state.clone())),
             )
                 .in_set(BigBrainSet::Actions),
         );
         app.add_systems(First, (
-            notice_scorer_system.run_if(in_state(self.state.clone())), 
+            notice_scorer_system.run_if(in_state(self.state.clone())),
             patrol_scorer_system.run_if(in_state(self.state.clone()))
         ));
     }
@@ -0,0 +1,439 @@
+//! 地面敌人通用行为树：沿路线巡逻、发现玩家后沿导航图靠近并攻击
+use crate::animator::*;
+use crate::enemy::archetype::{Enemy, Notice};
+use crate::enemy::coordination::Engagement;
+use crate::enemy::patrol::PatrolRoute;
+use crate::enemy::perception::Perception;
+use crate::navigation::{NavAgent, NavGraph, NavLink};
+use crate::player::Player;
+use bevy::prelude::*;
+use bevy_tnua::builtins::*;
+use bevy_tnua::math::*;
+use bevy_tnua::prelude::*;
+use big_brain::prelude::*;
+
+#[derive(Clone, Component, Reflect, Debug, ActionBuilder)]
+pub struct MoveToPlayer;
+
+const MAX_DISTANCE: f32 = 30.;
+/// 重新寻路的间隔
+const REPATH_INTERVAL: f32 = 0.5;
+/// 到达路点的水平距离
+const WAYPOINT_RADIUS: f32 = 8.;
+/// 到达路点的竖直距离
+const WAYPOINT_HEIGHT: f32 = 12.;
+/// 跳跃高度
+const JUMP_HEIGHT: f32 = 400.;
+
+pub fn move_to_player_action_system(
+    time: Res<Time>,
+    nav: Res<NavGraph>,
+    player_pos: Single<&Transform, With<Player>>,
+    mut actor_query: Query<
+        (
+            &Enemy,
+            &Transform,
+            &mut TnuaController,
+            &mut Animator,
+            &Notice,
+            &Perception,
+            &mut NavAgent,
+            Option<&mut Engagement>,
+        ),
+        Without<Player>,
+    >,
+    mut action_query: Query<(&Actor, &mut ActionState, &MoveToPlayer, &ActionSpan)>,
+) {
+    for (Actor(actor), mut action_state, _move_to, span) in &mut action_query {
+        let _guard = span.span().enter();
+        let (enemy, actor_pos, mut controller, mut animator, notice, perception, mut agent, mut engagement) =
+            actor_query.get_mut(*actor).expect("actor has no position");
+        match *action_state {
+            ActionState::Requested => {
+                agent.repath_timer = 0.;
+                *action_state = ActionState::Executing;
+            }
+            ActionState::Executing => {
+                // 看不到玩家时前往最后出现的位置，没有记忆则放弃
+                let Some(target) = perception.target(player_pos.translation.truncate()) else {
+                    if let Some(engagement) = engagement.as_mut() {
+                        engagement.release();
+                    }
+                    *action_state = ActionState::Failure;
+                    continue;
+                };
+                let position = actor_pos.translation.truncate();
+                // 看到玩家时前往协调器分配的站位：持有令牌时包抄，否则在外围等待
+                let (slot, waiting) = match engagement.as_deref() {
+                    Some(engagement) if engagement.engaged && perception.can_see_player => {
+                        (target + Vec2::X * engagement.offset, engagement.is_waiting())
+                    }
+                    _ => (target, false),
+                };
+                agent.repath_timer -= time.delta_secs();
+                if agent.repath_timer <= 0. {
+                    agent.repath_timer = REPATH_INTERVAL;
+                    match nav.find_path(position, slot) {
+                        Some(path) => agent.path = path,
+                        None => {
+                            // 没有通往玩家的路线，让出令牌给其他敌人
+                            agent.path.clear();
+                            if let Some(engagement) = engagement.as_mut() {
+                                engagement.release();
+                            }
+                        }
+                    }
+                }
+                // 跳过已经到达的路点
+                let feet = position - Vec2::Y * enemy.config.float_height;
+                while let Some(step) = agent.path.first() {
+                    if (step.position.x - feet.x).abs() < WAYPOINT_RADIUS
+                        && (step.position.y - feet.y).abs() < WAYPOINT_HEIGHT
+                    {
+                        agent.path.remove(0);
+                    } else {
+                        break;
+                    }
+                }
+                // 朝下一个路点移动，路线走完后直接朝目标移动
+                let (waypoint, jump) = match agent.path.first() {
+                    Some(step) => (step.position, step.link == NavLink::Jump),
+                    None => (slot, false),
+                };
+                if waiting && (slot.x - position.x).abs() < WAYPOINT_RADIUS {
+                    // 到达等待站位，面向玩家原地等待令牌
+                    agent.path.clear();
+                    let facing_direction = (target.x - position.x).signum();
+                    if facing_direction != 0. {
+                        animator.set_float("facing_direction", facing_direction);
+                    }
+                    controller.basis(TnuaBuiltinWalk {
+                        desired_velocity: Vec3::new(0., 0., 0.),
+                        float_height: enemy.config.float_height,
+                        air_acceleration: 600.,
+                        acceleration: 600.,
+                        max_slope: float_consts::FRAC_PI_4,
+                        ..Default::default()
+                    });
+                    continue;
+                }
+                let delta = target - position;
+                let steer = waypoint - position;
+                let facing_direction = if steer.x.trunc() > 0. {
+                    1.
+                } else if steer.x.trunc() < 0. {
+                    -1.
+                } else {
+                    0.
+                };
+                if facing_direction != 0. {
+                    animator.set_float("facing_direction", facing_direction);
+                }
+                // 搜寻时只需要水平方向到达
+                let distance = if perception.can_see_player { delta.length() } else { delta.x.abs() };
+                if waiting || distance > MAX_DISTANCE {
+                    let vx = enemy.speed(&animator, &notice) * facing_direction;
+                    controller.basis(TnuaBuiltinWalk {
+                        desired_velocity: Vec3::new(vx, 0., 0.),
+                        float_height: enemy.config.float_height,
+                        air_acceleration: 600.,
+                        acceleration: 600.,
+                        max_slope: float_consts::FRAC_PI_4,
+                        ..Default::default()
+                    });
+                    if jump && animator.get_bool("can_move") {
+                        controller.action(TnuaBuiltinJump {
+                            height: JUMP_HEIGHT,
+                            ..Default::default()
+                        });
+                    }
+                } else {
+                    agent.path.clear();
+                    controller.basis(TnuaBuiltinWalk {
+                        desired_velocity: Vec3::new(0., 0., 0.),
+                        float_height: enemy.config.float_height,
+                        air_acceleration: 600.,
+                        acceleration: 600.,
+                        max_slope: float_consts::FRAC_PI_4,
+                        ..Default::default()
+                    });
+                    // 到达记忆中的位置仍看不到玩家，搜寻失败
+                    *action_state = if perception.can_see_player {
+                        ActionState::Success
+                    } else {
+                        if let Some(engagement) = engagement.as_mut() {
+                            engagement.release();
+                        }
+                        ActionState::Failure
+                    };
+                }
+            }
+            ActionState::Cancelled => {
+                if let Some(engagement) = engagement.as_mut() {
+                    engagement.release();
+                }
+                *action_state = ActionState::Failure;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, Component, Reflect, Debug, ActionBuilder)]
+pub struct Attack;
+
+pub fn attack_action_system(
+    mut enemy_query: Query<(&Transform, &mut Animator, Option<&mut Engagement>), Without<Player>>,
+    player_pos: Single<&Transform, With<Player>>,
+    mut query: Query<(&Actor, &mut ActionState, &Attack, &ActionSpan)>,
+) {
+    for (Actor(actor), mut state, _attack, span) in &mut query {
+        let _guard = span.span().enter();
+
+        let (actor_pos, mut animator, mut engagement) = enemy_query
+            .get_mut(*actor)
+            .expect("actor did't have notice");
+
+        match *state {
+            ActionState::Requested => {
+                let delta = (player_pos.translation - actor_pos.translation).truncate();
+                let distance = delta.length();
+                // 参与协调的敌人必须持有攻击令牌
+                let has_token = engagement.as_ref().is_none_or(|e| !e.engaged || e.has_token);
+
+                if distance < MAX_DISTANCE && has_token {
+                    animator.set_trigger("attack");
+                    *state = ActionState::Executing;
+                } else {
+                    *state = ActionState::Failure;
+                }
+            }
+            ActionState::Executing => {
+                if !animator.is_active("attack") {
+                    if let Some(engagement) = engagement.as_mut() {
+                        engagement.release();
+                    }
+                    *state = ActionState::Success;
+                }
+            }
+            ActionState::Cancelled => {
+                if let Some(engagement) = engagement.as_mut() {
+                    engagement.release();
+                }
+                *state = ActionState::Failure;
+            }
+            _ => {}
+        }
+    }
+}
+/// 远离玩家一段时间
+#[derive(Clone, Component, Reflect, Debug, ActionBuilder)]
+pub struct Flee {
+    /// 逃跑时间
+    duration: f32,
+    timer: f32,
+}
+
+impl Flee {
+    pub fn new(duration: f32) -> Self {
+        Self { duration, timer: 0. }
+    }
+}
+
+pub fn flee_action_system(
+    time: Res<Time>,
+    player_pos: Single<&Transform, With<Player>>,
+    mut enemy_query: Query<
+        (&Enemy, &Transform, &mut TnuaController, &mut Animator, &Notice, Option<&mut Engagement>),
+        Without<Player>,
+    >,
+    mut query: Query<(&Actor, &mut ActionState, &mut Flee, &ActionSpan)>,
+) {
+    for (Actor(actor), mut state, mut flee, span) in &mut query {
+        let _guard = span.span().enter();
+
+        let (enemy, transform, mut controller, mut animator, notice, engagement) = enemy_query
+            .get_mut(*actor)
+            .expect("actor did't have notice");
+
+        match *state {
+            ActionState::Requested => {
+                flee.timer = 0.;
+                // 逃跑时不再占用攻击令牌
+                if let Some(mut engagement) = engagement {
+                    engagement.release();
+                }
+                *state = ActionState::Executing;
+            }
+            ActionState::Executing => {
+                flee.timer += time.delta_secs();
+                let away = if transform.translation.x >= player_pos.translation.x { 1. } else { -1. };
+                // 被墙壁或悬崖挡住时停下
+                let blocked = animator.get_bool("is_grounded")
+                    && (animator.get_bool("is_on_wall") || animator.get_bool("is_at_ledge"))
+                    && animator.get_float("facing_direction") == away;
+                animator.set_float("facing_direction", away);
+                let vx = if blocked { 0. } else { away * enemy.speed(&animator, &notice) };
+                controller.basis(TnuaBuiltinWalk {
+                    desired_velocity: Vec3::new(vx, 0., 0.),
+                    float_height: enemy.config.float_height,
+                    air_acceleration: 600.,
+                    acceleration: 600.,
+                    max_slope: float_consts::FRAC_PI_4,
+                    ..Default::default()
+                });
+                if flee.timer >= flee.duration {
+                    *state = ActionState::Success;
+                }
+            }
+            ActionState::Cancelled => {
+                *state = ActionState::Failure;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, Component, Reflect, Debug, ScorerBuilder)]
+pub struct NoticeScorer;
+
+pub fn notice_scorer_system(
+    notices: Query<&Notice>,
+    mut query: Query<(&Actor, &mut Score), With<NoticeScorer>>,
+) {
+    for (Actor(actor), mut score) in &mut query {
+        if let Ok(notice) = notices.get(*actor) {
+            score.set(notice.notice / 100.);
+        }
+    }
+}
+
+#[derive(Clone, Component, Reflect, Debug, ActionBuilder)]
+pub struct Patrol;
+
+pub fn patrol_action_system(
+    time: Res<Time>,
+    mut enemy_query: Query<
+        (
+            &Enemy,
+            &Transform,
+            &mut TnuaController,
+            &mut Animator,
+            &Notice,
+            Option<&mut PatrolRoute>,
+        ),
+        Without<Player>,
+    >,
+    mut query: Query<(&Actor, &mut ActionState, &Patrol, &ActionSpan)>,
+) {
+    for (Actor(actor), mut state, _patrol, span) in &mut query {
+        let _guard = span.span().enter();
+
+        let (enemy, transform, mut controller, mut animator, notice, route) = enemy_query
+            .get_mut(*actor)
+            .expect("actor did't have notice");
+
+        match *state {
+            ActionState::Requested => {
+                *state = ActionState::Executing;
+            }
+            ActionState::Executing => {
+                let mut facing_direction = animator.get_float("facing_direction");
+                let blocked = animator.get_bool("is_grounded")
+                    && (animator.get_bool("is_on_wall") || animator.get_bool("is_at_ledge"));
+                let mut moving = true;
+                if let Some(mut route) = route {
+                    if route.is_waiting() {
+                        // 停顿，张望时在停顿一半时回头
+                        let half = route.pause / 2.;
+                        let before = route.wait_timer;
+                        route.wait_timer -= time.delta_secs();
+                        if route.look_around && before > half && route.wait_timer <= half {
+                            facing_direction = -facing_direction;
+                        }
+                        moving = false;
+                    } else {
+                        let dx = route.target().x - transform.translation.x;
+                        if dx.abs() < WAYPOINT_RADIUS || (blocked && dx * facing_direction > 0.) {
+                            // 到达路点，或者前方是墙壁、悬崖无法到达
+                            route.advance();
+                            moving = false;
+                        } else {
+                            facing_direction = dx.signum();
+                        }
+                    }
+                } else if blocked {
+                    facing_direction = -facing_direction;
+                }
+                animator.set_float("facing_direction", facing_direction);
+                let vx = if moving { facing_direction * enemy.speed(&animator, &notice) } else { 0. };
+                controller.basis(TnuaBuiltinWalk {
+                    desired_velocity: Vec3::new(vx, 0., 0.),
+                    float_height: enemy.config.float_height,
+                    air_acceleration: 600.,
+                    acceleration: 600.,
+                    max_slope: float_consts::FRAC_PI_4,
+                    ..Default::default()
+                });
+                *state = ActionState::Success;
+            }
+            ActionState::Cancelled => {
+                *state = ActionState::Failure;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, Component, Reflect, Debug, ScorerBuilder)]
+pub struct PatrolScorer;
+
+pub fn patrol_scorer_system(
+    notices: Query<&Notice>,
+    mut query: Query<(&Actor, &mut Score), With<PatrolScorer>>,
+) {
+    for (Actor(actor), mut score) in &mut query {
+        if let Ok(notice) = notices.get(*actor) {
+            score.set(1. - notice.notice / 100.);
+        }
+    }
+}
+
+/// 地面敌人的默认行为树：发现玩家时靠近攻击，否则巡逻
+pub fn ground_thinker() -> ThinkerBuilder {
+    let move_and_attack = Steps::build()
+        .label("MoveAndAttack")
+        .step(MoveToPlayer)
+        .step(Attack);
+
+    let patrol = Steps::build().label("Patrol").step(Patrol);
+
+    Thinker::build()
+        .label("Thinker")
+        .picker(Highest)
+        .when(NoticeScorer, move_and_attack)
+        .when(PatrolScorer, patrol)
+}
+
+pub struct GroundBehaviourPlugin<S: States> {
+    pub state: S
+}
+
+impl<S: States> Plugin for GroundBehaviourPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            (
+                attack_action_system.run_if(in_state(self.state.clone())),
+                move_to_player_action_system.run_if(in_state(self.state.clone())),
+                patrol_action_system.run_if(in_state(self.state.clone())),
+                flee_action_system.run_if(in_state(self.state.clone())),
+            )
+                .in_set(BigBrainSet::Actions),
+        );
+        app.add_systems(First, (
+            notice_scorer_system.run_if(in_state(self.state.clone())), 
+            patrol_scorer_system.run_if(in_state(self.state.clone()))
+        ));
+    }
+}
@@ -1,21 +1,22 @@
 //! 武师boss
 
-use avian2d::prelude::*;
 use bevy::prelude::*;
-use big_brain::prelude::*;
-use game_derive::enter;
 use game_derive::exit;
 
 use crate::animator::Condition;
+use crate::arena::{is_defeated, spawn_arena, ArenaBoss, ArenaReset, BossArena, BossDefeated};
+use crate::boss_bar::Boss;
 use crate::animator::*;
-use crate::blocks::MartialBlocks;
-use crate::controller::ControllerBundle;
-use crate::damagable::{check_hitbox, Damagable, HasHitbox, HitBox, HitboxOf};
-use crate::game_layer::GameLayer;
-use crate::healthbar::Hint;
-use crate::hint::{HintEntity, ItemHint};
-use crate::items::{item_canpick_observer, item_cantpick_observer, ItemList, NotpickedItems};
-use crate::physics::PhysicsBundle;
+use crate::enemy::archetype::{
+    __attack_enter_handler, __attack_exit_handler, __stun_enter_handler, __stun_exit_handler,
+    spawn_enemy, CapsuleConfig, Enemy, EnemyConfig, HitboxConfig, Notice, NoticeConfig,
+};
+use crate::enemy::perception::PerceptionConfig;
+use crate::enemy::telegraph::{TelegraphConfig, TelegraphCue};
+use crate::enemy::phase::{BossPhase, BossPhases, PhaseTrigger};
+use crate::hint::HintEntity;
+use crate::loot::{DropLoot, RespawnUnclaimed};
+use crate::world_flags::WorldFlags;
 
 mod behaviour;
 use behaviour::*;
@@ -24,93 +25,116 @@ use behaviour::*;
 #[derive(Component, Reflect)]
 struct Martial;
 
+/// 武师，视线朝背后且穿透地形，攻击后可以立刻移动
+const MARTIAL: EnemyConfig = EnemyConfig {
+    name: "Martial Hero",
+    sheet: "Art/Martial Hero/martial_sheet.png",
+    tile_size: UVec2::new(200, 200),
+    columns: 8,
+    rows: 6,
+    scale: 1.,
+    sprite_faces_left: false,
+    collider: CapsuleConfig { radius: 10., top: 15., bottom: 15. },
+    sensor_width: 19.8,
+    float_height: 26.,
+    max_health: 1500.,
+    walk_speed: 25.,
+    noticed_speed: 50.,
+    notice: NoticeConfig { add_per_sec: 60., sub_per_sec: 0., max: 80. },
+    perception: PerceptionConfig {
+        view_distance: 550.,
+        view_angle: std::f32::consts::PI,
+        hearing_radius: 300.,
+        memory: 10.,
+        through_ground: true,
+    },
+    hitboxes: &[
+        HitboxConfig {
+            state: "Attack1",
+            size: Vec2::new(60., 40.),
+            offset: Vec2::new(40., 0.),
+            damage: 35.,
+        },
+        HitboxConfig {
+            state: "Attack2",
+            size: Vec2::new(80., 40.),
+            offset: Vec2::new(40., 0.),
+            damage: 50.,
+        },
+    ],
+    // 快攻只有刀光，重击全身泛红并标出范围
+    telegraphs: &[
+        TelegraphConfig {
+            state: "Attack1Prep",
+            cues: &[TelegraphCue::Glint { offset: Vec2::new(40., 10.), color: Color::srgb(1., 0.95, 0.6) }],
+            sound: Some("Audio/SFX/10_Battle_SFX/22_Slash_04.wav"),
+        },
+        TelegraphConfig {
+            state: "Attack2Prep",
+            cues: &[
+                TelegraphCue::Flash { color: Color::srgb(1., 0.3, 0.3) },
+                TelegraphCue::Area {
+                    size: Vec2::new(80., 40.),
+                    offset: Vec2::new(40., 0.),
+                    color: Color::srgba(1., 0.2, 0.2, 0.3),
+                },
+            ],
+            sound: Some("Audio/SFX/8_Atk_Magic_SFX/45_Charge_05.wav"),
+        },
+    ],
+    body_hitbox: None,
+    flying: None,
+    halt_on_stun: false,
+    resume_after_attack: true,
+    animator: setup_animator,
+    thinker: martial_thinker,
+    profile: None,
+    loot: Some("martial"),
+};
+
+/// 武师半血后进入第二阶段，在天花板上伺机瞬移攻击
+const MARTIAL_PHASES: &[BossPhase] = &[BossPhase {
+    name: "Ceiling",
+    trigger: PhaseTrigger::Health(0.5),
+    thinker: martial_phase_two_thinker,
+    transition_time: 1.2,
+    animation: Some("hit"),
+    music: None,
+}];
+
 /// 生成敌人
 fn setup_enemy(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    flags: Res<WorldFlags>,
 ) {
-    let texture =
-        asset_server.load("Art/Martial Hero/martial_sheet.png");
-    let layout = TextureAtlasLayout::from_grid(UVec2::new(200, 200), 8, 6, None, None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
+    // 已被击败时不再生成，掉落的能力道具还没拾取则放回原处，卷轴放回起点
+    if is_defeated(&flags, "martial") {
+        commands.trigger(RespawnUnclaimed { table: "martial", position: Vec2::new(392., -137.9) });
+        return;
+    }
 
-    spawn_enemy(
+    let arena = spawn_arena(
         &mut commands,
-        Vec2::new(392., -140.),
-        texture.clone(),
-        texture_atlas_layout.clone(),
+        BossArena::new("martial", "Audio/Music/08 SAMURAI BLADE/Battle-SAMURAI.mp3"),
+        Vec2::new(22., -140.),
+        Vec2::new(16., 80.),
     );
-}
 
-/// 生成敌人
-fn spawn_enemy(
-    commands: &mut Commands,
-    position: Vec2,
-    texture: Handle<Image>,
-    texture_atlas_layout: Handle<TextureAtlasLayout>,
-) {
-    let animator = setup_animator();
-    let collider_layer = CollisionLayers::new(
-        GameLayer::Enemy,
-        [
-            GameLayer::Default,
-            GameLayer::Ground,
-            GameLayer::PlayerHitBox,
-        ],
-    );
-
-    // 第一阶段行为树
-    let phase1_combo = Steps::build()
-        .label("Phase1Combo")
-        .step(MoveToPlayer)
-        .step(Attack1)
-        .step(Attack2);
-
-    // 第二阶段行为树
-    let phase2_transition = Steps::build()
-        .label("Phase2Transition")
-        .step(PhaseTransition);
-
-    let phase2_teleport = Steps::build()
-        .label("Phase2Teleport")
-        .step(TeleportAttack);
-
-    // 主行为树
-    let thinker = Thinker::build()
-        .label("NewEnemyThinker")
-        .picker(Highest)
-        .when(HealthScorer, phase2_transition)
-        .when(PhaseTwoScorer, phase2_teleport)
-        .when(NoticeScorer, phase1_combo);
-
-    commands.spawn((
-        Sprite {
-            image: texture,
-            texture_atlas: Some(TextureAtlas {
-                layout: texture_atlas_layout,
-                index: animator.first_index,
-            }),
-            ..default()
-        },
-        Transform::from_xyz(position.x, position.y, 0.0),
+    let position = Vec2::new(392., -140.);
+    let entity = spawn_enemy(&mut commands, &asset_server, &mut texture_atlas_layouts, &MARTIAL, position);
+    let phases = BossPhases::new(MARTIAL_PHASES);
+    commands.entity(entity).insert((
         Martial,
-        ControllerBundle::new(19.8),
-        PhysicsBundle {
-            collider: Collider::capsule_endpoints(
-                10.0, 
-                Vec2::Y * 15.0, 
-                Vec2::NEG_Y * 15.0
-            ),
-            layer: collider_layer,
-            ..default()
-        },
-        Damagable::new(1500.), 
-        animator,
-        Notice::new(0.0, 60.0, 0.0),
-        HealthState::new(1500.0),
         PhaseTwoTimer::new(),
-        thinker,
+        Boss::new(MARTIAL.name, phases.health_thresholds()),
+        phases,
+        ArenaBoss {
+            arena,
+            spawn: position.extend(0.0),
+            gravity: 30.0,
+        },
     ));
 }
 
@@ -343,8 +367,8 @@ fn setup_animator() -> Animator {
             },
         ],
         loop_animation: false,
-        on_enter: Some(__attack1_enter_handler),
-        on_exit: Some(__attack1_exit_handler),
+        on_enter: Some(__attack_enter_handler),
+        on_exit: Some(__attack_exit_handler),
         ..default()
     };
 
@@ -399,8 +423,8 @@ fn setup_animator() -> Animator {
             },
         ],
         loop_animation: false,
-        on_enter: Some(__attack2_enter_handler),
-        on_exit: Some(__attack2_exit_handler),
+        on_enter: Some(__attack_enter_handler),
+        on_exit: Some(__attack_exit_handler),
         ..default()
     };
 
@@ -626,207 +650,31 @@ fn setup_animator() -> Animator {
 #[exit("death")]
 fn on_martial_death(
     mut commands: Commands,
-    martial: Single<Entity, With<Martial>>,
-    blocks: Query<Entity, With<MartialBlocks>>,
-    items: Res<ItemList>, 
+    martial: Single<(Entity, &Enemy, &Transform), With<Martial>>,
     hint: Query<Entity, With<HintEntity>>,
-    mut text: Single<&mut Text, With<Hint>>,
 ) {
-    let entity = martial.into_inner();
+    let (entity, enemy, transform) = martial.into_inner();
     for h in hint {
         commands.entity(h).despawn();
     }
-    commands.entity(entity).despawn();
+    commands.trigger(BossDefeated { boss: entity });
     // 掉落卷轴
-    commands.spawn((
-        Sprite {
-            image: items.infos.get(&String::from("MartialScroll")).unwrap().icon.clone(),
-            ..default()
-        },
-        Collider::rectangle(20.0, 20.0),
-        Transform::from_xyz(120., 39.1, 0.0),
-        ItemHint,
-        NotpickedItems { id: "MartialScroll".to_string(), num: 1 },
-        Sensor,
-        CollisionEventsEnabled,
-        CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player])
-    )).observe(item_cantpick_observer).observe(item_canpick_observer);
-    for block in blocks {
-        commands.entity(block).despawn();
-    }
-    text.0 = "Brave Hero... \nGo Back to Where You Start...".to_string();
-} 
-
-#[enter("attack1")]
-fn on_attack1_enter(
-    mut commands: Commands
-) {
-    let entity = trigger.entity;
-    commands.spawn((
-            Collider::rectangle(60., 40.),
-            Transform::from_xyz(40., 0., 0.),
-            Sensor,
-            HitBox { damage: 35. },
-            CollisionLayers::new(GameLayer::EnemyHitBox, [GameLayer::Player]),
-            CollisionEventsEnabled,
-            ChildOf(entity),
-            HitboxOf(entity),
-        )).observe(check_hitbox);
-}
-
-#[exit("attack1")]
-fn on_attack1_exit(
-    mut commands: Commands,
-    mut enemy: Query<(&HasHitbox, &mut Animator), With<Martial>>,
-) {
-    let entity = trigger.entity;
-    let (hitboxes, mut animator) = enemy.get_mut(entity).unwrap();
-    let vec = (**hitboxes).clone();
-    for hitbox in vec {
-        commands.entity(hitbox).despawn();
-    }
-    animator.set_bool("can_move", true);
-}
-
-#[enter("attack2")]
-fn on_attack2_enter(
-    mut commands: Commands
-) {
-    let entity = trigger.entity;
-    commands.spawn((
-            Collider::rectangle(80., 40.),
-            Transform::from_xyz(40., 0., 0.),
-            Sensor,
-            HitBox { damage: 50. },
-            CollisionLayers::new(GameLayer::EnemyHitBox, [GameLayer::Player]),
-            CollisionEventsEnabled,
-            ChildOf(entity),
-            HitboxOf(entity),
-        )).observe(check_hitbox);
-}
-
-#[exit("attack2")]
-fn on_attack2_exit(
-    mut commands: Commands,
-    mut enemy: Query<(&HasHitbox, &mut Animator), With<Martial>>,
-) {
-    let entity = trigger.entity;
-    let (hitboxes, mut animator) = enemy.get_mut(entity).unwrap();
-    let vec = (**hitboxes).clone();
-    for hitbox in vec {
-        commands.entity(hitbox).despawn();
-    }
-    animator.set_bool("can_move", true);
-}
-
-#[enter("stun")]
-fn on_stun_enter(
-    mut martial: Query<&mut Animator, With<Martial>>,
-) {
-    let entity = trigger.entity;
-    let mut animator = martial.get_mut(entity).unwrap();
-    animator.set_bool("can_move", false);
-}
-
-#[exit("stun")]
-fn on_stun_exit(
-    mut martial: Query<&mut Animator, With<Martial>>,
-) {
-    let entity = trigger.entity;
-    let mut animator = martial.get_mut(entity).unwrap();
-    animator.set_bool("can_move", true);
+    commands.trigger(DropLoot { table: enemy.config.loot.unwrap(), position: transform.translation.truncate() });
+    // 遗言由过场动画`martial_defeated`播放
+    commands.entity(entity).despawn();
 }
 
-fn check_contact(
-    spatial_query: SpatialQuery,
-    mut query: Query<(&Transform, &mut Animator, &Collider), With<Martial>>,
+/// 区域重置时清空警觉度与瞬移状态，状态机回到待机，阶段由`BossPhases`重置
+fn on_arena_reset(
+    trigger: Trigger<ArenaReset>,
+    mut martials: Query<(&mut Notice, &mut PhaseTwoTimer, &mut Animator), With<Martial>>,
 ) {
-    let collider_notice = Collider::rectangle(1., 100.);
-
-    for (transform, mut animator, collider) in &mut query {
-        let origin = Vec2::new(transform.translation.x, transform.translation.y);
-        let rotation = transform.rotation.z;
-        let direction_x = if animator.get_float("facing_direction") > 0.0 {
-            Dir2::X
-        } else {
-            Dir2::NEG_X
-        };
-        let max_distance_y = 0.2;
-        let max_distance_x = 10.;
-        let max_distance_notice = 550.;
-        let max_hits = 1;
-
-        let config_y = ShapeCastConfig::from_max_distance(max_distance_y);
-        let config_x = ShapeCastConfig::from_max_distance(max_distance_x);
-        let notice_config = ShapeCastConfig::from_max_distance(max_distance_notice);
-        let filter = SpatialQueryFilter::default().with_mask(GameLayer::Ground);
-        let notice_filter =
-            SpatialQueryFilter::default().with_mask(GameLayer::Player);
-
-        let hits_ground = spatial_query.shape_hits(
-            &collider,
-            origin,
-            rotation,
-            Dir2::NEG_Y,
-            max_hits,
-            &config_y,
-            &filter,
-        );
-        let hits_wall = spatial_query.shape_hits(
-            &collider,
-            origin,
-            rotation,
-            direction_x,
-            max_hits,
-            &config_x,
-            &filter,
-        );
-        let hits_ceiling = spatial_query.shape_hits(
-            &collider,
-            origin,
-            rotation,
-            Dir2::Y,
-            max_hits,
-            &config_y,
-            &filter,
-        );
-        let hits_notice = spatial_query.shape_hits(
-            &collider_notice,
-            origin,
-            rotation,
-            -direction_x,
-            max_hits,
-            &notice_config,
-            &notice_filter,
-        );
-
-        animator.set_bool("is_grounded", hits_ground.len() > 0);
-        animator.set_bool("is_on_wall", hits_wall.len() > 0);
-        animator.set_bool("is_on_ceiling", hits_ceiling.len() > 0);
-        if let Some(_) = hits_notice.get(0) {
-            animator.set_bool(
-                "is_noticing",
-                true,
-            );
-        } else {
-            animator.set_bool("is_noticing", false);
-        }
-    }
-}
-
-fn on_flip_direction(mut query: Query<(&mut Transform, &Animator), With<Martial>>) {
-    for (mut transform, animator) in &mut query {
-        let facing_direction = animator.get_float("facing_direction");
-        if transform.scale.x * facing_direction < 0. {
-            transform.scale.x *= -1.;
-        }
-    }
-}
-
-fn on_move(mut query: Query<(&LinearVelocity, &mut Animator), With<Martial>>) {
-    for (vel, mut animator) in query.iter_mut() {
-        let is_moving = vel.x != 0.;
-        animator.set_bool("is_moving", is_moving);
+    if let Ok((mut notice, mut timer, mut animator)) = martials.get_mut(trigger.boss) {
+        notice.notice = 0.;
+        *timer = PhaseTwoTimer::new();
+        animator.reset_to("Idle");
+        animator.set_bool("is_moving", false);
+        animator.set_bool("can_move", true);
     }
 }
 
@@ -838,20 +686,56 @@ impl<S: States> Plugin for MartialPlugin<S> {
     fn build(&self, app: &mut App) {
         app.add_plugins(MartialBehaviourPlugin { state: self.state.clone() });
         app.add_systems(OnEnter(self.state.clone()), setup_enemy.run_if(in_state(self.state.clone())));
-        app.add_systems(
-            FixedUpdate,
-            (
-                check_contact.run_if(in_state(self.state.clone())),
-                on_move.run_if(in_state(self.state.clone())),
-                on_flip_direction.run_if(in_state(self.state.clone())),
-            ),
-        );
         app.add_observer(on_martial_death);
-        app.add_observer(on_attack1_enter);
-        app.add_observer(on_attack1_exit);
-        app.add_observer(on_attack2_enter);
-        app.add_observer(on_attack2_exit);
-        app.add_observer(on_stun_enter);
-        app.add_observer(on_stun_exit);
+        app.add_observer(on_arena_reset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::enemy::phase::PhasePlugin;
+    use crate::AppState;
+
+    #[test]
+    fn arena_reset_after_phase_change_returns_to_idle() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_plugins(PhasePlugin { state: AppState::InGame });
+        app.add_observer(on_arena_reset);
+
+        // 第二阶段中正在攻击前摇
+        let mut phases = BossPhases::new(MARTIAL_PHASES);
+        phases.current = 1;
+        let mut animator = setup_animator();
+        let (first, last) = AnimationType::Attack1Prep.config_index();
+        animator.set_initial_state("Attack1Prep", first, last, 10);
+        animator.set_trigger("attack1");
+        animator.set_bool("can_move", false);
+        let boss = app.world_mut().spawn((
+            Martial,
+            Enemy { config: &MARTIAL },
+            phases,
+            animator,
+            Notice::new(50., 1., 1.),
+            PhaseTwoTimer::new(),
+        )).id();
+
+        app.world_mut().trigger(ArenaReset { boss });
+        app.world_mut().run_system_once(move |mut commands: Commands, mut animators: Query<&mut Animator>| {
+            let mut atlas = TextureAtlas::default();
+            animators.get_mut(boss).unwrap().update(&mut commands, boss, Duration::ZERO, &mut atlas);
+        }).unwrap();
+
+        assert_eq!(app.world().get::<BossPhases>(boss).unwrap().current, 0);
+        let animator = app.world().get::<Animator>(boss).unwrap();
+        assert_eq!(animator.current_state(), "Idle");
+        assert!(!animator.is_active("attack1"));
+        assert!(animator.get_bool("can_move"));
+        assert_eq!(app.world().get::<Notice>(boss).unwrap().notice, 0.);
     }
-}
\ No newline at end of file
+}
@@ -1,8 +1,12 @@
 //! 武师boss行为树
 use crate::animator::*;
-use crate::damagable::Damagable;
+use crate::enemy::archetype::{Enemy, Notice};
 use crate::enemy::martial::Martial;
+use crate::enemy::phase::BossPhases;
+use crate::enemy::telegraph::spawn_marker;
+use crate::gravity::GravityDirection;
 use crate::player::Player;
+use crate::replay::GameRng;
 use avian2d::prelude::GravityScale;
 use avian2d::prelude::LinearVelocity;
 use bevy::prelude::*;
@@ -12,56 +16,45 @@ use bevy_tnua::prelude::*;
 use big_brain::prelude::*;
 use rand::Rng;
 
-const WALK_SPEED: f32 = 25.0;
-const NOTICED_SPEED: f32 = 50.0;
 const FLOAT_HEIGHT: f32 = 26.;
-
-fn get_speed(animator: &Animator, notice: &Notice) -> f32 {
-    if animator.get_bool("can_move") {
-        if notice.notice > 50. {
-            return NOTICED_SPEED;
-        }
-        return WALK_SPEED;
-    }
-    0.0
-}
-
-#[derive(Component, Debug, Reflect)]
-pub struct Notice {
-    pub add_per_sec: f32,
-    pub sub_per_sec: f32,
-    pub notice: f32,
-}
-
-impl Notice {
-    pub fn new(notice: f32, add_per_sec: f32, sub_per_sec: f32) -> Self {
-        Self {
-            notice,
-            add_per_sec,
-            sub_per_sec,
-        }
-    }
+/// 在天花板上时的重力大小
+const CEILING_GRAVITY: f32 = 130.;
+/// 瞬移到地面攻击时的重力大小
+const GROUND_GRAVITY: f32 = 30.;
+/// 瞬移前落点预警的时间
+const TELEPORT_WARNING: f32 = 0.6;
+const TELEPORT_MARKER_SIZE: Vec2 = Vec2::new(40., 6.);
+const TELEPORT_MARKER_COLOR: Color = Color::srgba(1., 0.2, 0.2, 0.6);
+
+/// 武师第一阶段行为树：追击玩家连击
+pub fn martial_thinker() -> ThinkerBuilder {
+    let phase1_combo = Steps::build()
+        .label("Phase1Combo")
+        .step(MoveToPlayer)
+        .step(Attack1)
+        .step(Attack2);
+
+    Thinker::build()
+        .label("NewEnemyThinker")
+        .picker(Highest)
+        .when(NoticeScorer, phase1_combo)
 }
 
-#[derive(Component, Debug, Reflect)]
-pub struct HealthState {
-    pub current_health: f32,
-    pub max_health: f32,
-    pub is_phase_two: bool,
-}
-
-impl HealthState {
-    pub fn new(max_health: f32) -> Self {
-        Self {
-            current_health: max_health,
-            max_health,
-            is_phase_two: false,
-        }
-    }
-
-    pub fn is_below_half(&self) -> bool {
-        self.current_health < self.max_health * 0.5
-    }
+/// 武师第二阶段行为树：跳到天花板，之后定时瞬移到玩家身边攻击
+pub fn martial_phase_two_thinker() -> ThinkerBuilder {
+    let phase2_transition = Steps::build()
+        .label("Phase2Transition")
+        .step(PhaseTransition);
+
+    let phase2_teleport = Steps::build()
+        .label("Phase2Teleport")
+        .step(TeleportAttack::default());
+
+    Thinker::build()
+        .label("PhaseTwoThinker")
+        .picker(Highest)
+        .when(CeilingScorer, phase2_transition)
+        .when(PhaseTwoScorer, phase2_teleport)
 }
 
 #[derive(Component, Debug, Reflect)]
@@ -81,41 +74,13 @@ impl PhaseTwoTimer {
     }
 }
 
-pub fn notice_system(time: Res<Time>, mut query: Query<(&mut Notice, &Animator), With<Martial>>) {
-    for (mut notice, animator) in &mut query {
-        if animator.is_active("hit") {
-            notice.notice = 100.;
-        }
-        if animator.get_bool("is_noticing") {
-            notice.notice += notice.add_per_sec * time.delta_secs();
-        } else {
-            notice.notice -= notice.sub_per_sec * time.delta_secs();
-        }
-
-        notice.notice = notice.notice.clamp(0.0, 80.0);
-    }
-}
-
-pub fn health_system(
-    mut query: Query<(&mut HealthState, &mut Animator, &Damagable), With<Martial>>
-) {
-    for (mut health_state, mut animator, damagable) in &mut query {
-        health_state.current_health = damagable.health;
-        
-        if health_state.is_below_half() && !health_state.is_phase_two {
-            health_state.is_phase_two = true;
-            animator.set_trigger("jump");
-        }
-    }
-}
-
 pub fn phase_two_timer_system(
     time: Res<Time>,
-    mut query: Query<(&mut PhaseTwoTimer, &HealthState), With<Martial>>,
+    mut query: Query<&mut PhaseTwoTimer, With<Martial>>,
     player_pos: Single<&Transform, With<Player>>,
 ) {
-    for (mut timer, health_state) in &mut query {
-        if health_state.is_phase_two && timer.is_on_ceiling {
+    for mut timer in &mut query {
+        if timer.is_on_ceiling {
             timer.teleport_timer.tick(time.delta());
             
             if timer.teleport_timer.finished() {
@@ -143,7 +108,8 @@ pub fn move_to_player_action_system(
             &LinearVelocity,
             &mut Animator,
             &Notice,
-            &HealthState,
+            &BossPhases,
+            &Enemy,
         ),
         Without<Player>,
     >,
@@ -151,7 +117,7 @@ pub fn move_to_player_action_system(
 ) {
     for (Actor(actor), mut action_state, _move_to, span) in &mut action_query {
         let _guard = span.span().enter();
-        let (actor_pos, mut controller, vel, mut animator, notice, health_state) =
+        let (actor_pos, mut controller, vel, mut animator, notice, phases, enemy) =
             actor_query.get_mut(*actor).expect("actor has no position");
         match *action_state {
             ActionState::Requested => {
@@ -173,8 +139,8 @@ pub fn move_to_player_action_system(
                 
                 let distance = delta.length();
                 if distance > FAR_MAX_DISTANCE {
-                    let vx = get_speed(&animator, &notice) * facing_direction;
-                    if controller.is_airborne().unwrap() && !health_state.is_phase_two {
+                    let vx = enemy.speed(&animator, notice) * facing_direction;
+                    if controller.is_airborne().unwrap() && phases.current == 0 {
                         controller.basis(TnuaBuiltinWalk {
                             desired_velocity: Vec3::new(vel.x, 0., 0.),
                             float_height: FLOAT_HEIGHT,
@@ -194,8 +160,8 @@ pub fn move_to_player_action_system(
                         });
                     }
                 } else if distance > MID_MAX_DISTANCE {
-                    let vx = 2.0 * get_speed(&animator, &notice) * facing_direction;
-                    if controller.is_airborne().unwrap() && !health_state.is_phase_two {
+                    let vx = 2.0 * enemy.speed(&animator, notice) * facing_direction;
+                    if controller.is_airborne().unwrap() && phases.current == 0 {
                         controller.basis(TnuaBuiltinWalk {
                             desired_velocity: Vec3::new(vel.x, 0., 0.),
                             float_height: FLOAT_HEIGHT,
@@ -216,7 +182,7 @@ pub fn move_to_player_action_system(
                     }
                     *action_state = ActionState::Success;
                 } else {
-                    if controller.is_airborne().unwrap() && !health_state.is_phase_two {
+                    if controller.is_airborne().unwrap() && phases.current == 0 {
                         controller.basis(TnuaBuiltinWalk {
                             desired_velocity: Vec3::new(vel.x, 0., 0.),
                             float_height: FLOAT_HEIGHT,
@@ -250,14 +216,14 @@ pub fn move_to_player_action_system(
 pub struct Attack1;
 
 pub fn attack1_action_system(
-    mut enemy_query: Query<(&Transform, &mut Animator, &HealthState), Without<Player>>,
+    mut enemy_query: Query<(&Transform, &mut Animator, &BossPhases), Without<Player>>,
     player_pos: Single<&Transform, With<Player>>,
     mut query: Query<(&Actor, &mut ActionState, &Attack1, &ActionSpan)>,
 ) {
     for (Actor(actor), mut state, _attack, span) in &mut query {
         let _guard = span.span().enter();
 
-        let (actor_pos, mut animator, health_state) = enemy_query
+        let (actor_pos, mut animator, phases) = enemy_query
             .get_mut(*actor)
             .expect("actor didn't have components");
 
@@ -268,7 +234,7 @@ pub fn attack1_action_system(
                 
                 if distance > FAR_MAX_DISTANCE {
                     *state = ActionState::Failure;
-                } else if distance <= CLOSE_MAX_DISTANCE || health_state.is_phase_two {
+                } else if distance <= CLOSE_MAX_DISTANCE || phases.current > 0 {
                     animator.set_trigger("attack1");
                     *state = ActionState::Executing;
                 } else {
@@ -292,14 +258,14 @@ pub fn attack1_action_system(
 pub struct Attack2;
 
 pub fn attack2_action_system(
-    mut enemy_query: Query<(&Transform, &mut Animator, &HealthState), Without<Player>>,
+    mut enemy_query: Query<(&Transform, &mut Animator, &BossPhases), Without<Player>>,
     player_pos: Single<&Transform, With<Player>>,
     mut query: Query<(&Actor, &mut ActionState, &Attack2, &ActionSpan)>,
 ) {
     for (Actor(actor), mut state, _attack, span) in &mut query {
         let _guard = span.span().enter();
 
-        let (actor_pos, mut animator, health_state) = enemy_query
+        let (actor_pos, mut animator, phases) = enemy_query
             .get_mut(*actor)
             .expect("actor didn't have components");
 
@@ -309,7 +275,7 @@ pub fn attack2_action_system(
                 let distance = delta.length();
                 
                 // 只在第一阶段且玩家在近距离时执行
-                if !health_state.is_phase_two && distance <= CLOSE_MAX_DISTANCE {
+                if phases.current == 0 && distance <= CLOSE_MAX_DISTANCE {
                     animator.set_trigger("attack2");
                     *state = ActionState::Executing;
                 } else {
@@ -333,14 +299,15 @@ pub fn attack2_action_system(
 pub struct JumpAttack;
 
 pub fn jump_attack_action_system(
-    mut enemy_query: Query<(&Transform, &mut Animator, &mut TnuaController, &HealthState), Without<Player>>,
+    mut enemy_query: Query<(&Transform, &mut Animator, &mut TnuaController, &BossPhases), Without<Player>>,
     player_pos: Single<&Transform, With<Player>>,
+    mut rng: ResMut<GameRng>,
     mut query: Query<(&Actor, &mut ActionState, &JumpAttack, &ActionSpan)>,
 ) {
     for (Actor(actor), mut state, _attack, span) in &mut query {
         let _guard = span.span().enter();
 
-        let (actor_pos, mut animator, mut controller, health_state) = enemy_query
+        let (actor_pos, mut animator, mut controller, phases) = enemy_query
             .get_mut(*actor)
             .expect("actor didn't have components");
 
@@ -350,9 +317,8 @@ pub fn jump_attack_action_system(
                 let distance = delta.length();
                 
                 // 只在第一阶段且中距离时有概率执行跳跃攻击
-                if !health_state.is_phase_two && distance > MID_MAX_DISTANCE && distance <= JUMP_ATTACK_DISTANCE {
-                    let mut rng = rand::rng();
-                    let random: f32 = rng.random();
+                if phases.current == 0 && distance > MID_MAX_DISTANCE && distance <= JUMP_ATTACK_DISTANCE {
+                    let random: f32 = rng.rng.random();
                     if random > 0.7 { // 30% 概率
                         if !controller.is_airborne().unwrap() {
                             controller.action(TnuaBuiltinJump {
@@ -408,13 +374,13 @@ pub fn jump_attack_action_system(
 pub struct PhaseTransition;
 
 pub fn phase_transition_action_system(
-    mut enemy_query: Query<(&Transform, &mut Animator, &mut TnuaController, &mut PhaseTwoTimer, &mut GravityScale), Without<Player>>,
+    mut enemy_query: Query<(&Transform, &mut Animator, &mut TnuaController, &mut PhaseTwoTimer, &mut GravityScale, &mut GravityDirection), Without<Player>>,
     mut query: Query<(&Actor, &mut ActionState, &PhaseTransition, &ActionSpan)>,
 ) {
     for (Actor(actor), mut state, _transition, span) in &mut query {
         let _guard = span.span().enter();
 
-        let (_actor_pos, mut animator, mut controller, mut timer, mut gravity) = enemy_query
+        let (_actor_pos, mut animator, mut controller, mut timer, mut gravity, mut direction) = enemy_query
             .get_mut(*actor)
             .expect("actor didn't have components");
 
@@ -429,9 +395,10 @@ pub fn phase_transition_action_system(
             }
             ActionState::Executing => {
                 if !animator.in_state("Jump".to_string()) {
-                    // 传送到天花板
+                    // 重力反转，倒立在天花板上
                     timer.is_on_ceiling = true;
-                    gravity.0 = -gravity.0 - 100.;
+                    gravity.0 = CEILING_GRAVITY;
+                    *direction = GravityDirection::Up;
                     *state = ActionState::Success;
                 }
             }
@@ -443,50 +410,75 @@ pub fn phase_transition_action_system(
     }
 }
 
-#[derive(Clone, Component, Debug, ActionBuilder, Reflect)]
-pub struct TeleportAttack;
+/// 瞬移攻击：先在落点显示预警，隐身一段时间后在落点现身攻击
+#[derive(Clone, Component, Debug, Default, ActionBuilder, Reflect)]
+pub struct TeleportAttack {
+    timer: f32,
+    /// 落点
+    target: Option<Vec2>,
+}
 
 pub fn teleport_attack_action_system(
-    mut enemy_query: Query<(&mut Transform, &mut Animator, &mut PhaseTwoTimer, &mut GravityScale), Without<Player>>,
-    mut query: Query<(&Actor, &mut ActionState, &TeleportAttack, &ActionSpan)>,
+    mut commands: Commands,
+    time: Res<Time>,
+    mut enemy_query: Query<(&mut Transform, &mut Animator, &mut PhaseTwoTimer, &mut GravityScale, &mut GravityDirection), Without<Player>>,
+    mut query: Query<(&Actor, &mut ActionState, &mut TeleportAttack, &ActionSpan)>,
 ) {
-    for (Actor(actor), mut state, _teleport, span) in &mut query {
+    for (Actor(actor), mut state, mut teleport, span) in &mut query {
         let _guard = span.span().enter();
 
-        let (mut transform, mut animator, mut timer, mut gravity) = enemy_query
+        let (mut transform, mut animator, mut timer, mut gravity, mut direction) = enemy_query
             .get_mut(*actor)
             .expect("actor didn't have components");
 
         match *state {
             ActionState::Requested => {
-                println!("try hide!");
-                if let Some(target_pos) = timer.teleport_position {
-                    // 隐藏并传送到玩家位置
-                    println!("hide!");
+                if let Some(target_pos) = timer.teleport_position.take() {
+                    // 隐身并在落点显示预警
                     animator.set_trigger("hide");
-                    transform.translation.x = target_pos.x;
-                    transform.translation.y = target_pos.y;
-                    gravity.0 += 110.;
-                    timer.teleport_position = None;
+                    spawn_marker(
+                        &mut commands,
+                        target_pos - Vec2::new(0., FLOAT_HEIGHT),
+                        TELEPORT_MARKER_SIZE,
+                        TELEPORT_MARKER_COLOR,
+                        TELEPORT_WARNING,
+                    );
+                    teleport.target = Some(target_pos);
+                    teleport.timer = 0.;
                     *state = ActionState::Executing;
                 } else {
                     *state = ActionState::Failure;
-                    timer.into_inner().teleport_timer.reset();
+                    timer.teleport_timer.reset();
                 }
             }
             ActionState::Executing => {
-                if animator.in_state("Hidden".to_string()) {
+                teleport.timer += time.delta_secs();
+                if let Some(target_pos) = teleport.target {
+                    if teleport.timer < TELEPORT_WARNING {
+                        continue;
+                    }
+                    // 预警结束，传送到落点
+                    transform.translation.x = target_pos.x;
+                    transform.translation.y = target_pos.y;
+                    gravity.0 = GROUND_GRAVITY;
+                    *direction = GravityDirection::Down;
+                    teleport.target = None;
+                } else if animator.in_state("Hidden".to_string()) {
                     // 显现并攻击
                     animator.set_trigger("showup");
                 } else if !animator.in_state("Attack1Prep".to_string()) && !animator.in_state("Attack1".to_string()){
                     // 攻击完成，回到天花板
-                    gravity.0 -= 110.;
-                    timer.into_inner().teleport_timer.reset();
-                    println!("attack ok!");
+                    gravity.0 = CEILING_GRAVITY;
+                    *direction = GravityDirection::Up;
+                    timer.teleport_timer.reset();
                     *state = ActionState::Success;
                 }
             }
             ActionState::Cancelled => {
+                if teleport.target.take().is_none() {
+                    gravity.0 = CEILING_GRAVITY;
+                    *direction = GravityDirection::Up;
+                }
                 *state = ActionState::Failure;
             }
             _ => {}
@@ -508,20 +500,17 @@ pub fn notice_scorer_system(
     }
 }
 
+/// 第二阶段还没有到达天花板
 #[derive(Clone, Component, Debug, ScorerBuilder, Reflect)]
-pub struct HealthScorer;
+pub struct CeilingScorer;
 
-pub fn health_scorer_system(
-    health_states: Query<&HealthState>,
-    mut query: Query<(&Actor, &mut Score), With<HealthScorer>>,
+pub fn ceiling_scorer_system(
+    timers: Query<&PhaseTwoTimer>,
+    mut query: Query<(&Actor, &mut Score), With<CeilingScorer>>,
 ) {
     for (Actor(actor), mut score) in &mut query {
-        if let Ok(health_state) = health_states.get(*actor) {
-            if health_state.is_below_half() {
-                score.set(0.99);
-            } else {
-                score.set(0.0);
-            }
+        if let Ok(timer) = timers.get(*actor) {
+            score.set(if timer.is_on_ceiling { 0.0 } else { 0.99 });
         }
     }
 }
@@ -530,13 +519,12 @@ pub fn health_scorer_system(
 pub struct PhaseTwoScorer;
 
 pub fn phase_two_scorer_system(
-    health_states: Query<&HealthState>,
     timers: Query<&PhaseTwoTimer>,
     mut query: Query<(&Actor, &mut Score), With<PhaseTwoScorer>>,
 ) {
     for (Actor(actor), mut score) in &mut query {
-        if let (Ok(health_state), Ok(timer)) = (health_states.get(*actor), timers.get(*actor)) {
-            if health_state.is_phase_two && timer.teleport_timer.finished() {
+        if let Ok(timer) = timers.get(*actor) {
+            if timer.is_on_ceiling && timer.teleport_timer.finished() {
                 score.set(1.0);
             } else {
                 score.set(0.0);
@@ -551,11 +539,7 @@ pub struct MartialBehaviourPlugin<S: States> {
 
 impl<S: States> Plugin for MartialBehaviourPlugin<S> {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (
-            notice_system.run_if(in_state(self.state.clone())),
-            health_system.run_if(in_state(self.state.clone())),
-            phase_two_timer_system.run_if(in_state(self.state.clone())),
-        ));
+        app.add_systems(Update, phase_two_timer_system.run_if(in_state(self.state.clone())));
         app.add_systems(
             PreUpdate,
             (
@@ -570,7 +554,7 @@ impl<S: States> Plugin for MartialBehaviourPlugin<S> {
         );
         app.add_systems(First, (
             notice_scorer_system.run_if(in_state(self.state.clone())),
-            health_scorer_system.run_if(in_state(self.state.clone())),
+            ceiling_scorer_system.run_if(in_state(self.state.clone())),
             phase_two_scorer_system.run_if(in_state(self.state.clone())),
         ));
     }
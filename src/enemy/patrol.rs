@@ -0,0 +1,157 @@
+//! 巡逻路线
+//! 敌人沿路点巡逻，到达路点后可以停顿并左右张望。
+//! 路线可以在代码中指定，也可以在Tiled中用类别为`PatrolRoute`的折线或矩形绘制，
+//! 生成的敌人会使用离自己最近的路线。
+
+use bevy::prelude::*;
+
+use crate::enemy::archetype::Enemy;
+use crate::level::{LevelObject, LevelObjects};
+
+/// 敌人与Tiled路线的最大距离，超过则不使用该路线
+const ROUTE_ATTACH_DISTANCE: f32 = 64.;
+
+/// 巡逻路线组件
+#[derive(Component, Debug, Clone, Reflect)]
+pub struct PatrolRoute {
+    /// 路点
+    pub waypoints: Vec<Vec2>,
+    /// 当前前往的路点
+    pub current: usize,
+    /// 到达终点后是否回到起点循环，否则原路返回
+    pub looping: bool,
+    /// 原路返回时是否正在向前走
+    pub forward: bool,
+    /// 到达路点后停顿的时间
+    pub pause: f32,
+    /// 停顿时是否左右张望
+    pub look_around: bool,
+    /// 剩余停顿时间
+    pub wait_timer: f32,
+}
+
+impl PatrolRoute {
+    /// 沿路点巡逻
+    pub fn new(waypoints: Vec<Vec2>) -> Self {
+        Self {
+            waypoints,
+            current: 0,
+            looping: false,
+            forward: true,
+            pause: 0.,
+            look_around: false,
+            wait_timer: 0.,
+        }
+    }
+
+    /// 在左右边界之间往返巡逻
+    pub fn bounds(left: f32, right: f32, y: f32) -> Self {
+        Self::new(vec![Vec2::new(left, y), Vec2::new(right, y)])
+    }
+
+    /// 设置到达路点后的停顿
+    pub fn with_pause(mut self, pause: f32, look_around: bool) -> Self {
+        self.pause = pause;
+        self.look_around = look_around;
+        self
+    }
+
+    /// 从Tiled对象生成：折线使用其顶点，矩形使用其底边两端
+    pub fn from_object(object: &LevelObject) -> Option<Self> {
+        let mut route = if !object.points.is_empty() {
+            Self::new(object.points.clone())
+        } else if object.size.x > 0. {
+            let bottom = object.position.y - object.size.y / 2.;
+            Self::bounds(
+                object.position.x - object.size.x / 2.,
+                object.position.x + object.size.x / 2.,
+                bottom,
+            )
+        } else {
+            return None;
+        };
+        route.looping = object.get_bool("loop").unwrap_or(false);
+        route.pause = object.get_float("pause").unwrap_or(0.);
+        route.look_around = object.get_bool("look_around").unwrap_or(false);
+        Some(route)
+    }
+
+    /// 当前目标路点
+    pub fn target(&self) -> Vec2 {
+        self.waypoints[self.current]
+    }
+
+    /// 是否正在停顿
+    pub fn is_waiting(&self) -> bool {
+        self.wait_timer > 0.
+    }
+
+    /// 前往下一个路点，并开始停顿
+    pub fn advance(&mut self) {
+        self.wait_timer = self.pause;
+        let len = self.waypoints.len();
+        if len < 2 {
+            return;
+        }
+        if self.looping {
+            self.current = (self.current + 1) % len;
+            return;
+        }
+        if self.forward && self.current + 1 >= len {
+            self.forward = false;
+        } else if !self.forward && self.current == 0 {
+            self.forward = true;
+        }
+        if self.forward {
+            self.current += 1;
+        } else {
+            self.current -= 1;
+        }
+    }
+
+    /// 到路线的最近距离
+    fn distance_to(&self, position: Vec2) -> f32 {
+        if self.waypoints.len() == 1 {
+            return self.waypoints[0].distance(position);
+        }
+        self.waypoints
+            .windows(2)
+            .map(|w| {
+                let segment = w[1] - w[0];
+                let t = ((position - w[0]).dot(segment) / segment.length_squared().max(f32::EPSILON))
+                    .clamp(0., 1.);
+                (w[0] + segment * t).distance(position)
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+}
+
+/// 新生成的敌人使用附近的Tiled巡逻路线
+fn attach_level_routes(
+    mut commands: Commands,
+    level: Res<LevelObjects>,
+    enemies: Query<(Entity, &Transform), (Added<Enemy>, Without<PatrolRoute>)>,
+) {
+    for (entity, transform) in &enemies {
+        let position = transform.translation.truncate();
+        let nearest = level
+            .with_class("PatrolRoute")
+            .filter_map(PatrolRoute::from_object)
+            .map(|route| (route.distance_to(position), route))
+            .filter(|(distance, _)| *distance <= ROUTE_ATTACH_DISTANCE)
+            .min_by(|(a, _), (b, _)| a.total_cmp(b));
+        if let Some((_, route)) = nearest {
+            commands.entity(entity).insert(route);
+        }
+    }
+}
+
+pub struct PatrolPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for PatrolPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, attach_level_routes.run_if(in_state(self.state.clone())));
+    }
+}
@@ -0,0 +1,193 @@
+//! 敌人感知
+//! 视觉：以朝向为中心的视锥，视线被地形遮挡；
+//! 听觉：玩家奔跑、落地以及战斗时发出声音，听觉范围内的敌人会被惊动；
+//! 记忆：记录玩家最后出现的位置，丢失目标后一段时间内仍会前往该位置搜寻。
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::animator::Animator;
+use crate::enemy::archetype::Notice;
+use crate::game_layer::GameLayer;
+use crate::player::Player;
+
+/// 奔跑时发出脚步声的间隔
+const FOOTSTEP_INTERVAL: f32 = 0.4;
+/// 听到声音时增加的警觉度（乘以响度）
+const HEARD_NOTICE: f32 = 40.;
+
+/// 感知配置
+#[derive(Debug, Clone, Copy)]
+pub struct PerceptionConfig {
+    /// 视线距离
+    pub view_distance: f32,
+    /// 视锥半角（弧度），PI表示全方向
+    pub view_angle: f32,
+    /// 听觉范围（响度为1的声音）
+    pub hearing_radius: f32,
+    /// 丢失目标后记住其位置的时间
+    pub memory: f32,
+    /// 视线是否穿透地形
+    pub through_ground: bool,
+}
+
+/// 感知组件
+#[derive(Component, Debug, Reflect)]
+pub struct Perception {
+    pub view_distance: f32,
+    pub view_angle: f32,
+    pub hearing_radius: f32,
+    pub memory: f32,
+    pub through_ground: bool,
+    /// 当前是否看得到玩家
+    pub can_see_player: bool,
+    /// 看到玩家时的接近程度（0~1），越近警觉度增长越快
+    pub proximity: f32,
+    /// 玩家最后出现的位置
+    pub last_known_position: Option<Vec2>,
+    /// 上次看到或听到玩家后经过的时间
+    pub time_since_sensed: f32,
+}
+
+impl Perception {
+    pub fn new(config: &PerceptionConfig) -> Self {
+        Self {
+            view_distance: config.view_distance,
+            view_angle: config.view_angle,
+            hearing_radius: config.hearing_radius,
+            memory: config.memory,
+            through_ground: config.through_ground,
+            can_see_player: false,
+            proximity: 0.,
+            last_known_position: None,
+            time_since_sensed: 0.,
+        }
+    }
+
+    /// 获取追击目标：看得到玩家时为玩家位置，否则为记忆中的位置
+    pub fn target(&self, player_position: Vec2) -> Option<Vec2> {
+        if self.can_see_player {
+            Some(player_position)
+        } else {
+            self.last_known_position
+        }
+    }
+}
+
+/// 声音触发器
+#[derive(Event, Debug, Clone, Copy)]
+pub struct Noise {
+    /// 声源位置
+    pub position: Vec2,
+    /// 响度，听觉范围按响度缩放
+    pub loudness: f32,
+}
+
+impl Noise {
+    /// 奔跑脚步声
+    pub fn footstep(position: Vec2) -> Self {
+        Self { position, loudness: 0.6 }
+    }
+
+    /// 落地声
+    pub fn landing(position: Vec2) -> Self {
+        Self { position, loudness: 1.0 }
+    }
+
+    /// 战斗中的打击声
+    pub fn combat(position: Vec2) -> Self {
+        Self { position, loudness: 1.5 }
+    }
+}
+
+/// 视觉检测
+fn vision_system(
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    player: Single<&Transform, With<Player>>,
+    mut query: Query<(&Transform, &mut Animator, &mut Perception), Without<Player>>,
+) {
+    let player_position = player.into_inner().translation.truncate();
+    for (transform, mut animator, mut perception) in &mut query {
+        let origin = transform.translation.truncate();
+        let delta = player_position - origin;
+        let distance = delta.length();
+        let facing = Vec2::new(animator.get_float("facing_direction").signum(), 0.);
+
+        let mut sees = distance <= perception.view_distance
+            && (distance == 0. || facing.angle_to(delta).abs() <= perception.view_angle);
+        if sees && !perception.through_ground {
+            if let Ok(direction) = Dir2::new(delta) {
+                let filter = SpatialQueryFilter::default().with_mask(GameLayer::Ground);
+                sees = spatial_query
+                    .cast_ray(origin, direction, distance, true, &filter)
+                    .is_none();
+            }
+        }
+
+        animator.set_bool("is_noticing", sees);
+        perception.can_see_player = sees;
+        if sees {
+            perception.proximity = 1. - distance / perception.view_distance;
+            perception.last_known_position = Some(player_position);
+            perception.time_since_sensed = 0.;
+        } else {
+            perception.proximity = 0.;
+            perception.time_since_sensed += time.delta_secs();
+            if perception.time_since_sensed > perception.memory {
+                perception.last_known_position = None;
+            }
+        }
+    }
+}
+
+/// 听到声音，记住声源位置并提高警觉度
+fn hear_noise(
+    trigger: Trigger<Noise>,
+    mut query: Query<(&Transform, &mut Perception, &mut Notice)>,
+) {
+    let noise = trigger.event();
+    for (transform, mut perception, mut notice) in &mut query {
+        let distance = transform.translation.truncate().distance(noise.position);
+        if distance > perception.hearing_radius * noise.loudness {
+            continue;
+        }
+        perception.last_known_position = Some(noise.position);
+        perception.time_since_sensed = 0.;
+        notice.notice = (notice.notice + HEARD_NOTICE * noise.loudness).min(notice.max);
+    }
+}
+
+/// 玩家奔跑时发出脚步声
+fn footstep_noise_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut timer: Local<f32>,
+    player: Single<(&Transform, &Animator), With<Player>>,
+) {
+    let (transform, animator) = player.into_inner();
+    let running = animator.get_bool("is_running")
+        && animator.get_bool("is_moving")
+        && animator.get_bool("is_grounded");
+    if !running {
+        *timer = FOOTSTEP_INTERVAL;
+        return;
+    }
+    *timer += time.delta_secs();
+    if *timer >= FOOTSTEP_INTERVAL {
+        *timer = 0.;
+        commands.trigger(Noise::footstep(transform.translation.truncate()));
+    }
+}
+
+pub struct PerceptionPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for PerceptionPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, vision_system.run_if(in_state(self.state.clone())));
+        app.add_systems(Update, footstep_noise_system.run_if(in_state(self.state.clone())));
+        app.add_observer(hear_noise);
+    }
+}
@@ -0,0 +1,206 @@
+//! Boss阶段
+//! boss的血量或战斗时间达到阈值时进入下一阶段：转阶段期间世界放慢、boss无敌并停下播放转阶段动画，
+//! 可以切换战斗音乐，结束后换上该阶段的行为树。区域重置时回到初始阶段。
+
+use avian2d::prelude::LinearVelocity;
+use bevy::prelude::*;
+use bevy_kira_audio::AudioChannel;
+use bevy_tnua::builtins::TnuaBuiltinWalk;
+use bevy_tnua::math::float_consts;
+use bevy_tnua::prelude::*;
+use big_brain::prelude::*;
+use big_brain::thinker::HasThinker;
+
+use crate::animator::Animator;
+use crate::arena::{ActiveBoss, ArenaReset};
+use crate::background::{play_music, MusicChannel};
+use crate::damagable::Damagable;
+use crate::enemy::archetype::Enemy;
+
+/// 转阶段时的世界速度
+const TRANSITION_TIME_SCALE: f32 = 0.3;
+
+/// 进入阶段的条件
+#[derive(Debug, Clone, Copy)]
+pub enum PhaseTrigger {
+    /// 剩余血量比例不高于该值
+    Health(f32),
+    /// 战斗开始后经过的秒数
+    Time(f32),
+}
+
+/// 阶段配置，初始阶段使用敌人配置中的行为树
+#[derive(Debug)]
+pub struct BossPhase {
+    /// 阶段名
+    pub name: &'static str,
+    /// 进入条件
+    pub trigger: PhaseTrigger,
+    /// 该阶段的行为树
+    pub thinker: fn() -> ThinkerBuilder,
+    /// 转阶段持续时间（现实时间）
+    pub transition_time: f32,
+    /// 转阶段时触发的动画
+    pub animation: Option<&'static str>,
+    /// 切换的战斗音乐
+    pub music: Option<&'static str>,
+}
+
+/// boss阶段组件
+#[derive(Component, Debug)]
+pub struct BossPhases {
+    /// 初始阶段之后的各阶段
+    pub phases: &'static [BossPhase],
+    /// 当前阶段，0为初始阶段
+    pub current: usize,
+    /// 战斗时间
+    pub elapsed: f32,
+    /// 转阶段剩余时间
+    pub transition: f32,
+}
+
+impl BossPhases {
+    pub fn new(phases: &'static [BossPhase]) -> Self {
+        Self {
+            phases,
+            current: 0,
+            elapsed: 0.,
+            transition: 0.,
+        }
+    }
+
+    /// 按血量进入的阶段分界，用于boss血条标记
+    pub fn health_thresholds(&self) -> Vec<f32> {
+        self.phases
+            .iter()
+            .filter_map(|phase| match phase.trigger {
+                PhaseTrigger::Health(ratio) => Some(ratio),
+                PhaseTrigger::Time(_) => None,
+            })
+            .collect()
+    }
+
+    /// 是否正在转阶段
+    pub fn is_transitioning(&self) -> bool {
+        self.transition > 0.
+    }
+
+    /// 当前阶段的配置，初始阶段为空
+    pub fn phase(&self) -> Option<&'static BossPhase> {
+        self.current.checked_sub(1).map(|i| &self.phases[i])
+    }
+}
+
+/// 检查阶段条件并处理转阶段
+fn phase_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    real_time: Res<Time<Real>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    music: Res<AudioChannel<MusicChannel>>,
+    asset_server: Res<AssetServer>,
+    mut bosses: Query<(
+        Entity,
+        &Enemy,
+        &mut BossPhases,
+        &mut Damagable,
+        &mut Animator,
+        &mut TnuaController,
+        &mut LinearVelocity,
+        Option<&HasThinker>,
+    ), With<ActiveBoss>>,
+) {
+    for (entity, enemy, mut phases, mut damagable, mut animator, mut controller, mut velocity, thinker) in &mut bosses {
+        if !damagable.is_alive {
+            continue;
+        }
+        if phases.is_transitioning() {
+            // 转阶段期间保持无敌并停在原地
+            damagable.set_invincible(true);
+            controller.basis(TnuaBuiltinWalk {
+                desired_velocity: Vec3::ZERO,
+                float_height: enemy.config.float_height,
+                air_acceleration: 600.,
+                acceleration: 600.,
+                max_slope: float_consts::FRAC_PI_4,
+                ..Default::default()
+            });
+            phases.transition -= real_time.delta_secs();
+            if !phases.is_transitioning() {
+                let phase = phases.phase().unwrap();
+                virtual_time.set_relative_speed(1.);
+                damagable.set_invincible(false);
+                animator.set_bool("can_move", true);
+                commands.entity(entity).insert((phase.thinker)());
+            }
+            continue;
+        }
+
+        phases.elapsed += time.delta_secs();
+        let all = phases.phases;
+        let Some(next) = all.get(phases.current) else { continue; };
+        let reached = match next.trigger {
+            PhaseTrigger::Health(ratio) => damagable.health <= damagable.max_health * ratio,
+            PhaseTrigger::Time(seconds) => phases.elapsed >= seconds,
+        };
+        if !reached {
+            continue;
+        }
+
+        // 开始转阶段：停下旧的行为树，放慢世界
+        phases.current += 1;
+        phases.transition = next.transition_time.max(f32::EPSILON);
+        if let Some(thinker) = thinker {
+            commands.entity(thinker.entity()).despawn();
+        }
+        commands.entity(entity).remove::<(HasThinker, ThinkerBuilder)>();
+        virtual_time.set_relative_speed(TRANSITION_TIME_SCALE);
+        damagable.set_invincible(true);
+        animator.set_bool("can_move", false);
+        velocity.x = 0.;
+        if let Some(trigger) = next.animation {
+            animator.set_trigger(trigger);
+        }
+        if let Some(track) = next.music {
+            play_music(&music, &asset_server, track);
+        }
+    }
+}
+
+/// 区域重置时回到初始阶段
+fn on_arena_reset(
+    trigger: Trigger<ArenaReset>,
+    mut commands: Commands,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut bosses: Query<(&Enemy, &mut BossPhases, &mut Animator, Option<&HasThinker>)>,
+) {
+    let Ok((enemy, mut phases, mut animator, thinker)) = bosses.get_mut(trigger.boss) else { return; };
+    phases.elapsed = 0.;
+    if phases.current == 0 {
+        return;
+    }
+    if phases.is_transitioning() {
+        virtual_time.set_relative_speed(1.);
+        animator.set_bool("can_move", true);
+    }
+    phases.current = 0;
+    phases.transition = 0.;
+    if let Some(thinker) = thinker {
+        commands.entity(thinker.entity()).despawn();
+    }
+    commands
+        .entity(trigger.boss)
+        .remove::<HasThinker>()
+        .insert((enemy.config.thinker)());
+}
+
+pub struct PhasePlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for PhasePlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, phase_system.run_if(in_state(self.state.clone())));
+        app.add_observer(on_arena_reset);
+    }
+}
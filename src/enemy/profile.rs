@@ -0,0 +1,228 @@
+//! 敌人AI配置
+//! 行为树以资源文件（`*.ai.ron`）描述：每个选项由一个带响应曲线的评分器和一串行动组成，
+//! 调整曲线与权重即可改变敌人的攻击性，不需要新增系统。修改资源文件后会重新生成行为树。
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::{prelude::*, scene::ron};
+use big_brain::prelude::*;
+use big_brain::thinker::HasThinker;
+use serde::Deserialize;
+
+use crate::damagable::Damagable;
+use crate::enemy::archetype::Notice;
+use crate::enemy::ground::{Attack, Flee, MoveToPlayer, Patrol};
+use crate::player::Player;
+
+/// 评分器的输入，都归一化到0~1
+#[derive(Debug, Clone, Copy, Deserialize, Reflect)]
+pub enum ScoreInput {
+    /// 警觉度
+    Notice,
+    /// 剩余血量比例
+    Health,
+    /// 与玩家的距离，超过`max`时为1
+    PlayerDistance { max: f32 },
+    /// 固定值
+    Constant(f32),
+}
+
+/// 响应曲线，把输入映射为评分
+#[derive(Debug, Clone, Copy, Deserialize, Reflect)]
+pub enum ResponseCurve {
+    /// `slope * x + offset`
+    Linear { slope: f32, offset: f32 },
+    /// 输入低于阈值时为`below`，否则为`above`
+    Step { threshold: f32, below: f32, above: f32 },
+    /// `x ^ exponent`
+    Power { exponent: f32 },
+    /// S形曲线，在`midpoint`处为0.5
+    Logistic { steepness: f32, midpoint: f32 },
+}
+
+impl ResponseCurve {
+    pub fn evaluate(&self, x: f32) -> f32 {
+        let y = match *self {
+            Self::Linear { slope, offset } => slope * x + offset,
+            Self::Step { threshold, below, above } => if x < threshold { below } else { above },
+            Self::Power { exponent } => x.max(0.).powf(exponent),
+            Self::Logistic { steepness, midpoint } => 1. / (1. + (-steepness * (x - midpoint)).exp()),
+        };
+        y.clamp(0., 1.)
+    }
+}
+
+fn default_weight() -> f32 {
+    1.
+}
+
+/// 评分器定义
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScorerDef {
+    pub input: ScoreInput,
+    pub curve: ResponseCurve,
+    /// 评分乘以的权重
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+}
+
+/// 行动定义
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum ActionDef {
+    MoveToPlayer,
+    Attack,
+    Patrol,
+    /// 远离玩家一段时间
+    Flee { duration: f32 },
+}
+
+/// 行为树中的一个选项
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChoiceDef {
+    pub label: String,
+    pub scorer: ScorerDef,
+    /// 依次执行的行动
+    pub actions: Vec<ActionDef>,
+}
+
+/// 选项选择方式
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum PickerDef {
+    /// 评分最高的选项
+    #[default]
+    Highest,
+    /// 第一个达到阈值的选项
+    FirstToScore { threshold: f32 },
+}
+
+/// AI配置资源
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct AiProfile {
+    #[serde(default)]
+    pub picker: PickerDef,
+    pub choices: Vec<ChoiceDef>,
+}
+
+impl AiProfile {
+    /// 根据配置生成行为树
+    pub fn thinker(&self) -> ThinkerBuilder {
+        let mut thinker = Thinker::build().label("Thinker");
+        thinker = match self.picker {
+            PickerDef::Highest => thinker.picker(Highest),
+            PickerDef::FirstToScore { threshold } => thinker.picker(FirstToScore::new(threshold)),
+        };
+        for choice in &self.choices {
+            let mut steps = Steps::build().label(choice.label.clone());
+            for action in &choice.actions {
+                steps = match *action {
+                    ActionDef::MoveToPlayer => steps.step(MoveToPlayer),
+                    ActionDef::Attack => steps.step(Attack),
+                    ActionDef::Patrol => steps.step(Patrol),
+                    ActionDef::Flee { duration } => steps.step(Flee::new(duration)),
+                };
+            }
+            let scorer = CurveScorer {
+                input: choice.scorer.input,
+                curve: choice.scorer.curve,
+                weight: choice.scorer.weight,
+            };
+            thinker = thinker.when(scorer, steps);
+        }
+        thinker
+    }
+}
+
+/// 读取`*.ai.ron`
+#[derive(Default)]
+struct AiProfileLoader;
+
+impl AssetLoader for AiProfileLoader {
+    type Asset = AiProfile;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ai.ron"]
+    }
+}
+
+/// 敌人使用的AI配置，加载完成后生成行为树
+#[derive(Component, Debug)]
+pub struct AiProfileHandle(pub Handle<AiProfile>);
+
+/// 由响应曲线计算评分
+#[derive(Clone, Component, Debug, ScorerBuilder, Reflect)]
+pub struct CurveScorer {
+    pub input: ScoreInput,
+    pub curve: ResponseCurve,
+    pub weight: f32,
+}
+
+pub fn curve_scorer_system(
+    player: Single<&Transform, With<Player>>,
+    actors: Query<(&Transform, &Notice, &Damagable), Without<Player>>,
+    mut query: Query<(&Actor, &mut Score, &CurveScorer)>,
+) {
+    let player = player.translation.truncate();
+    for (Actor(actor), mut score, scorer) in &mut query {
+        let Ok((transform, notice, damagable)) = actors.get(*actor) else { continue; };
+        let x = match scorer.input {
+            ScoreInput::Notice => notice.notice / 100.,
+            ScoreInput::Health => damagable.health / damagable.max_health,
+            ScoreInput::PlayerDistance { max } => {
+                (transform.translation.truncate().distance(player) / max).min(1.)
+            }
+            ScoreInput::Constant(value) => value,
+        };
+        score.set((scorer.curve.evaluate(x) * scorer.weight).clamp(0., 1.));
+    }
+}
+
+/// 配置加载完成时生成行为树，配置修改后重新生成
+fn apply_ai_profiles(
+    mut commands: Commands,
+    profiles: Res<Assets<AiProfile>>,
+    mut events: EventReader<AssetEvent<AiProfile>>,
+    pending: Query<(Entity, &AiProfileHandle), Without<ThinkerBuilder>>,
+    running: Query<(Entity, &AiProfileHandle, &HasThinker)>,
+) {
+    for (entity, handle) in &pending {
+        if let Some(profile) = profiles.get(&handle.0) {
+            commands.entity(entity).insert(profile.thinker());
+        }
+    }
+    for event in events.read() {
+        let AssetEvent::Modified { id } = event else { continue; };
+        let Some(profile) = profiles.get(*id) else { continue; };
+        for (entity, handle, thinker) in &running {
+            if handle.0.id() == *id {
+                commands.entity(thinker.entity()).despawn();
+                commands.entity(entity).remove::<HasThinker>().insert(profile.thinker());
+            }
+        }
+    }
+}
+
+pub struct ProfilePlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for ProfilePlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<AiProfile>();
+        app.init_asset_loader::<AiProfileLoader>();
+        app.add_systems(Update, apply_ai_profiles.run_if(in_state(self.state.clone())));
+        app.add_systems(First, curve_scorer_system.run_if(in_state(self.state.clone())));
+    }
+}
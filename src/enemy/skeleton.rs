@@ -1,137 +1,133 @@
-//! 初始关骷髅怪物
+//! 骷髅敌人，包括初始关骷髅与城堡骷髅
 
-use avian2d::prelude::*;
+use bevy::ecs::system::EntityCommands;
 use bevy::prelude::*;
-use big_brain::prelude::*;
-use game_derive::enter;
-use game_derive::exit;
 
 use crate::animator::Condition;
 use crate::animator::*;
-use crate::controller::ControllerBundle;
-use crate::damagable::{check_hitbox, Damagable, HasHitbox, HitBox, HitboxOf};
-use crate::game_layer::GameLayer;
-use crate::physics::PhysicsBundle;
-use crate::player::Player;
-mod behaviour;
-use behaviour::*;
-
-/// 骷髅标识
-#[derive(Component, Reflect)]
-struct Skeleton;
+use crate::enemy::archetype::{
+    __attack_enter_handler, __attack_exit_handler, __death_exit_handler, __stun_enter_handler,
+    __stun_exit_handler, CapsuleConfig, EnemyConfig, HitboxConfig, NoticeConfig,
+};
+use crate::enemy::perception::PerceptionConfig;
+use crate::enemy::telegraph::{TelegraphConfig, TelegraphCue};
+use crate::enemy::ground::{ground_thinker, GroundBehaviourPlugin};
+use crate::enemy::coordination::Engagement;
+use crate::enemy::spawner::{EnemySpawner, RespawnPolicy};
+
+/// 初始关骷髅
+const SKELETON: EnemyConfig = EnemyConfig {
+    name: "Skeleton",
+    sheet: "Art/Monster_Creatures_Fantasy(Version 1.3)/Skeleton_sheet.png",
+    tile_size: UVec2::new(150, 150),
+    columns: 6,
+    rows: 7,
+    scale: 0.7,
+    sprite_faces_left: false,
+    collider: CapsuleConfig { radius: 10., top: 16., bottom: 16. },
+    sensor_width: 14.,
+    float_height: 18.,
+    max_health: 100.,
+    walk_speed: 20.,
+    noticed_speed: 40.,
+    notice: NoticeConfig { add_per_sec: 50., sub_per_sec: 10., max: 100. },
+    perception: PerceptionConfig {
+        view_distance: 120.,
+        view_angle: 0.5,
+        hearing_radius: 150.,
+        memory: 4.,
+        through_ground: false,
+    },
+    hitboxes: &[HitboxConfig {
+        state: "Attack",
+        size: Vec2::new(30., 10.),
+        offset: Vec2::new(30., 0.),
+        damage: 20.,
+    }],
+    telegraphs: &[TelegraphConfig {
+        state: "AttackPrep",
+        cues: &[TelegraphCue::Glint { offset: Vec2::new(30., 6.), color: Color::srgb(1., 0.95, 0.6) }],
+        sound: None,
+    }],
+    body_hitbox: None,
+    flying: None,
+    halt_on_stun: false,
+    resume_after_attack: false,
+    animator: skeleton_animator,
+    thinker: ground_thinker,
+    profile: Some("AI/skeleton.ai.ron"),
+    loot: Some("skeleton"),
+};
+
+/// 胆小的骷髅，血量低时逃跑
+const COWARDLY_SKELETON: EnemyConfig = EnemyConfig {
+    name: "Cowardly Skeleton",
+    noticed_speed: 50.,
+    profile: Some("AI/cowardly_skeleton.ai.ron"),
+    ..SKELETON
+};
+
+/// 狂暴的骷髅，很快进入战斗且移动更快
+const BERSERKER_SKELETON: EnemyConfig = EnemyConfig {
+    name: "Berserker Skeleton",
+    noticed_speed: 60.,
+    profile: Some("AI/berserker_skeleton.ai.ron"),
+    ..SKELETON
+};
+
+/// 城堡骷髅，攻击范围更大、伤害更高
+const CASTLE_SKELETON: EnemyConfig = EnemyConfig {
+    name: "Castle Skeleton",
+    sheet: "Art/Monster_Creatures_Fantasy(Version 1.3)/Skeleton_sheet2.png",
+    hitboxes: &[HitboxConfig {
+        state: "Attack",
+        size: Vec2::new(40., 20.),
+        offset: Vec2::new(30., 0.),
+        damage: 30.,
+    }],
+    animator: castle_skeleton_animator,
+    loot: Some("castle_skeleton"),
+    ..SKELETON
+};
 
 /// 初始化敌人
-fn setup_enemy(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-) {
-    let texture =
-        asset_server.load("Art/Monster_Creatures_Fantasy(Version 1.3)/Skeleton_sheet.png");
-    let layout = TextureAtlasLayout::from_grid(UVec2::new(150, 150), 6, 7, None, None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
+fn setup_enemy(mut commands: Commands) {
+    let skeletons = [
+        (&SKELETON, Vec2::new(250.0, 45.1)),
+        (&SKELETON, Vec2::new(400.0, 45.2)),
+        (&SKELETON, Vec2::new(250.0, 157.)),
+        (&COWARDLY_SKELETON, Vec2::new(400.0, 157.)),
+        (&SKELETON, Vec2::new(400.0, 230.)),
+        (&BERSERKER_SKELETON, Vec2::new(500.0, 230.)),
+        (&SKELETON, Vec2::new(726.0, 413.)),
+    ];
+    for (config, position) in skeletons {
+        commands.spawn((
+            EnemySpawner::new(config, RespawnPolicy::OnRest).with_setup(setup_skeleton),
+            Transform::from_translation(position.extend(0.)),
+        ));
+    }
 
-    spawn_enemy(
-        &mut commands,
-        Vec2::new(250.0, 45.1),
-        texture.clone(),
-        texture_atlas_layout.clone(),
-    );
-    spawn_enemy(
-        &mut commands,
-        Vec2::new(400.0, 45.2),
-        texture.clone(),
-        texture_atlas_layout.clone(),
-    );
-    spawn_enemy(
-        &mut commands,
-        Vec2::new(250.0, 157.),
-        texture.clone(),
-        texture_atlas_layout.clone(),
-    );
-    spawn_enemy(
-        &mut commands,
-        Vec2::new(400.0, 157.),
-        texture.clone(),
-        texture_atlas_layout.clone(),
-    );
-    spawn_enemy(
-        &mut commands,
-        Vec2::new(400.0, 230.),
-        texture.clone(),
-        texture_atlas_layout.clone(),
-    );
-    spawn_enemy(
-        &mut commands,
-        Vec2::new(500.0, 230.),
-        texture.clone(),
-        texture_atlas_layout.clone(),
-    );
-    spawn_enemy(
-        &mut commands,
-        Vec2::new(726.0, 413.),
-        texture.clone(),
-        texture_atlas_layout.clone(),
-    );
+    let castle_skeletons = [
+        Vec2::new(730.0, 573.1),
+        Vec2::new(2927.0, 349.),
+        Vec2::new(1720.0, 509.),
+        Vec2::new(2560.0, 509.),
+        Vec2::new(2187.0, 685.),
+        Vec2::new(1377.0, 637.),
+        Vec2::new(441., 594.),
+    ];
+    for position in castle_skeletons {
+        commands.spawn((
+            EnemySpawner::new(&CASTLE_SKELETON, RespawnPolicy::OnReenter { radius: 400. }).with_setup(setup_skeleton),
+            Transform::from_translation(position.extend(0.)),
+        ));
+    }
 }
 
-/// 生成单个敌人
-fn spawn_enemy(
-    commands: &mut Commands,
-    position: Vec2,
-    texture: Handle<Image>,
-    texture_atlas_layout: Handle<TextureAtlasLayout>,
-) {
-    let animator = setup_animator();
-    let collider_layer = CollisionLayers::new(
-        GameLayer::Enemy,
-        [
-            GameLayer::Default,
-            GameLayer::Ground,
-            GameLayer::PlayerHitBox,
-        ],
-    );
-    let move_and_attack = Steps::build()
-        .label("MoveAndAttack")
-        .step(MoveToPlayer)
-        .step(Attack);
-
-    let patrol = Steps::build().label("Patrol").step(Patrol);
-
-    let thinker = Thinker::build()
-        .label("Thinker")
-        .picker(Highest)
-        .when(NoticeScorer, move_and_attack)
-        .when(PatrolScorer, patrol);
-
-    commands.spawn((
-        Sprite {
-            image: texture,
-            texture_atlas: Some(TextureAtlas {
-                layout: texture_atlas_layout,
-                index: animator.first_index,
-            }),
-            ..default()
-        },
-        Transform::from_xyz(position.x, position.y, 0.0).with_scale(
-            Vec3::new(0.7, 0.7, 0.7)
-        ),
-        Skeleton,
-        ControllerBundle::new(14.),
-        PhysicsBundle {
-            collider: Collider::capsule_endpoints(
-                10.0, 
-                Vec2::Y * 16.0, 
-                Vec2::NEG_Y * 16.0
-            ),
-            layer: collider_layer,
-            ..default()
-        },
-        Damagable::new(100.),
-        animator,
-        Notice::new(0.0, 50.0, 10.0),
-        thinker,
-    ));
+/// 骷髅生成后加入群体协调
+fn setup_skeleton(entity: &mut EntityCommands) {
+    entity.insert(Engagement::default());
 }
 
 /// 动画类型
@@ -165,8 +161,18 @@ impl AnimationType {
 }
 
 
+/// 初始关骷髅动画状态机
+fn skeleton_animator() -> Animator {
+    setup_animator(AnimationType::Attack)
+}
+
+/// 城堡骷髅动画状态机，攻击使用第三套攻击动画
+fn castle_skeleton_animator() -> Animator {
+    setup_animator(AnimationType::Attack3)
+}
+
 /// 初始化动画状态机
-fn setup_animator() -> Animator {
+fn setup_animator(attack: AnimationType) -> Animator {
     let idle_state = AnimationState {
         name: "Idle".to_string(),
         first_index: AnimationType::Idle.config_index().0,
@@ -307,8 +313,8 @@ fn setup_animator() -> Animator {
 
     let attack_state = AnimationState {
         name: "Attack".to_string(),
-        first_index: AnimationType::Attack.config_index().0,
-        last_index: AnimationType::Attack.config_index().1,
+        first_index: attack.config_index().0,
+        last_index: attack.config_index().1,
         transitions: vec![
             Transition {
                 conditions: vec![],
@@ -383,6 +389,7 @@ fn setup_animator() -> Animator {
     animator.add_parameter("hit", AnimatorParam::Trigger(false));
     animator.add_parameter("is_grounded", AnimatorParam::Bool(true));
     animator.add_parameter("is_on_wall", AnimatorParam::Bool(false));
+    animator.add_parameter("is_at_ledge", AnimatorParam::Bool(false));
     animator.add_parameter("is_on_ceiling", AnimatorParam::Bool(false));
     animator.add_parameter("facing_direction", AnimatorParam::Float(1.0));
     animator.add_parameter("is_noticing", AnimatorParam::Bool(false));
@@ -405,182 +412,13 @@ fn setup_animator() -> Animator {
     animator
 }
 
-/// 死亡状态
-#[exit("death")]
-fn on_death_exit(mut commands: Commands) {
-    let entity = trigger.entity;
-    commands.entity(entity).despawn();
-}
-
-/// 进入攻击状态
-#[enter("attack")]
-fn on_attack_enter(
-    mut commands: Commands
-) {
-    let entity = trigger.entity;
-    commands.spawn((
-            Collider::rectangle(30., 10.),
-            Transform::from_xyz(30., 0., 0.),
-            Sensor,
-            HitBox { damage: 20. },
-            CollisionLayers::new(GameLayer::EnemyHitBox, [GameLayer::Player]),
-            CollisionEventsEnabled,
-            ChildOf(entity),
-            HitboxOf(entity),
-        )).observe(check_hitbox);
-}
-
-/// 退出攻击状态
-#[exit("attack")]
-fn on_attack_exit(
-    mut commands: Commands,
-    enemy: Query<&HasHitbox, With<Skeleton>>,
-) {
-    let entity = trigger.entity;
-    let hitboxes = enemy.get(entity).unwrap();
-    let vec = (**hitboxes).clone();
-    for hitbox in vec {
-        commands.entity(hitbox).despawn();
-    }
-}
-
-/// 进入硬直状态
-#[enter("stun")]
-fn on_stun_enter(
-    mut player: Query<&mut Animator, With<Skeleton>>,
-) {
-    let entity = trigger.entity;
-    let mut animator = player.get_mut(entity).unwrap();
-    animator.set_bool("can_move", false);
-}
-
-/// 退出硬直状态
-#[exit("stun")]
-fn on_stun_exit(
-    mut player: Query<&mut Animator, With<Skeleton>>,
-) {
-    let entity = trigger.entity;
-    let mut animator = player.get_mut(entity).unwrap();
-    animator.set_bool("can_move", true);
-}
-
-/// 检查接触
-fn check_contact(
-    spatial_query: SpatialQuery,
-    mut query: Query<(&Transform, &mut Animator, &Collider), With<Skeleton>>,
-    entity: Single<Entity, With<Player>>,
-) {
-    let collider_notice = Collider::rectangle(1., 20.);
-
-    for (transform, mut animator, collider) in &mut query {
-        let origin = Vec2::new(transform.translation.x, transform.translation.y);
-        let rotation = transform.rotation.z;
-        let direction_x = if animator.get_float("facing_direction") > 0.0 {
-            Dir2::X
-        } else {
-            Dir2::NEG_X
-        };
-        let max_distance_y = 0.2;
-        let max_distance_x = 10.;
-        let max_distance_notice = 100.;
-        let max_hits = 1;
-
-        let config_y = ShapeCastConfig::from_max_distance(max_distance_y);
-        let config_x = ShapeCastConfig::from_max_distance(max_distance_x);
-        let notice_config = ShapeCastConfig::from_max_distance(max_distance_notice);
-        let filter = SpatialQueryFilter::default().with_mask(GameLayer::Ground);
-        let notice_filter =
-            SpatialQueryFilter::default().with_mask([GameLayer::Player, GameLayer::Ground]);
-
-        let hits_ground = spatial_query.shape_hits(
-            &collider,
-            origin,
-            rotation,
-            Dir2::NEG_Y,
-            max_hits,
-            &config_y,
-            &filter,
-        );
-        let hits_wall = spatial_query.shape_hits(
-            &collider,
-            origin,
-            rotation,
-            direction_x,
-            max_hits,
-            &config_x,
-            &filter,
-        );
-        let hits_ceiling = spatial_query.shape_hits(
-            &collider,
-            origin,
-            rotation,
-            Dir2::Y,
-            max_hits,
-            &config_y,
-            &filter,
-        );
-        let hits_notice = spatial_query.shape_hits(
-            &collider_notice,
-            origin,
-            rotation,
-            direction_x,
-            max_hits,
-            &notice_config,
-            &notice_filter,
-        );
-
-        animator.set_bool("is_grounded", hits_ground.len() > 0);
-        animator.set_bool("is_on_wall", hits_wall.len() > 0);
-        animator.set_bool("is_on_ceiling", hits_ceiling.len() > 0);
-        if let Some(noticed_entity) = hits_notice.get(0) {
-            animator.set_bool(
-                "is_noticing",
-                noticed_entity.entity.to_bits() == entity.to_bits(),
-            );
-        } else {
-            animator.set_bool("is_noticing", false);
-        }
-    }
-}
-
-/// 检查调转方向
-fn on_flip_direction(mut query: Query<(&mut Transform, &Animator), With<Skeleton>>) {
-    for (mut transform, animator) in &mut query {
-        let facing_direction = animator.get_float("facing_direction");
-        if transform.scale.x * facing_direction < 0. {
-            transform.scale.x *= -1.;
-        }
-    }
-}
-
-/// 检查移动
-fn on_move(mut query: Query<(&LinearVelocity, &mut Animator), With<Skeleton>>) {
-    for (vel, mut animator) in query.iter_mut() {
-        let is_moving = vel.x != 0.;
-        animator.set_bool("is_moving", is_moving);
-    }
-}
-
 pub struct SkeletonPlugin<S: States> {
     pub state: S
 }
 
 impl<S: States> Plugin for SkeletonPlugin<S> {
     fn build(&self, app: &mut App) {
-        app.add_plugins(SkeletonBehaviourPlugin { state: self.state.clone() });
+        app.add_plugins(GroundBehaviourPlugin { state: self.state.clone() });
         app.add_systems(OnEnter(self.state.clone()), setup_enemy.run_if(in_state(self.state.clone())));
-        app.add_systems(
-            FixedUpdate,
-            (
-                check_contact.run_if(in_state(self.state.clone())),
-                on_move.run_if(in_state(self.state.clone())),
-                on_flip_direction.run_if(in_state(self.state.clone())),
-            ),
-        );
-        app.add_observer(on_death_exit);
-        app.add_observer(on_attack_enter);
-        app.add_observer(on_attack_exit);
-        app.add_observer(on_stun_enter);
-        app.add_observer(on_stun_exit);
     }
 }
@@ -0,0 +1,154 @@
+//! 敌人生成器
+//! 每个生成器负责一个敌人，按重生规则在敌人死亡后重新生成：不重生、在存档点休息后、
+//! 玩家离开区域再回来时、或经过一段时间后。
+
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+use crate::checkpoint::Rested;
+use crate::enemy::archetype::{spawn_enemy, Enemy, EnemyConfig};
+use crate::player::Player;
+
+/// 重生规则
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RespawnPolicy {
+    /// 死亡后不再生成
+    Never,
+    /// 玩家在存档点休息后重生
+    OnRest,
+    /// 玩家离开`radius`范围后再回来时重生
+    OnReenter { radius: f32 },
+    /// 死亡一段时间后重生
+    After(f32),
+}
+
+/// 敌人生成器，生成位置为自身位置
+#[derive(Component)]
+pub struct EnemySpawner {
+    pub config: &'static EnemyConfig,
+    pub policy: RespawnPolicy,
+    /// 生成后的额外初始化，如插入敌人专属组件
+    pub setup: Option<fn(&mut EntityCommands)>,
+    /// 当前生成的敌人
+    pub enemy: Option<Entity>,
+    /// 敌人死亡后经过的时间
+    pub timer: f32,
+    /// 玩家是否已离开范围
+    pub away: bool,
+}
+
+impl EnemySpawner {
+    pub fn new(config: &'static EnemyConfig, policy: RespawnPolicy) -> Self {
+        Self {
+            config,
+            policy,
+            setup: None,
+            enemy: None,
+            timer: 0.,
+            away: false,
+        }
+    }
+
+    /// 设置生成后的额外初始化
+    pub fn with_setup(mut self, setup: fn(&mut EntityCommands)) -> Self {
+        self.setup = Some(setup);
+        self
+    }
+}
+
+/// 由生成器生成敌人
+fn spawn_from(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    spawner: &mut EnemySpawner,
+    position: Vec2,
+) {
+    let entity = spawn_enemy(commands, asset_server, texture_atlas_layouts, spawner.config, position);
+    if let Some(setup) = spawner.setup {
+        setup(&mut commands.entity(entity));
+    }
+    spawner.enemy = Some(entity);
+    spawner.timer = 0.;
+    spawner.away = false;
+}
+
+/// 生成器加入时生成第一个敌人
+fn on_add_spawner(
+    trigger: Trigger<OnAdd, EnemySpawner>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut spawners: Query<(&mut EnemySpawner, &Transform)>,
+) {
+    let (mut spawner, transform) = spawners.get_mut(trigger.target()).unwrap();
+    let position = transform.translation.truncate();
+    spawn_from(&mut commands, &asset_server, &mut texture_atlas_layouts, &mut spawner, position);
+}
+
+/// 按时间与区域规则重生
+fn respawn_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    player: Single<&Transform, With<Player>>,
+    enemies: Query<(), With<Enemy>>,
+    mut spawners: Query<(&mut EnemySpawner, &Transform), Without<Player>>,
+) {
+    let player = player.translation.truncate();
+    for (mut spawner, transform) in &mut spawners {
+        if spawner.enemy.is_some_and(|enemy| enemies.contains(enemy)) {
+            continue;
+        }
+        spawner.enemy = None;
+        let position = transform.translation.truncate();
+        let respawn = match spawner.policy {
+            RespawnPolicy::Never | RespawnPolicy::OnRest => false,
+            RespawnPolicy::OnReenter { radius } => {
+                let inside = player.distance(position) <= radius;
+                if !inside {
+                    spawner.away = true;
+                }
+                spawner.away && inside
+            }
+            RespawnPolicy::After(seconds) => {
+                spawner.timer += time.delta_secs();
+                spawner.timer >= seconds
+            }
+        };
+        if respawn {
+            spawn_from(&mut commands, &asset_server, &mut texture_atlas_layouts, &mut spawner, position);
+        }
+    }
+}
+
+/// 在存档点休息后重生
+fn on_rested(
+    _trigger: Trigger<Rested>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    enemies: Query<(), With<Enemy>>,
+    mut spawners: Query<(&mut EnemySpawner, &Transform)>,
+) {
+    for (mut spawner, transform) in &mut spawners {
+        if spawner.policy != RespawnPolicy::OnRest || spawner.enemy.is_some_and(|enemy| enemies.contains(enemy)) {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        spawn_from(&mut commands, &asset_server, &mut texture_atlas_layouts, &mut spawner, position);
+    }
+}
+
+pub struct SpawnerPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for SpawnerPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, respawn_system.run_if(in_state(self.state.clone())));
+        app.add_observer(on_add_spawner);
+        app.add_observer(on_rested);
+    }
+}
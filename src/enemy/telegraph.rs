@@ -0,0 +1,177 @@
+//! 攻击预警
+//! 敌人进入配置的动画状态（通常是攻击前摇）时显示预警：全身闪色、武器闪光、地面范围指示与音效，
+//! 离开该状态时移除。不同攻击使用不同的预警，玩家可以据此辨认招式。
+
+use bevy::prelude::*;
+use bevy_kira_audio::{Audio, AudioControl};
+
+use crate::animator::Animator;
+use crate::enemy::archetype::Enemy;
+
+/// 闪色的频率
+const FLASH_FREQUENCY: f32 = 12.;
+/// 闪光的尺寸
+const GLINT_SIZE: f32 = 8.;
+/// 闪光旋转速度
+const GLINT_SPIN: f32 = 6.;
+
+/// 预警表现
+#[derive(Debug, Clone, Copy)]
+pub enum TelegraphCue {
+    /// 全身在原色与该颜色之间闪烁
+    Flash { color: Color },
+    /// 在身体某处显示旋转的闪光，如武器尖端
+    Glint { offset: Vec2, color: Color },
+    /// 标出攻击将覆盖的范围
+    Area { size: Vec2, offset: Vec2, color: Color },
+}
+
+/// 预警配置
+#[derive(Debug)]
+pub struct TelegraphConfig {
+    /// 触发预警的动画状态
+    pub state: &'static str,
+    /// 预警表现
+    pub cues: &'static [TelegraphCue],
+    /// 预警音效
+    pub sound: Option<&'static str>,
+}
+
+/// 正在预警
+#[derive(Component, Debug)]
+pub struct Telegraphing {
+    /// 触发预警的动画状态
+    state: &'static str,
+    /// 闪色颜色
+    flash: Option<Color>,
+    /// 预警生成的实体
+    cues: Vec<Entity>,
+    timer: f32,
+}
+
+/// 预警生成的闪光
+#[derive(Component)]
+struct Glint;
+
+/// 独立的地面标记，到时自动消失
+#[derive(Component)]
+pub struct TelegraphMarker {
+    /// 剩余时间
+    pub lifetime: f32,
+}
+
+/// 在世界中生成范围标记，用于不跟随敌人的预警（如瞬移落点）
+pub fn spawn_marker(commands: &mut Commands, position: Vec2, size: Vec2, color: Color, lifetime: f32) -> Entity {
+    commands.spawn((
+        Sprite::from_color(color, size),
+        Transform::from_xyz(position.x, position.y, -0.1),
+        TelegraphMarker { lifetime },
+    )).id()
+}
+
+/// 生成跟随敌人的预警实体
+fn spawn_cue(commands: &mut Commands, entity: Entity, cue: &TelegraphCue) -> Option<Entity> {
+    match *cue {
+        TelegraphCue::Flash { .. } => None,
+        TelegraphCue::Glint { offset, color } => Some(commands.spawn((
+            Sprite::from_color(color, Vec2::splat(GLINT_SIZE)),
+            Transform::from_xyz(offset.x, offset.y, 0.1),
+            Glint,
+            ChildOf(entity),
+        )).id()),
+        TelegraphCue::Area { size, offset, color } => Some(commands.spawn((
+            Sprite::from_color(color, size),
+            Transform::from_xyz(offset.x, offset.y, -0.1),
+            ChildOf(entity),
+        )).id()),
+    }
+}
+
+/// 根据动画状态开始或结束预警
+fn telegraph_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    mut enemies: Query<(Entity, &Enemy, &Animator, &mut Sprite, Option<&Telegraphing>)>,
+) {
+    for (entity, enemy, animator, mut sprite, telegraphing) in &mut enemies {
+        let state = animator.current_state();
+        if let Some(telegraphing) = telegraphing {
+            if telegraphing.state == state {
+                continue;
+            }
+            // 离开预警状态
+            for cue in &telegraphing.cues {
+                commands.entity(*cue).despawn();
+            }
+            if telegraphing.flash.is_some() {
+                sprite.color = Color::WHITE;
+            }
+            commands.entity(entity).remove::<Telegraphing>();
+        }
+
+        let Some(config) = enemy.config.telegraphs.iter().find(|t| t.state == state) else { continue; };
+        let cues = config.cues.iter().filter_map(|cue| spawn_cue(&mut commands, entity, cue)).collect();
+        let flash = config.cues.iter().find_map(|cue| match cue {
+            TelegraphCue::Flash { color } => Some(*color),
+            _ => None,
+        });
+        if let Some(sound) = config.sound {
+            audio.play(asset_server.load(sound));
+        }
+        commands.entity(entity).insert(Telegraphing {
+            state: config.state,
+            flash,
+            cues,
+            timer: 0.,
+        });
+    }
+}
+
+/// 更新闪色与闪光
+fn animate_telegraphs(
+    time: Res<Time>,
+    mut enemies: Query<(&mut Telegraphing, &mut Sprite)>,
+    mut glints: Query<&mut Transform, With<Glint>>,
+) {
+    for (mut telegraphing, mut sprite) in &mut enemies {
+        telegraphing.timer += time.delta_secs();
+        if let Some(color) = telegraphing.flash {
+            let t = (telegraphing.timer * FLASH_FREQUENCY).sin() * 0.5 + 0.5;
+            sprite.color = Color::WHITE.mix(&color, t);
+        }
+        for cue in &telegraphing.cues {
+            if let Ok(mut transform) = glints.get_mut(*cue) {
+                transform.rotation = Quat::from_rotation_z(telegraphing.timer * GLINT_SPIN);
+                transform.scale = Vec3::splat(1. + (telegraphing.timer * FLASH_FREQUENCY).sin().abs() * 0.5);
+            }
+        }
+    }
+}
+
+/// 移除到时的地面标记
+fn marker_system(mut commands: Commands, time: Res<Time>, mut markers: Query<(Entity, &mut TelegraphMarker, &mut Sprite)>) {
+    for (entity, mut marker, mut sprite) in &mut markers {
+        marker.lifetime -= time.delta_secs();
+        if marker.lifetime <= 0. {
+            commands.entity(entity).despawn();
+        } else {
+            let t = (marker.lifetime * FLASH_FREQUENCY).sin() * 0.25 + 0.75;
+            sprite.color.set_alpha(t * 0.6);
+        }
+    }
+}
+
+pub struct TelegraphPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for TelegraphPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (
+            telegraph_system.run_if(in_state(self.state.clone())),
+            animate_telegraphs.run_if(in_state(self.state.clone())),
+            marker_system.run_if(in_state(self.state.clone())),
+        ));
+    }
+}
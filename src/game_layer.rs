@@ -19,4 +19,6 @@ pub enum GameLayer {
     Sensor,
     /// 地面
     Ground, 
+    /// 可交互机关（如可破坏墙体）
+    Interactive,
 }
@@ -1,9 +1,10 @@
 //! 生成获取能力UI
 
 use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
 
-use crate::items::{ItemList, NearingItem, NotpickedItems};
-use crate::player::Player;
+use crate::controls::{MenuAction, Prompt};
+use crate::items::{GotItem, ItemList};
 use crate::PausedState;
 
 /// 菜单项
@@ -20,16 +21,11 @@ pub struct UI;
 /// 生成UI
 fn spawn_box(
     mut commands: Commands, 
-    player: Single<&NearingItem, With<Player>>,
-    items: Query<&NotpickedItems>,
+    got_item: Res<GotItem>,
     item_list: Res<ItemList>,
     asset_server: Res<AssetServer>,
 ) {
-    let nearing_items = player.into_inner();
-    let item = (**nearing_items).get(0).unwrap();
-    let item_info = items.get(*item).unwrap();
-    let id = &item_info.id;
-    let info = item_list.infos.get(id).unwrap();
+    let info = item_list.infos.get(&got_item.0).unwrap();
     
     let ui_container = Node {
         width: Val::Percent(100.0),
@@ -82,7 +78,7 @@ fn spawn_box(
     let start_text = Text::new("[ Continue ]");
 
     let name_text = Text::new(info.name.clone());
-    let desc_text = Text::new(info.description.clone());
+    let desc_text = Text::new("");
    
     let font = TextFont {
         font: asset_server.load("UI/Fonts/m5x7.ttf"),
@@ -118,7 +114,7 @@ fn spawn_box(
         desc_node,
     )).with_children(|parent| {
         parent.spawn((
-            desc_text, sfont.clone(), Label
+            desc_text, sfont.clone(), Label, Prompt::new(&info.description)
         ));
     }).id();
 
@@ -134,19 +130,17 @@ fn spawn_box(
     commands
         .entity(ui_entity)
         .add_children(&[title_entity, name_node_entity, desc_node_entity, start_node_entity]);
-
-    commands.entity(*item).despawn();
 }
 
 /// 处理输入
 fn handle_enter(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    menu_input: Res<ActionState<MenuAction>>,
     items: Query<&MenuItem>,
     mut commands: Commands,
     ui: Query<Entity, With<UI>>,
     mut next_state: ResMut<NextState<PausedState>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Enter) {
+    if menu_input.just_pressed(&MenuAction::Confirm) {
         for item in &items {
             if item.is_selected {
                 if item.id == 0 {
@@ -158,7 +152,7 @@ fn handle_enter(
                 break;
             }
         }
-    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+    } else if menu_input.just_pressed(&MenuAction::Back) {
         if let Ok(entity) = ui.single() {
             commands.entity(entity).despawn();
         }
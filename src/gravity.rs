@@ -0,0 +1,97 @@
+//! 重力方向
+//! 角色受到的重力方向由`GravityDirection`决定。改变方向时同步翻转重力、控制器的上方向与精灵的Y缩放，
+//! 地面与天花板检测、跳跃方向和相机偏移都以它为准。玩家的重力反转与武师的天花板阶段都使用这一机制。
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_tnua::prelude::*;
+
+/// 重力方向
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum GravityDirection {
+    /// 正常重力，向下
+    #[default]
+    Down,
+    /// 反转重力，向上
+    Up,
+}
+
+impl GravityDirection {
+    /// “上”在世界Y轴上的符号，重力反转时为-1
+    pub fn sign(&self) -> f32 {
+        match self {
+            GravityDirection::Down => 1.,
+            GravityDirection::Up => -1.,
+        }
+    }
+
+    /// 控制器的上方向
+    pub fn up(&self) -> Dir3 {
+        match self {
+            GravityDirection::Down => Dir3::Y,
+            GravityDirection::Up => Dir3::NEG_Y,
+        }
+    }
+
+    /// 二维的上方向，用于地面与天花板检测
+    pub fn up_2d(&self) -> Dir2 {
+        match self {
+            GravityDirection::Down => Dir2::Y,
+            GravityDirection::Up => Dir2::NEG_Y,
+        }
+    }
+
+    /// 反转后的方向
+    pub fn flipped(&self) -> Self {
+        match self {
+            GravityDirection::Down => GravityDirection::Up,
+            GravityDirection::Up => GravityDirection::Down,
+        }
+    }
+}
+
+/// 方向改变时翻转重力，保留重力大小
+fn sync_gravity(mut query: Query<(&GravityDirection, &mut GravityScale), Changed<GravityDirection>>) {
+    for (direction, mut gravity) in &mut query {
+        gravity.0 = gravity.0.abs() * direction.sign();
+    }
+}
+
+/// 精灵与碰撞体随重力方向上下翻转，子实体（判定框等）一起翻转
+fn sync_orientation(mut query: Query<(&GravityDirection, &mut Transform)>) {
+    for (direction, mut transform) in &mut query {
+        if transform.scale.y * direction.sign() < 0. {
+            transform.scale.y *= -1.;
+        }
+    }
+}
+
+/// 把控制器的上方向设为重力方向的反方向，地面检测与跳跃随之翻转
+fn orient_controller(mut query: Query<(&GravityDirection, &mut TnuaController)>) {
+    for (direction, mut controller) in &mut query {
+        let Some((walk, _)) = controller.concrete_basis::<TnuaBuiltinWalk>() else { continue; };
+        if walk.up == direction.up() { continue; }
+        let walk = TnuaBuiltinWalk { up: direction.up(), ..walk.clone() };
+        controller.basis(walk);
+    }
+}
+
+pub struct GravityPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for GravityPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.register_type::<GravityDirection>();
+        app.add_systems(
+            FixedUpdate,
+            (
+                (sync_gravity, sync_orientation).run_if(in_state(self.state.clone())),
+                // 在设置控制器的系统之后、Tnua计算之前运行
+                orient_controller
+                    .in_set(TnuaUserControlsSystemSet)
+                    .run_if(in_state(self.state.clone())),
+            ),
+        );
+    }
+}
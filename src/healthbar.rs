@@ -2,6 +2,7 @@
 
 use bevy::prelude::*;
 
+use crate::controls::Prompt;
 use crate::damagable::Damagable;
 use crate::items::{ActiveItems, ItemBag, ItemList};
 use crate::player::Player;
@@ -179,7 +180,7 @@ fn spawn_box(
         hint_container,
     )).with_children(|parent| {
         parent.spawn((
-            hint, big_font.clone(), Label, Hint
+            hint, big_font.clone(), Label, Hint, Prompt::default()
         ));
     }).id();
 
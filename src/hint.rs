@@ -3,7 +3,7 @@
 use bevy::prelude::*;
 use avian2d::prelude::*;
 
-use crate::{game_layer::GameLayer, healthbar::Hint, player::Player};
+use crate::{controls::Prompt, game_layer::GameLayer, healthbar::Hint, player::Player};
 /// 上下左右提示
 #[derive(Component)]
 pub struct WASDHint;
@@ -85,36 +85,36 @@ fn print_started_collisions(
     mut collision_event_reader: EventReader<CollisionStarted>,
     player: Query<&Player>, hint: Query<&WASDHint>, bhint: Query<&BattleHint>,
     jhint: Query<&JumpHint>, shint: Query<&SlideHint>, ihint: Query<&ItemHint>, uhint: Query<&UseHint>,
-    mut text: Single<&mut Text, With<Hint>>,
+    mut prompt: Single<&mut Prompt, With<Hint>>,
 ) {
     for CollisionStarted(entity1, entity2) in collision_event_reader.read() {
         if (player.contains(*entity1) && hint.contains(*entity2)) ||
            (player.contains(*entity2) && hint.contains(*entity1))  {
-            text.0 = "Move: [W] [A] [S] [D] \nOpen Item Bag: [Esc][Open Bag]".to_string();
+            prompt.set("Move: {Move} \nOpen Item Bag: {Pause}[Open Bag]");
         }
         if (player.contains(*entity1) && bhint.contains(*entity2)) ||
            (player.contains(*entity2) && bhint.contains(*entity1))  {
-            text.0 = "Attack: [Left Mouse] \nDefense: [Right Mouse]".to_string();
+            prompt.set("Attack: {Attack} \nDefense: {Defense}");
         }
 
         if (player.contains(*entity1) && jhint.contains(*entity2)) ||
            (player.contains(*entity2) && jhint.contains(*entity1))  {
-            text.0 = "Jump: [Space]".to_string();
+            prompt.set("Jump: {Jump}");
         }
 
         if (player.contains(*entity1) && shint.contains(*entity2)) ||
            (player.contains(*entity2) && shint.contains(*entity1))  {
-            text.0 = "Run: long [Shift] \nSlide: short [Shift]".to_string();
+            prompt.set("Run: long {Run} \nSlide: short {Run}");
         }
 
         if (player.contains(*entity1) && ihint.contains(*entity2)) ||
            (player.contains(*entity2) && ihint.contains(*entity1))  {
-            text.0 = "Pick up Item: [E]".to_string();
+            prompt.set("Pick up Item: {PickItem}");
         }
 
         if (player.contains(*entity1) && uhint.contains(*entity2)) ||
            (player.contains(*entity2) && uhint.contains(*entity1))  {
-            text.0 = "Use Items for healing: [R]".to_string();
+            prompt.set("Use Items for healing: {UseItem}");
         }
     }
 }
@@ -124,32 +124,32 @@ fn print_ended_collisions(
     mut collision_event_reader: EventReader<CollisionEnded>,
     player: Query<&Player>, hint: Query<&WASDHint>, bhint: Query<&BattleHint>,
     jhint: Query<&JumpHint>, shint: Query<&SlideHint>, ihint: Query<&ItemHint>, uhint: Query<&UseHint>,
-    mut text: Single<&mut Text, With<Hint>>,
+    mut prompt: Single<&mut Prompt, With<Hint>>,
 ) {
     for CollisionEnded(entity1, entity2) in collision_event_reader.read() {
         if (player.contains(*entity1) && hint.contains(*entity2)) ||
            (player.contains(*entity2) && hint.contains(*entity1))  {
-            text.0 = "".to_string();
+            prompt.set("");
         }
         if (player.contains(*entity1) && bhint.contains(*entity2)) ||
            (player.contains(*entity2) && bhint.contains(*entity1))  {
-            text.0 = "".to_string();
+            prompt.set("");
         }
         if (player.contains(*entity1) && jhint.contains(*entity2)) ||
            (player.contains(*entity2) && jhint.contains(*entity1))  {
-            text.0 = "".to_string();
+            prompt.set("");
         }
         if (player.contains(*entity1) && shint.contains(*entity2)) ||
            (player.contains(*entity2) && shint.contains(*entity1))  {
-            text.0 = "".to_string();
+            prompt.set("");
         }
         if (player.contains(*entity1) && ihint.contains(*entity2)) ||
            (player.contains(*entity2) && ihint.contains(*entity1))  {
-            text.0 = "".to_string();
+            prompt.set("");
         }
         if (player.contains(*entity1) && uhint.contains(*entity2)) ||
            (player.contains(*entity2) && uhint.contains(*entity1))  {
-            text.0 = "".to_string();
+            prompt.set("");
         }
     }
 }
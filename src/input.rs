@@ -6,9 +6,13 @@ use avian2d::prelude::*;
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
 use bevy_tnua::prelude::*;
+use serde::{Deserialize, Serialize};
 
+use crate::abilities::Abilities;
 use crate::animator::Animator;
-use crate::damagable::Damagable;
+use crate::gravity::GravityDirection;
+use crate::input_buffer::{BufferedAction, InputBuffer, InputBufferPlugin, InputBufferSet};
+use crate::items::AbilityType;
 use crate::items::ActiveItems;
 use crate::items::NearingItem;
 use crate::items::NotpickedItems;
@@ -23,6 +27,8 @@ const RUN_SPEED: f32 = 120.0;
 const MOVE_ACC: f32 = 600.0;
 /// 下蹲速度
 const CROUCH_SPEED: f32 = 50.0;
+/// 摇杆死区
+const STICK_DEADZONE: f32 = 0.2;
 /// 获取速度
 pub fn get_speed(animator: &Animator) -> f32 {
     if animator.get_bool("can_move") {
@@ -45,38 +51,15 @@ pub struct PlayerInputBundle {
     input_manager: InputMap<Action>,
 }
 
-impl Default for PlayerInputBundle {
-    fn default() -> Self {
-        Self {
-            input_manager: PlayerInputBundle::default_input_map(),
-        }
-    }
-}
-
-/// 设置默认键位
+/// 按键设置生成键位，见`controls`
 impl PlayerInputBundle {
-    fn default_input_map() -> InputMap<Action> {
-        let mut input_map = InputMap::default();
-
-        input_map.insert(Action::Up, KeyCode::KeyW);
-        input_map.insert(Action::Down, KeyCode::KeyS);
-        input_map.insert(Action::Left, KeyCode::KeyA);
-        input_map.insert(Action::Right, KeyCode::KeyD);
-        input_map.insert(Action::Jump, KeyCode::Space);
-        input_map.insert(Action::Run, KeyCode::ShiftLeft);
-        input_map.insert(Action::Crouch, KeyCode::KeyC);
-        input_map.insert(Action::Attack, MouseButton::Left);
-        input_map.insert(Action::Defense, MouseButton::Right);
-        input_map.insert(Action::UseItem, KeyCode::KeyR);
-        input_map.insert(Action::PickItem, KeyCode::KeyE);
-        input_map.insert(Action::ReverseGravity, KeyCode::KeyG);
-        input_map.insert(Action::ChangeItem, KeyCode::Tab);
-        input_map
+    pub fn new(input_manager: InputMap<Action>) -> Self {
+        Self { input_manager }
     }
 }
 
 /// 键位对应的动作
-#[derive(Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect)]
+#[derive(Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect, Serialize, Deserialize)]
 pub enum Action {
     Up,
     Down,
@@ -90,11 +73,31 @@ pub enum Action {
     UseItem,
     PickItem,
     ReverseGravity,
-    ChangeItem
+    ChangeItem,
+    /// 手柄摇杆的模拟移动，不可改键
+    #[actionlike(DualAxis)]
+    Move,
 }
 
 /// 每个动作对应的一些方法
 impl Action {
+    /// 所有动作，按设置界面中的顺序
+    pub const ALL: [Self; 13] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Jump,
+        Action::Run,
+        Action::Crouch,
+        Action::Attack,
+        Action::Defense,
+        Action::UseItem,
+        Action::PickItem,
+        Action::ReverseGravity,
+        Action::ChangeItem,
+    ];
+
     const DIRECTIONS: [Self; 4] = [Action::Up, Action::Down, Action::Left, Action::Right];
 
     fn direction(self) -> Option<Dir2> {
@@ -108,12 +111,22 @@ impl Action {
     }
 }
 
+/// 玩家输入被锁定，如过场动画播放期间
+#[derive(Component)]
+pub struct InputLocked;
+
+/// 玩家输入没有被锁定
+fn input_unlocked(locked: Query<(), (With<Player>, With<InputLocked>)>) -> bool {
+    locked.is_empty()
+}
+
 /// 输入移动按键
 fn on_move(
     player: Single<
         (
             &ActionState<Action>,
             &LinearVelocity,
+            &GravityDirection,
             &mut Transform,
             &mut Animator,
             &mut TnuaController,
@@ -121,7 +134,7 @@ fn on_move(
         With<Player>,
     >,
 ) {
-    let (action_state, vel, mut transform, mut animator, mut controller) = player.into_inner();
+    let (action_state, vel, direction, mut transform, mut animator, mut controller) = player.into_inner();
 
     let mut direction_vector = Vec2::ZERO;
 
@@ -134,10 +147,20 @@ fn on_move(
         }
     }
 
+    // 没有按键输入时使用摇杆，推动幅度决定速度
+    let mut magnitude = 1.0;
+    if direction_vector == Vec2::ZERO {
+        let stick = action_state.axis_pair(&Action::Move);
+        if stick.x.abs() > STICK_DEADZONE {
+            direction_vector = Vec2::new(stick.x.signum(), 0.);
+            magnitude = ((stick.x.abs() - STICK_DEADZONE) / (1.0 - STICK_DEADZONE)).min(1.0);
+        }
+    }
+
     // 设置是否正在活动
     let is_moving = direction_vector != Vec2::ZERO;
     if is_moving {
-        direction_vector = direction_vector.normalize();
+        direction_vector = direction_vector.normalize() * magnitude;
     }
 
     // 设置朝向
@@ -152,7 +175,8 @@ fn on_move(
 
     // 设置动画状态机参数
     animator.set_bool("is_moving", is_moving);
-    animator.set_float("velocity_y", vel.y);
+    // 沿重力反方向的速度，重力反转时上升仍为正
+    animator.set_float("velocity_y", vel.y * direction.sign());
     
     // 设置控制器
     controller.basis(TnuaBuiltinWalk {
@@ -166,9 +190,14 @@ fn on_move(
 }
 
 /// 输入下蹲按键
-fn on_crouch(player: Single<(&ActionState<Action>, &mut Animator), With<Player>>) {
-    let (action_state, mut animator) = player.into_inner();
+fn on_crouch(player: Single<(&ActionState<Action>, &mut Animator, &mut InputBuffer), With<Player>>) {
+    let (action_state, mut animator, mut buffer) = player.into_inner();
     if action_state.just_pressed(&Action::Crouch) {
+        // 空中下蹲为下砸
+        if !animator.get_bool("is_grounded") {
+            buffer.press(BufferedAction::GroundPound);
+            return;
+        }
         let crouching = animator.get_bool("is_crouching");
         // 通知动画状态机下蹲
         animator.set_bool("is_crouching", !crouching);
@@ -177,12 +206,12 @@ fn on_crouch(player: Single<(&ActionState<Action>, &mut Animator), With<Player>>
 
 /// 输入跳跃按键
 fn on_jump(
-    player: Single<(&ActionState<Action>, &mut Animator), With<Player>>,
+    player: Single<(&ActionState<Action>, &mut InputBuffer), With<Player>>,
 ) {
-    let (action_state, mut animator) = player.into_inner();
-    if action_state.just_pressed(&Action::Jump) && animator.get_bool("can_move") {
-        // 通知动画状态机跳跃
-        animator.set_trigger("jump");
+    let (action_state, mut buffer) = player.into_inner();
+    if action_state.just_pressed(&Action::Jump) {
+        // 缓冲跳跃，能够起跳时再通知动画状态机
+        buffer.press(BufferedAction::Jump);
     }
 }
 
@@ -195,28 +224,27 @@ fn on_run(player: Single<(&ActionState<Action>, &mut Animator), With<Player>>) {
 }
 
 /// 输入攻击按键
-fn on_attack(player: Single<(&ActionState<Action>, &mut Animator), With<Player>>) {
-    let (action_state, mut animator) = player.into_inner();
+fn on_attack(player: Single<(&ActionState<Action>, &mut InputBuffer), With<Player>>) {
+    let (action_state, mut buffer) = player.into_inner();
     if action_state.just_pressed(&Action::Attack) {
-        animator.set_trigger("attack");
+        buffer.press(BufferedAction::Attack);
     }
 }
 
 /// 输入防守按键
-fn on_defense(player: Single<(&ActionState<Action>, &mut Animator, &mut Damagable), With<Player>>) {
-    let (action_state, mut animator, mut damagable) = player.into_inner();
+fn on_defense(player: Single<(&ActionState<Action>, &mut InputBuffer), With<Player>>) {
+    let (action_state, mut buffer) = player.into_inner();
     if action_state.just_pressed(&Action::Defense) {
-        animator.set_trigger("defense");
-        damagable.set_defending(true);
+        buffer.press(BufferedAction::Defense);
     }
 }
 
 /// 输入滑行按键
 fn on_slide(
     time: Res<Time>,
-    player: Single<(&ActionState<Action>, &mut Animator, &mut Damagable), With<Player>>,
+    player: Single<(&ActionState<Action>, &mut Animator, &mut InputBuffer), With<Player>>,
 ) {
-    let (action_state, mut animator, mut damagable) = player.into_inner();
+    let (action_state, mut animator, mut buffer) = player.into_inner();
     // 计算按跑步按键时间
     if action_state.pressed(&Action::Run) {
         let shift_press_time = animator.get_float("shift_press_time");
@@ -226,8 +254,7 @@ fn on_slide(
         let shift_press_time = animator.get_float("shift_press_time");
         // 短按 -> 滑行
         if shift_press_time <= 0.4 {
-            animator.set_trigger("slide");
-            damagable.set_invincible_with_time(0.5);
+            buffer.press(BufferedAction::Slide);
         }
         animator.set_float("shift_press_time", 0.0);
     }
@@ -265,12 +292,12 @@ fn on_pick(
 
 /// 输入反转重力按键
 fn on_reverse(
-    player: Single<(&Animator, &mut GravityScale, &ActionState<Action>), With<Player>>,
+    player: Single<(&Abilities, &mut GravityDirection, &ActionState<Action>), With<Player>>,
 ) {
-    let (animator, mut gravity, action_state) = player.into_inner();
+    let (abilities, mut direction, action_state) = player.into_inner();
     // 若已经获得能力
-    if action_state.just_pressed(&Action::ReverseGravity) && animator.get_bool("can_reverse_gravity") {
-        gravity.0 = -gravity.0 - 100.;
+    if action_state.just_pressed(&Action::ReverseGravity) && abilities.has(AbilityType::ReverseGravity) {
+        *direction = direction.flipped();
     }
 }
 
@@ -292,6 +319,7 @@ pub struct PlayerInputPlugin<S: States> {
 impl<S: States> Plugin for PlayerInputPlugin<S> {
     fn build(&self, app: &mut App) {
         app.add_plugins(InputManagerPlugin::<Action>::default());
+        app.add_plugins(InputBufferPlugin { state: self.state.clone() });
         app.add_systems(
             Update,
             (
@@ -306,7 +334,7 @@ impl<S: States> Plugin for PlayerInputPlugin<S> {
                 on_pick.run_if(in_state(self.state.clone())),
                 on_reverse.run_if(in_state(self.state.clone())),
                 on_change_item.run_if(in_state(self.state.clone())),
-            )
+            ).run_if(input_unlocked).before(InputBufferSet)
         );
     }
 }
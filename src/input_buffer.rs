@@ -0,0 +1,225 @@
+//! 输入缓冲
+//! 跳跃、攻击、滑行、防守的按键会被记住一小段时间，等动画状态机能够响应时再触发，
+//! 在上一个动作结束前按下的输入不再丢失，也不会在很久之后才突然生效。
+//! 另外提供走下平台后的土狼时间，以及提前松开跳跃键时截断上升速度的短跳。
+//! 二段跳、空中冲刺、下砸等能力是否可用由`Abilities`判断。
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::abilities::Abilities;
+use crate::animator::Animator;
+use crate::damagable::Damagable;
+use crate::gravity::GravityDirection;
+use crate::input::Action;
+use crate::items::AbilityType;
+use crate::player::Player;
+
+/// 可缓冲的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferedAction {
+    Jump,
+    Attack,
+    Slide,
+    Defense,
+    GroundPound,
+}
+
+/// 输入缓冲参数
+#[derive(Resource, Debug, Clone)]
+pub struct InputBufferSettings {
+    /// 按键被记住的时间
+    pub window: f32,
+    /// 离开地面后仍可起跳的时间
+    pub coyote_time: f32,
+    /// 提前松开跳跃键时保留的上升速度比例
+    pub jump_cut: f32,
+}
+
+impl Default for InputBufferSettings {
+    fn default() -> Self {
+        Self {
+            window: 0.15,
+            coyote_time: 0.1,
+            jump_cut: 0.5,
+        }
+    }
+}
+
+/// 玩家的输入缓冲
+#[derive(Component, Debug, Default)]
+pub struct InputBuffer {
+    /// 尚未触发的按键及其已等待的时间
+    presses: Vec<(BufferedAction, f32)>,
+    /// 离开地面的时间
+    airborne: f32,
+    /// 本次离地是否由跳跃造成
+    jumped: bool,
+    /// 即将开始的跳跃发生在空中（土狼时间内或二段跳）
+    pub air_jump: bool,
+    /// 本次离地是否已经二段跳
+    double_jumped: bool,
+    /// 本次离地是否已经空中冲刺
+    air_dashed: bool,
+    /// 跳跃上升中，松开跳跃键时截断
+    rising: bool,
+}
+
+impl InputBuffer {
+    /// 记录一次按键，同一动作只保留最新的一次
+    pub fn press(&mut self, action: BufferedAction) {
+        self.presses.retain(|(buffered, _)| *buffered != action);
+        self.presses.push((action, 0.));
+    }
+
+    /// 跳跃开始，由进入跳跃状态时调用
+    pub fn start_jump(&mut self) {
+        self.jumped = true;
+        self.air_jump = false;
+        self.rising = true;
+    }
+}
+
+/// 输入缓冲系统集，按键输入系统在它之前运行
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InputBufferSet;
+
+/// 记录离地时间，落地后重置跳跃状态
+fn track_airborne(time: Res<Time>, player: Single<(&Animator, &mut InputBuffer), With<Player>>) {
+    let (animator, mut buffer) = player.into_inner();
+    if animator.get_bool("is_grounded") {
+        // 起跳后离地前的几帧仍然触地，不能在这时重置
+        if buffer.airborne > 0. {
+            buffer.jumped = false;
+            buffer.rising = false;
+            buffer.double_jumped = false;
+            buffer.air_dashed = false;
+        }
+        buffer.airborne = 0.;
+    } else {
+        buffer.airborne += time.delta_secs();
+    }
+}
+
+/// 尝试触发缓冲的动作，成功时返回true
+fn try_fire(
+    action: BufferedAction,
+    settings: &InputBufferSettings,
+    animator: &mut Animator,
+    damagable: &mut Damagable,
+    abilities: &Abilities,
+    buffer: &mut InputBuffer,
+) -> bool {
+    match action {
+        BufferedAction::Jump => {
+            if !animator.get_bool("can_move") { return false; }
+            let grounded = animator.get_bool("is_grounded");
+            let wall = animator.get_bool("is_on_wall") && abilities.has(AbilityType::WallJump);
+            let coyote = !grounded && !buffer.jumped && buffer.airborne <= settings.coyote_time;
+            let double = !grounded && !coyote && !buffer.double_jumped && abilities.has(AbilityType::DoubleJump);
+            if (grounded || wall || coyote) && animator.accepts("jump") {
+                buffer.air_jump = coyote && !wall;
+                animator.set_trigger("jump");
+                true
+            } else if (coyote || double) && animator.accepts("air_jump") {
+                buffer.air_jump = true;
+                buffer.double_jumped |= !coyote;
+                animator.set_trigger("air_jump");
+                true
+            } else {
+                false
+            }
+        }
+        BufferedAction::Attack => {
+            if !animator.accepts("attack") { return false; }
+            animator.set_trigger("attack");
+            true
+        }
+        BufferedAction::Slide => {
+            if animator.accepts("slide") {
+                animator.set_trigger("slide");
+                damagable.set_invincible_with_time(0.5);
+                true
+            } else if !buffer.air_dashed && abilities.has(AbilityType::AirDash) && animator.accepts("air_dash") {
+                // 空中按滑行为空中冲刺
+                buffer.air_dashed = true;
+                animator.set_trigger("air_dash");
+                true
+            } else {
+                false
+            }
+        }
+        BufferedAction::Defense => {
+            if !animator.accepts("defense") { return false; }
+            animator.set_trigger("defense");
+            damagable.set_defending(true);
+            true
+        }
+        BufferedAction::GroundPound => {
+            if !abilities.has(AbilityType::GroundPound) || !animator.accepts("ground_pound") { return false; }
+            animator.set_trigger("ground_pound");
+            true
+        }
+    }
+}
+
+/// 触发能够响应的缓冲动作，丢弃超时的按键
+fn replay_buffer(
+    time: Res<Time>,
+    settings: Res<InputBufferSettings>,
+    player: Single<(&mut Animator, &mut Damagable, &Abilities, &mut InputBuffer), With<Player>>,
+) {
+    let (mut animator, mut damagable, abilities, mut buffer) = player.into_inner();
+    let presses = std::mem::take(&mut buffer.presses);
+    for (action, age) in presses {
+        if try_fire(action, &settings, &mut animator, &mut damagable, abilities, &mut buffer) {
+            continue;
+        }
+        let age = age + time.delta_secs();
+        // 连段的下一段还没开放时保留攻击输入，等到可以接招时再触发
+        let chaining = action == BufferedAction::Attack && animator.awaits("attack");
+        if age <= settings.window || chaining {
+            buffer.presses.push((action, age));
+        }
+    }
+}
+
+/// 上升中松开跳跃键时截断上升速度
+fn jump_cut(
+    settings: Res<InputBufferSettings>,
+    player: Single<(&ActionState<Action>, &GravityDirection, &mut LinearVelocity, &mut InputBuffer), With<Player>>,
+) {
+    let (action_state, direction, mut velocity, mut buffer) = player.into_inner();
+    if !buffer.rising { return; }
+    // 重力反转时向下为上升
+    let rising_speed = velocity.y * direction.sign();
+    if rising_speed <= 0. {
+        // 已经到达最高点
+        if buffer.airborne > 0. {
+            buffer.rising = false;
+        }
+        return;
+    }
+    if !action_state.pressed(&Action::Jump) {
+        velocity.y *= settings.jump_cut;
+        buffer.rising = false;
+    }
+}
+
+pub struct InputBufferPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for InputBufferPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputBufferSettings>();
+        app.add_systems(
+            Update,
+            (track_airborne, replay_buffer, jump_cut)
+                .chain()
+                .in_set(InputBufferSet)
+                .run_if(in_state(self.state.clone())),
+        );
+    }
+}
@@ -2,10 +2,10 @@
 //! 资源存储完整的道具信息，玩家的组件只存储道具名。
 use std::collections::HashMap;
 use bevy::prelude::*;
-use avian2d::prelude::{OnCollisionEnd, OnCollisionStart};
+use avian2d::prelude::{Collider, CollisionEventsEnabled, CollisionLayers, OnCollisionEnd, OnCollisionStart, Sensor};
 use serde::{Deserialize, Serialize};
 
-use crate::{animator::Animator, blocks::InitialGate, damagable::Damagable, healthbar::Hint, PausedState};
+use crate::{abilities::Abilities, damagable::Damagable, game_layer::GameLayer, healthbar::Hint, hint::ItemHint, signal::SignalTrigger, world_flags::{item_flag, SetWorldFlag}, PausedState};
 
 /// 使用道具时生成的道具子实体与角色的关系
 #[derive(Component)]
@@ -91,13 +91,15 @@ pub struct ItemInfo {
     pub item_type: ItemType,
 }
 
-/// 道具类别， 分为消耗性和能力型。
+/// 道具类别， 分为消耗性、能力型和货币。
 #[derive(Debug, Clone, Copy, Reflect, Serialize, Deserialize)]
 pub enum ItemType {
     /// 消耗性道具，需要使用触发能力
     Consumable(ConsumableType),
     /// 能力型道具，拾取即可获得能力
     Ability(AbilityType),
+    /// 货币，只计数不能装备
+    Currency,
 }
 
 /// 消耗性道具的类别，使用后生效
@@ -109,13 +111,21 @@ pub enum ConsumableType {
     OpenTheDoor,
 }
 
-/// 能力型道具的类别，拾取后生效
-#[derive(Debug, Clone, Copy, Reflect, Serialize, Deserialize)]
+/// 能力型道具的类别，拾取后记录在`Abilities`中
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
 pub enum AbilityType {
     /// 蹬墙跳
     WallJump,
     /// 重力反转
     ReverseGravity,
+    /// 二段跳
+    DoubleJump,
+    /// 空中冲刺
+    AirDash,
+    /// 滑墙减速
+    WallSlide,
+    /// 下砸
+    GroundPound,
 }
 
 /// 道具使用触发器
@@ -133,7 +143,6 @@ fn use_potion_observer(
     mut commands: Commands,
     item_list: Res<ItemList>,
     mut users: Query<(&mut Damagable, &mut ItemBag)>,
-    door: Query<Entity, With<InitialGate>>,
 ) {
     let user = trigger.user;
     let item = &trigger.item;
@@ -153,7 +162,7 @@ fn use_potion_observer(
                 }
                 // 开门
                 ConsumableType::OpenTheDoor => {
-                    commands.entity(door.single().unwrap()).despawn();
+                    commands.trigger(SignalTrigger::on("initial_gate"));
                 }
             }
         }
@@ -162,6 +171,29 @@ fn use_potion_observer(
     }
 }
 
+/// 在场景中生成可拾取的道具
+pub fn spawn_pickup(
+    commands: &mut Commands,
+    items: &ItemList,
+    id: &str,
+    num: u32,
+    position: Vec3,
+) -> Entity {
+    commands.spawn((
+        Sprite {
+            image: items.infos.get(id).unwrap().icon.clone(),
+            ..default()
+        },
+        Collider::rectangle(40.0, 20.0),
+        Transform::from_translation(position),
+        ItemHint,
+        NotpickedItems { id: id.to_string(), num },
+        Sensor,
+        CollisionEventsEnabled,
+        CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player])
+    )).observe(item_cantpick_observer).observe(item_canpick_observer).id()
+}
+
 /// 设置道具为可拾取的观察者系统
 /// 当玩家进入道具Sensor的时候，设置道具和玩家具有ItemNear关系
 pub fn item_canpick_observer(
@@ -196,12 +228,18 @@ pub struct PickItemTrigger {
     pub num: u32,
 }
 
+/// 获得能力型道具界面展示的道具
+#[derive(Resource, Debug, Default)]
+pub struct GotItem(pub String);
+
 /// 拾取道具观察者系统
+/// 道具也可能来自对话等其他来源，此时身边没有对应的道具实体
 fn pick_item_observer(
     trigger: Trigger<PickItemTrigger>,
     mut commands: Commands,
     item_list: Res<ItemList>,
-    mut users: Query<(&mut Animator, &mut ItemBag, &NearingItem, &mut ActiveItems)>,
+    mut users: Query<(&mut Abilities, &mut ItemBag, Option<&NearingItem>, &mut ActiveItems)>,
+    pickups: Query<&NotpickedItems>,
     mut next_state: ResMut<NextState<PausedState>>,
     text: Single<&mut Text, With<Hint>>,
 ) {
@@ -209,39 +247,52 @@ fn pick_item_observer(
     let item = &trigger.item;
     let map = &item_list.infos;
     let info = map.get(item).unwrap();
-    if let Ok((mut animator, mut bag, nearing, mut acts)) = users.get_mut(user) {
+    if let Ok((mut abilities, mut bag, nearing, mut acts)) = users.get_mut(user) {
+        // 身边与拾取的道具相同的道具实体
+        let picked: Vec<Entity> = nearing
+            .map(|nearing| (**nearing).clone())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|entity| pickups.get(*entity).is_ok_and(|pickup| &pickup.id == item))
+            .collect();
         let before_num = bag.get(item);
         // 如果超过最大可存放数量，则不能拾取
         if before_num + trigger.num > info.max_stack { return; }
         // 把道具放进背包
         bag.put(item.clone(), before_num + trigger.num);
+        // 唯一道具拾取后不再生成
+        if info.max_stack == 1 {
+            commands.trigger(SetWorldFlag(item_flag(item)));
+        }
         match info.item_type {
             // 能力型道具
             ItemType::Ability(it) => {
-                match it {
-                    // 蹬墙跳
-                    AbilityType::WallJump => {
-                        animator.set_bool("can_wall_jump", true);
-                    }
-                    // 反转重力
-                    AbilityType::ReverseGravity => {
-                        animator.set_bool("can_reverse_gravity", true);
-                    }
+                abilities.unlock(it);
+                for entity in &picked {
+                    commands.entity(*entity).despawn();
                 }
+                commands.insert_resource(GotItem(item.clone()));
                 text.into_inner().0 = "".to_string();
                 // 转换为获取能力型道具特殊UI状态
                 next_state.set(PausedState::GetItem);
             }
             // 消耗性道具
-            _ => {
-                for item in (**nearing).clone() {
-                    commands.entity(item).despawn();
+            ItemType::Consumable(_) => {
+                for entity in &picked {
+                    commands.entity(*entity).despawn();
                 }
                 if !acts.items.contains(item) {
                     acts.items.push(item.clone());
                 }
                 text.into_inner().0 = "".to_string();
             }
+            // 货币
+            ItemType::Currency => {
+                for entity in &picked {
+                    commands.entity(*entity).despawn();
+                }
+                text.into_inner().0 = "".to_string();
+            }
         }
     }
 }
@@ -266,7 +317,7 @@ fn init_items(assets_server: Res<AssetServer>, mut item_list: ResMut<ItemList>)
     item_list.infos.insert("Key".to_string(), key);
     let fire_glove = ItemInfo {
         name: "Fire Glove".to_string(),
-        description: "Wall Jump: Press [Space] when at wall".to_string(),
+        description: "Wall Jump: Press {Jump} when at wall".to_string(),
         icon: assets_server.load("Art/Kyrise's 16x16 RPG Icon Pack - V1.3/icons/16x16/gloves_01e.png"),
         max_stack: 1,
         item_type: ItemType::Ability(AbilityType::WallJump),
@@ -274,12 +325,52 @@ fn init_items(assets_server: Res<AssetServer>, mut item_list: ResMut<ItemList>)
     item_list.infos.insert("FireGlove".to_string(), fire_glove);
     let martial_scroll = ItemInfo {
         name: "Martial Scroll".to_string(),
-        description: "Reverse Gravity: Press [G]".to_string(),
+        description: "Reverse Gravity: Press {ReverseGravity}".to_string(),
         icon: assets_server.load("Art/Kyrise's 16x16 RPG Icon Pack - V1.3/icons/16x16/scroll_01a.png"),
         max_stack: 1,
         item_type: ItemType::Ability(AbilityType::ReverseGravity),
     };
     item_list.infos.insert("MartialScroll".to_string(), martial_scroll);
+    let climbing_claws = ItemInfo {
+        name: "Climbing Claws".to_string(),
+        description: "Wall Slide: Hold towards a wall to slide down slowly".to_string(),
+        icon: assets_server.load("Art/Kyrise's 16x16 RPG Icon Pack - V1.3/icons/16x16/gloves_01b.png"),
+        max_stack: 1,
+        item_type: ItemType::Ability(AbilityType::WallSlide),
+    };
+    item_list.infos.insert("ClimbingClaws".to_string(), climbing_claws);
+    let feather_charm = ItemInfo {
+        name: "Feather Charm".to_string(),
+        description: "Double Jump: Press {Jump} in the air".to_string(),
+        icon: assets_server.load("Art/Kyrise's 16x16 RPG Icon Pack - V1.3/icons/16x16/necklace_01a.png"),
+        max_stack: 1,
+        item_type: ItemType::Ability(AbilityType::DoubleJump),
+    };
+    item_list.infos.insert("FeatherCharm".to_string(), feather_charm);
+    let dash_boots = ItemInfo {
+        name: "Dash Boots".to_string(),
+        description: "Air Dash: Tap {Run} in the air".to_string(),
+        icon: assets_server.load("Art/Kyrise's 16x16 RPG Icon Pack - V1.3/icons/16x16/boots_01a.png"),
+        max_stack: 1,
+        item_type: ItemType::Ability(AbilityType::AirDash),
+    };
+    item_list.infos.insert("DashBoots".to_string(), dash_boots);
+    let iron_gauntlet = ItemInfo {
+        name: "Iron Gauntlet".to_string(),
+        description: "Ground Pound: Press {Crouch} in the air. Breaks cracked floors".to_string(),
+        icon: assets_server.load("Art/Kyrise's 16x16 RPG Icon Pack - V1.3/icons/16x16/gloves_01c.png"),
+        max_stack: 1,
+        item_type: ItemType::Ability(AbilityType::GroundPound),
+    };
+    item_list.infos.insert("IronGauntlet".to_string(), iron_gauntlet);
+    let coin = ItemInfo {
+        name: "Coin".to_string(),
+        description: "An old coin dropped by the monsters of the castle.".to_string(),
+        icon: assets_server.load("Art/Kyrise's 16x16 RPG Icon Pack - V1.3/icons/16x16/coin_01a.png"),
+        max_stack: 9999,
+        item_type: ItemType::Currency,
+    };
+    item_list.infos.insert("Coin".to_string(), coin);
 }
 
 pub struct ItemsPlugin;
@@ -287,6 +378,7 @@ pub struct ItemsPlugin;
 impl Plugin for ItemsPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(ItemList { infos: HashMap::new() });
+        app.init_resource::<GotItem>();
         app.add_systems(Startup, init_items);
         app.add_observer(use_potion_observer);
         app.add_observer(pick_item_observer);
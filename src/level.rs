@@ -0,0 +1,150 @@
+//! 读取Tiled地图中的对象层，供机关、巡逻路线等系统使用。
+//! 瓦片与碰撞体仍由bevy_ecs_tiled生成，这里只解析对象的几何信息和自定义属性。
+
+use bevy::prelude::*;
+use tiled::{Loader, ObjectShape, PropertyValue};
+use std::collections::HashMap;
+
+use crate::tiles::{MAP_ORIGIN, MAP_PATH};
+
+/// 地图中的单个对象
+#[derive(Debug, Clone)]
+pub struct LevelObject {
+    /// Tiled对象ID
+    pub id: u32,
+    /// 对象名
+    pub name: String,
+    /// 对象类别（Tiled中的Class）
+    pub class: String,
+    /// 所在图层名
+    pub layer: String,
+    /// 对象中心的世界坐标
+    pub position: Vec2,
+    /// 对象尺寸（矩形对象）
+    pub size: Vec2,
+    /// 折线/多边形顶点的世界坐标
+    pub points: Vec<Vec2>,
+    /// 自定义属性
+    pub properties: HashMap<String, PropertyValue>,
+}
+
+impl LevelObject {
+    /// 获取字符串属性
+    pub fn get_string(&self, key: &str) -> Option<String> {
+        match self.properties.get(key) {
+            Some(PropertyValue::StringValue(s)) => Some(s.clone()),
+            Some(PropertyValue::FileValue(s)) => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    /// 获取浮点属性，整数属性也会被转换
+    pub fn get_float(&self, key: &str) -> Option<f32> {
+        match self.properties.get(key) {
+            Some(PropertyValue::FloatValue(f)) => Some(*f),
+            Some(PropertyValue::IntValue(i)) => Some(*i as f32),
+            _ => None,
+        }
+    }
+
+    /// 获取布尔属性
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.properties.get(key) {
+            Some(PropertyValue::BoolValue(b)) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// 获取对象引用属性，返回被引用对象的ID
+    pub fn get_object(&self, key: &str) -> Option<u32> {
+        match self.properties.get(key) {
+            Some(PropertyValue::ObjectValue(id)) if *id != 0 => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+/// 地图对象资源
+#[derive(Resource, Default)]
+pub struct LevelObjects {
+    pub objects: Vec<LevelObject>,
+    /// 整张地图的世界坐标范围
+    pub bounds: Rect,
+}
+
+impl LevelObjects {
+    /// 按类别获取对象
+    pub fn with_class<'a>(&'a self, class: &'a str) -> impl Iterator<Item = &'a LevelObject> {
+        self.objects.iter().filter(move |o| o.class == class)
+    }
+
+    /// 按ID获取对象
+    pub fn get(&self, id: u32) -> Option<&LevelObject> {
+        self.objects.iter().find(|o| o.id == id)
+    }
+}
+
+/// Tiled坐标（y轴向下）转换为世界坐标，`origin`为地图左下角的世界坐标
+fn to_world(origin: Vec2, map_height: f32, x: f32, y: f32) -> Vec2 {
+    origin + Vec2::new(x, map_height - y)
+}
+
+/// 解析地图对象层
+fn load_level_objects(mut level: ResMut<LevelObjects>) {
+    let map = match Loader::new().load_tmx_map(MAP_PATH) {
+        Ok(map) => map,
+        Err(e) => {
+            println!("Failed to parse {}: {}", MAP_PATH, e);
+            return;
+        }
+    };
+    let map_height = (map.height * map.tile_height) as f32;
+    let map_width = (map.width * map.tile_width) as f32;
+    // 地图实体的位置是左下角瓦片的中心，地图的左下角还要再偏移半个瓦片
+    let origin = MAP_ORIGIN - Vec2::new(map.tile_width as f32, map.tile_height as f32) / 2.;
+    level.bounds = Rect::from_corners(origin, origin + Vec2::new(map_width, map_height));
+
+    for layer in map.layers() {
+        let Some(object_layer) = layer.as_object_layer() else { continue; };
+        let (offset_x, offset_y) = (layer.offset_x, layer.offset_y);
+        for object in object_layer.objects() {
+            let x = object.x + offset_x;
+            let y = object.y + offset_y;
+            let (position, size, points) = match &object.shape {
+                ObjectShape::Rect { width, height } | ObjectShape::Ellipse { width, height } => (
+                    to_world(origin, map_height, x + width / 2., y + height / 2.),
+                    Vec2::new(*width, *height),
+                    vec![],
+                ),
+                ObjectShape::Polyline { points } | ObjectShape::Polygon { points } => (
+                    to_world(origin, map_height, x, y),
+                    Vec2::ZERO,
+                    points
+                        .iter()
+                        .map(|(px, py)| to_world(origin, map_height, x + px, y + py))
+                        .collect(),
+                ),
+                _ => (to_world(origin, map_height, x, y), Vec2::ZERO, vec![]),
+            };
+            level.objects.push(LevelObject {
+                id: object.id(),
+                name: object.name.clone(),
+                class: object.user_type.clone(),
+                layer: layer.name.clone(),
+                position,
+                size,
+                points,
+                properties: object.properties.clone(),
+            });
+        }
+    }
+}
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelObjects>();
+        app.add_systems(Startup, load_level_objects);
+    }
+}
@@ -0,0 +1,97 @@
+//! 本地化
+//! 对话、人名等文字以键名书写，按当前语言从`Locale/<语言>.locale.ron`中查找译文，找不到时原样显示键名，
+//! 因此直接写成文字的内容也能正常显示。语言通过`--lang <语言>`启动参数选择，默认为英文。
+
+use std::collections::HashMap;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::{prelude::*, scene::ron};
+use serde::Deserialize;
+
+/// 默认语言
+const DEFAULT_LANGUAGE: &str = "en";
+
+/// 一种语言的译文表
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct LocaleTable(pub HashMap<String, String>);
+
+/// 读取`*.locale.ron`
+#[derive(Default)]
+struct LocaleLoader;
+
+impl AssetLoader for LocaleLoader {
+    type Asset = LocaleTable;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["locale.ron"]
+    }
+}
+
+/// 当前语言的译文
+#[derive(Resource)]
+pub struct Localization {
+    /// 语言
+    pub language: String,
+    table: Handle<LocaleTable>,
+    strings: HashMap<String, String>,
+}
+
+impl Localization {
+    /// 查找译文，没有时返回键名本身
+    pub fn tr<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings.get(key).map_or(key, |s| s.as_str())
+    }
+}
+
+/// 从启动参数读取语言并加载译文表
+fn load_locale(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let args: Vec<String> = std::env::args().collect();
+    let language = args
+        .iter()
+        .position(|arg| arg == "--lang")
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+    let table = asset_server.load(format!("Locale/{}.locale.ron", language));
+    commands.insert_resource(Localization { language, table, strings: HashMap::new() });
+}
+
+/// 译文表加载或修改后更新
+fn sync_locale(
+    mut events: EventReader<AssetEvent<LocaleTable>>,
+    tables: Res<Assets<LocaleTable>>,
+    mut locale: ResMut<Localization>,
+) {
+    for event in events.read() {
+        if event.is_loaded_with_dependencies(&locale.table) || event.is_modified(&locale.table) {
+            if let Some(table) = tables.get(&locale.table) {
+                locale.strings = table.0.clone();
+            }
+        }
+    }
+}
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LocaleTable>();
+        app.init_asset_loader::<LocaleLoader>();
+        app.add_systems(PreStartup, load_locale);
+        app.add_systems(Update, sync_locale);
+    }
+}
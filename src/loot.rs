@@ -0,0 +1,401 @@
+//! 敌人掉落
+//! 掉落表以资源文件（`*.loot.ron`）描述：每种敌人有必定掉落的道具和若干次按权重抽取的掉落，
+//! 抽取使用带种子的随机数，便于复现。掉落物带物理散开，货币和消耗品靠近玩家时会被吸过来自动拾取，
+//! 能力型道具仍需手动拾取。
+
+use std::collections::HashMap;
+
+use avian2d::prelude::*;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::{prelude::*, scene::ron};
+use bevy_kira_audio::{Audio, AudioControl};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+use crate::game_layer::GameLayer;
+use crate::hint::ItemHint;
+use crate::items::{spawn_pickup, ActiveItems, ItemBag, ItemList, ItemType, NotpickedItems};
+use crate::player::Player;
+use crate::world_flags::{item_flag, WorldFlags};
+
+/// 掉落表资源路径
+const LOOT_TABLES: &str = "Loot/enemies.loot.ron";
+/// 掉落物的重力
+const LOOT_GRAVITY: f32 = 30.;
+/// 掉落时的水平速度范围
+const SCATTER_SPEED: f32 = 80.;
+/// 掉落时的向上速度范围
+const SCATTER_LIFT: (f32, f32) = (120., 220.);
+/// 掉落后多久开始被吸引
+const MAGNET_DELAY: f32 = 0.6;
+/// 开始吸引的距离
+const MAGNET_RADIUS: f32 = 60.;
+/// 吸引速度
+const MAGNET_SPEED: f32 = 260.;
+/// 自动拾取的距离
+const COLLECT_RADIUS: f32 = 10.;
+/// 拾取货币的音效
+const COLLECT_SOUND: &str = "Audio/SFX/10_UI_Menu_SFX/079_Buy_sell_01.wav";
+
+fn default_one() -> u32 {
+    1
+}
+
+/// 必定掉落的道具
+#[derive(Debug, Clone, Deserialize)]
+pub struct LootDrop {
+    pub item: String,
+    #[serde(default = "default_one")]
+    pub count: u32,
+    /// 掉落在固定的位置而不是敌人所在处
+    #[serde(default)]
+    pub at: Option<(f32, f32)>,
+}
+
+/// 按权重抽取的掉落
+#[derive(Debug, Clone, Deserialize)]
+pub struct LootEntry {
+    /// 道具id，为空表示不掉落
+    pub item: Option<String>,
+    pub weight: u32,
+    #[serde(default = "default_one")]
+    pub min: u32,
+    #[serde(default = "default_one")]
+    pub max: u32,
+}
+
+/// 一种敌人的掉落表
+#[derive(Debug, Clone, Deserialize)]
+pub struct LootTable {
+    #[serde(default)]
+    pub guaranteed: Vec<LootDrop>,
+    /// 抽取次数
+    #[serde(default = "default_one")]
+    pub rolls: u32,
+    #[serde(default)]
+    pub entries: Vec<LootEntry>,
+}
+
+impl LootTable {
+    /// 抽取一次掉落结果：道具、数量与固定掉落位置
+    pub fn roll(&self, rng: &mut StdRng) -> Vec<(String, u32, Option<Vec2>)> {
+        let mut drops: Vec<_> = self
+            .guaranteed
+            .iter()
+            .map(|drop| (drop.item.clone(), drop.count, drop.at.map(Vec2::from)))
+            .collect();
+        let total: u32 = self.entries.iter().map(|entry| entry.weight).sum();
+        if total == 0 {
+            return drops;
+        }
+        for _ in 0..self.rolls {
+            let mut pick = rng.random_range(0..total);
+            let Some(entry) = self.entries.iter().find(|entry| {
+                if pick < entry.weight {
+                    return true;
+                }
+                pick -= entry.weight;
+                false
+            }) else { continue; };
+            if let Some(item) = &entry.item {
+                let count = rng.random_range(entry.min..=entry.max.max(entry.min));
+                drops.push((item.clone(), count, None));
+            }
+        }
+        drops
+    }
+}
+
+/// 所有敌人的掉落表，以表名索引
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct LootTableSet(pub HashMap<String, LootTable>);
+
+/// 读取`*.loot.ron`
+#[derive(Default)]
+struct LootTableLoader;
+
+impl AssetLoader for LootTableLoader {
+    type Asset = LootTableSet;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["loot.ron"]
+    }
+}
+
+/// 已加载的掉落表
+#[derive(Resource)]
+pub struct LootTables(pub Handle<LootTableSet>);
+
+/// 掉落使用的随机数
+#[derive(Resource)]
+pub struct LootRng {
+    /// 种子
+    pub seed: u64,
+    pub rng: StdRng,
+}
+
+impl LootRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+impl Default for LootRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+/// 在某处按掉落表生成掉落物
+#[derive(Event, Clone, Copy)]
+pub struct DropLoot {
+    /// 掉落表名
+    pub table: &'static str,
+    pub position: Vec2,
+}
+
+/// 首领已被击败时，把掉落表中还没拾取的能力型道具放回原处
+#[derive(Event, Clone, Copy)]
+pub struct RespawnUnclaimed {
+    /// 掉落表名
+    pub table: &'static str,
+    /// 没有固定掉落位置的道具从这里起依次排开
+    pub position: Vec2,
+}
+
+/// 掉落表加载完成前收到的请求
+#[derive(Resource, Default)]
+struct PendingLoot {
+    drops: Vec<DropLoot>,
+    respawns: Vec<RespawnUnclaimed>,
+}
+
+/// 会被玩家吸引的掉落物
+#[derive(Component, Debug)]
+pub struct Magnetic {
+    /// 开始吸引前的剩余时间
+    pub delay: f32,
+    /// 是否正在被吸引
+    pub attracted: bool,
+}
+
+/// 生成带物理的掉落物
+fn spawn_loot(commands: &mut Commands, items: &ItemList, rng: &mut StdRng, id: &str, num: u32, position: Vec2) {
+    let velocity = Vec2::new(
+        rng.random_range(-SCATTER_SPEED..SCATTER_SPEED),
+        rng.random_range(SCATTER_LIFT.0..SCATTER_LIFT.1),
+    );
+    let entity = spawn_pickup(commands, items, id, num, position.extend(0.));
+    commands.entity(entity).insert((
+        RigidBody::Dynamic,
+        LockedAxes::ROTATION_LOCKED,
+        LinearVelocity(velocity),
+        GravityScale(LOOT_GRAVITY),
+    ));
+    // 与地面碰撞的实体碰撞体
+    commands.spawn((
+        Collider::circle(4.),
+        CollisionLayers::new(GameLayer::Default, [GameLayer::Ground]),
+        Restitution::new(0.3),
+        Transform::default(),
+        ChildOf(entity),
+    ));
+    if !matches!(items.infos.get(id).unwrap().item_type, ItemType::Ability(_)) {
+        // 自动拾取的掉落物不显示拾取提示，也不参与手动拾取
+        commands
+            .entity(entity)
+            .remove::<ItemHint>()
+            .insert((CollisionLayers::NONE, Magnetic { delay: MAGNET_DELAY, attracted: false }));
+    }
+}
+
+/// 按掉落表生成掉落物，跳过未知的道具
+fn drop_loot(commands: &mut Commands, items: &ItemList, set: &LootTableSet, rng: &mut StdRng, request: DropLoot) {
+    let Some(table) = set.0.get(request.table) else {
+        println!("Loot table {} not found", request.table);
+        return;
+    };
+    for (item, count, at) in table.roll(rng) {
+        let Some(info) = items.infos.get(&item) else {
+            println!("Loot item {} not found", item);
+            continue;
+        };
+        let position = at.unwrap_or(request.position);
+        if matches!(info.item_type, ItemType::Currency) {
+            // 货币一枚一枚散开
+            for _ in 0..count {
+                spawn_loot(commands, items, rng, &item, 1, position);
+            }
+        } else {
+            spawn_loot(commands, items, rng, &item, count, position);
+        }
+    }
+}
+
+/// 掉落表已加载时直接掉落，否则等加载完成
+fn drop_loot_observer(
+    trigger: Trigger<DropLoot>,
+    mut commands: Commands,
+    items: Res<ItemList>,
+    tables: Res<LootTables>,
+    sets: Res<Assets<LootTableSet>>,
+    mut rng: ResMut<LootRng>,
+    mut pending: ResMut<PendingLoot>,
+) {
+    match sets.get(&tables.0) {
+        Some(set) => drop_loot(&mut commands, &items, set, &mut rng.rng, *trigger.event()),
+        None => pending.drops.push(*trigger.event()),
+    }
+}
+
+/// 放回还没拾取的能力型道具，有固定掉落位置的放在该位置，其余从`position`起依次排开
+fn respawn_unclaimed(
+    commands: &mut Commands,
+    items: &ItemList,
+    set: &LootTableSet,
+    flags: &WorldFlags,
+    request: RespawnUnclaimed,
+) {
+    let Some(table) = set.0.get(request.table) else { return; };
+    let mut offset = 0.;
+    for drop in &table.guaranteed {
+        let Some(info) = items.infos.get(&drop.item) else {
+            println!("Loot item {} not found", drop.item);
+            continue;
+        };
+        if !matches!(info.item_type, ItemType::Ability(_)) { continue; }
+        if flags.is_set(&item_flag(&drop.item)) { continue; }
+        let at = match drop.at {
+            Some(at) => Vec2::from(at),
+            None => {
+                offset += 24.;
+                request.position + Vec2::new(offset - 24., 0.)
+            }
+        };
+        spawn_pickup(commands, items, &drop.item, drop.count, at.extend(0.));
+    }
+}
+
+/// 掉落表已加载时直接放回，否则等加载完成
+fn respawn_unclaimed_observer(
+    trigger: Trigger<RespawnUnclaimed>,
+    mut commands: Commands,
+    items: Res<ItemList>,
+    tables: Res<LootTables>,
+    sets: Res<Assets<LootTableSet>>,
+    flags: Res<WorldFlags>,
+    mut pending: ResMut<PendingLoot>,
+) {
+    match sets.get(&tables.0) {
+        Some(set) => respawn_unclaimed(&mut commands, &items, set, &flags, *trigger.event()),
+        None => pending.respawns.push(*trigger.event()),
+    }
+}
+
+/// 掉落表加载完成后处理等待中的请求
+fn flush_pending_loot(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<LootTableSet>>,
+    items: Res<ItemList>,
+    tables: Res<LootTables>,
+    sets: Res<Assets<LootTableSet>>,
+    flags: Res<WorldFlags>,
+    mut rng: ResMut<LootRng>,
+    mut pending: ResMut<PendingLoot>,
+) {
+    if !events.read().any(|event| event.is_loaded_with_dependencies(&tables.0)) { return; }
+    let Some(set) = sets.get(&tables.0) else { return; };
+    for request in std::mem::take(&mut pending.drops) {
+        drop_loot(&mut commands, &items, set, &mut rng.rng, request);
+    }
+    for request in std::mem::take(&mut pending.respawns) {
+        respawn_unclaimed(&mut commands, &items, set, &flags, request);
+    }
+}
+
+/// 吸引并自动拾取靠近玩家的掉落物
+fn magnet_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    items: Res<ItemList>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    player: Single<(&Transform, &mut ItemBag, &mut ActiveItems), With<Player>>,
+    mut loot: Query<(Entity, &Transform, &NotpickedItems, &mut Magnetic, &mut LinearVelocity), Without<Player>>,
+) {
+    let (player_transform, mut bag, mut acts) = player.into_inner();
+    let target = player_transform.translation.truncate();
+    for (entity, transform, item, mut magnetic, mut velocity) in &mut loot {
+        magnetic.delay -= time.delta_secs();
+        if magnetic.delay > 0. {
+            continue;
+        }
+        let info = items.infos.get(&item.id).unwrap();
+        let before = bag.get(&item.id);
+        // 背包已满时不吸引
+        if before + item.num > info.max_stack {
+            continue;
+        }
+        let delta = target - transform.translation.truncate();
+        if !magnetic.attracted {
+            if delta.length() > MAGNET_RADIUS {
+                continue;
+            }
+            magnetic.attracted = true;
+            commands.entity(entity).insert((RigidBody::Kinematic, GravityScale(0.)));
+        }
+        if delta.length() <= COLLECT_RADIUS {
+            bag.put(item.id.clone(), before + item.num);
+            match info.item_type {
+                ItemType::Consumable(_) => {
+                    if !acts.items.contains(&item.id) {
+                        acts.items.push(item.id.clone());
+                    }
+                }
+                ItemType::Currency => {
+                    audio.play(asset_server.load(COLLECT_SOUND));
+                }
+                ItemType::Ability(_) => {}
+            }
+            commands.entity(entity).despawn();
+            continue;
+        }
+        velocity.0 = delta.normalize_or_zero() * MAGNET_SPEED;
+    }
+}
+
+fn load_loot_tables(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(LootTables(asset_server.load(LOOT_TABLES)));
+}
+
+pub struct LootPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for LootPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LootTableSet>();
+        app.init_asset_loader::<LootTableLoader>();
+        app.init_resource::<LootRng>();
+        app.init_resource::<PendingLoot>();
+        app.add_systems(Startup, load_loot_tables);
+        app.add_systems(Update, (magnet_system.run_if(in_state(self.state.clone())), flush_pending_loot));
+        app.add_observer(drop_loot_observer);
+        app.add_observer(respawn_unclaimed_observer);
+    }
+}
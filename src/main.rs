@@ -11,14 +11,20 @@ use bevy_kira_audio::prelude::*;
 mod background;
 mod player;
 mod camera;
+mod cutscene;
+mod dialogue;
 mod tiles;
 mod animator;
 mod enemy;
 mod game_layer;
 mod damagable;
 mod input;
+mod input_buffer;
+mod abilities;
+mod combo;
 mod controller;
 mod physics;
+mod gravity;
 mod healthbar;
 mod items;
 mod menu;
@@ -29,6 +35,19 @@ mod getitem;
 mod bag_ui;
 mod ending;
 mod blocks;
+mod level;
+mod signal;
+mod arena;
+mod boss_bar;
+mod navigation;
+mod projectile;
+mod loot;
+mod world_flags;
+mod checkpoint;
+mod controls;
+mod controls_ui;
+mod locale;
+mod replay;
 
 /// 宏观游戏状态
 #[derive(States, Debug, Clone, PartialEq, Eq, Hash)]
@@ -53,6 +72,8 @@ enum PausedState {
     GetItem,
     /// 打开背包状态
     BagUI,
+    /// 按键设置状态
+    Controls,
 }
 
 /// 主函数
@@ -84,6 +105,33 @@ fn main() {
         .add_plugins(player::PlayerPlugin {
             state: AppState::InGame,
         })
+        .add_plugins(gravity::GravityPlugin {
+            state: AppState::InGame,
+        })
+        .add_plugins(abilities::AbilitiesPlugin {
+            state: AppState::InGame,
+        })
+        .add_plugins(level::LevelPlugin)
+        .add_plugins(world_flags::WorldFlagsPlugin)
+        .add_plugins(checkpoint::CheckpointPlugin {
+            state: AppState::InGame,
+        })
+        .add_plugins(navigation::NavigationPlugin)
+        .add_plugins(signal::SignalPlugin {
+            state: AppState::InGame,
+        })
+        .add_plugins(arena::ArenaPlugin {
+            state: AppState::InGame,
+        })
+        .add_plugins(cutscene::CutscenePlugin {
+            state: AppState::InGame,
+        })
+        .add_plugins(dialogue::DialoguePlugin {
+            state: AppState::InGame,
+        })
+        .add_plugins(boss_bar::BossBarPlugin {
+            state: AppState::InGame,
+        })
         .add_plugins(blocks::BlockPlugin {
             state: AppState::InGame,
         })
@@ -93,6 +141,9 @@ fn main() {
         .add_plugins(damagable::DamagePlugin {
             state: AppState::InGame,
         })
+        .add_plugins(projectile::ProjectilePlugin {
+            state: AppState::InGame,
+        })
         .add_plugins(healthbar::HealthBarPlugin {
             state: AppState::InGame,
         })
@@ -102,7 +153,13 @@ fn main() {
         .add_plugins(pause::PausePlugin)
         .add_plugins(getitem::GetItemPlugin)
         .add_plugins(bag_ui::BagUIPlugin)
+        .add_plugins(controls::ControlsPlugin)
+        .add_plugins(controls_ui::ControlsUIPlugin)
+        .add_plugins(locale::LocalePlugin)
         .add_plugins(items::ItemsPlugin)
+        .add_plugins(loot::LootPlugin {
+            state: AppState::InGame,
+        })
         .add_plugins(hint::HintPlugin {
             state: AppState::InGame,
         })
@@ -110,6 +167,7 @@ fn main() {
             state: AppState::Ending,
         })
         .add_plugins(save::SavingPlugin)
+        .add_plugins(replay::ReplayPlugin)
         .run();
 }
 
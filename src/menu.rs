@@ -1,5 +1,7 @@
 use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
 
+use crate::controls::MenuAction;
 use crate::AppState;
 
 /// 主菜单选项
@@ -91,9 +93,9 @@ fn spawn_box(
 /// 处理上下选择
 fn handle_choice(
     mut items: Query<(&mut Text, &mut MenuItem)>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    menu_input: Res<ActionState<MenuAction>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+    if menu_input.just_pressed(&MenuAction::Down) {
         let mut selected  = -1;
         let mut switched = false;
         for (_, item) in &mut items {
@@ -119,7 +121,7 @@ fn handle_choice(
             }
         }
     }        
-    else if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+    else if menu_input.just_pressed(&MenuAction::Up) {
         let mut selected  = -1;
         let mut switched = false;
         for (_, item) in &mut items {
@@ -149,14 +151,14 @@ fn handle_choice(
 
 /// 处理确认输入
 fn handle_enter(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    menu_input: Res<ActionState<MenuAction>>,
     items: Query<&MenuItem>,
     mut commands: Commands,
     ui: Single<Entity, With<UI>>,
     mut next_state: ResMut<NextState<AppState>>,
     mut exit_events: EventWriter<AppExit>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Enter) {
+    if menu_input.just_pressed(&MenuAction::Confirm) {
         for item in &items {
             if item.is_selected {
                 if item.id == 0 {
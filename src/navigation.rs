@@ -0,0 +1,380 @@
+//! 地面敌人寻路
+//! 根据Tiled碰撞层生成导航图：碰撞体顶部未被遮挡的部分是可行走的平台，
+//! 平台上按固定间隔放置路点，相邻路点之间可以行走，平台边缘之间可以跳跃或落下，
+//! 叠在下方平台之上的平台可以从边缘外侧跳上去。
+//! 地面敌人用A*在导航图上寻找通往玩家的路线。
+
+use bevy::prelude::*;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::level::{LevelObject, LevelObjects};
+
+/// 碰撞体所在图层
+const COLLIDER_LAYER: &str = "Colliders";
+/// 平台上方需要留出的空间
+const CLEARANCE: f32 = 24.;
+/// 平台最小宽度
+const MIN_SURFACE_WIDTH: f32 = 8.;
+/// 路点间隔
+const NODE_SPACING: f32 = 32.;
+/// 平台两端路点到边缘的距离
+const EDGE_INSET: f32 = 4.;
+/// 走下平台时离开边缘的水平距离
+const DROP_OFFSET: f32 = 12.;
+/// 最大落下高度
+const MAX_DROP: f32 = 240.;
+/// 最大跳跃高度
+const MAX_JUMP_HEIGHT: f32 = 48.;
+/// 最大跳跃水平距离
+const MAX_JUMP_DISTANCE: f32 = 80.;
+/// 跳跃额外代价，避免能走过去时也跳
+const JUMP_PENALTY: f32 = 40.;
+
+/// 路点之间的连接方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum NavLink {
+    /// 在同一平台上行走
+    Walk,
+    /// 跳到另一平台
+    Jump,
+    /// 从平台边缘落下
+    Drop,
+}
+
+/// 导航图中的边
+#[derive(Debug, Clone)]
+pub struct NavEdge {
+    pub to: usize,
+    pub link: NavLink,
+    pub cost: f32,
+}
+
+/// 路点，位置在平台表面
+#[derive(Debug, Clone)]
+pub struct NavNode {
+    pub position: Vec2,
+    pub surface: usize,
+    pub edges: Vec<NavEdge>,
+}
+
+/// 可行走的平台
+#[derive(Debug, Clone)]
+pub struct Surface {
+    pub left: f32,
+    pub right: f32,
+    pub y: f32,
+    /// 平台上的路点，从左到右
+    pub nodes: Vec<usize>,
+}
+
+/// 路线中的一步
+#[derive(Debug, Clone, Copy, Reflect)]
+pub struct NavStep {
+    /// 路点位置
+    pub position: Vec2,
+    /// 到达该路点的方式
+    pub link: NavLink,
+}
+
+/// 导航图资源
+#[derive(Resource, Default)]
+pub struct NavGraph {
+    pub surfaces: Vec<Surface>,
+    pub nodes: Vec<NavNode>,
+}
+
+/// 寻路代理组件，保存当前路线
+#[derive(Component, Default, Reflect)]
+pub struct NavAgent {
+    /// 剩余路线
+    pub path: Vec<NavStep>,
+    /// 距离下次重新寻路的时间
+    pub repath_timer: f32,
+}
+
+/// A*开放列表中的节点
+#[derive(PartialEq)]
+struct OpenNode {
+    estimate: f32,
+    node: usize,
+}
+
+impl Eq for OpenNode {}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // 估价小的优先
+        other.estimate.partial_cmp(&self.estimate).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl NavGraph {
+    /// 获取位置脚下（或最近）的路点
+    pub fn nearest_node(&self, position: Vec2) -> Option<usize> {
+        let below = self.surfaces.iter().enumerate()
+            .filter(|(_, s)| {
+                s.left - NODE_SPACING <= position.x
+                    && position.x <= s.right + NODE_SPACING
+                    && s.y <= position.y + EDGE_INSET
+                    && !s.nodes.is_empty()
+            })
+            .max_by(|(_, a), (_, b)| a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal));
+        if let Some((_, surface)) = below {
+            return surface.nodes.iter().copied().min_by(|a, b| {
+                let da = (self.nodes[*a].position.x - position.x).abs();
+                let db = (self.nodes[*b].position.x - position.x).abs();
+                da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+            });
+        }
+        (0..self.nodes.len()).min_by(|a, b| {
+            let da = self.nodes[*a].position.distance_squared(position);
+            let db = self.nodes[*b].position.distance_squared(position);
+            da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+        })
+    }
+
+    /// 寻找从`from`到`to`的路线，不包含起点路点
+    pub fn find_path(&self, from: Vec2, to: Vec2) -> Option<Vec<NavStep>> {
+        let start = self.nearest_node(from)?;
+        let goal = self.nearest_node(to)?;
+        let goal_position = self.nodes[goal].position;
+
+        let mut cost = vec![f32::INFINITY; self.nodes.len()];
+        let mut came_from: Vec<Option<(usize, NavLink)>> = vec![None; self.nodes.len()];
+        let mut open = BinaryHeap::new();
+        cost[start] = 0.;
+        open.push(OpenNode {
+            estimate: self.nodes[start].position.distance(goal_position),
+            node: start,
+        });
+
+        while let Some(OpenNode { node, .. }) = open.pop() {
+            if node == goal {
+                let mut steps = vec![];
+                let mut current = goal;
+                while let Some((previous, link)) = came_from[current] {
+                    steps.push(NavStep { position: self.nodes[current].position, link });
+                    current = previous;
+                }
+                steps.reverse();
+                return Some(steps);
+            }
+            for edge in &self.nodes[node].edges {
+                let new_cost = cost[node] + edge.cost;
+                if new_cost < cost[edge.to] {
+                    cost[edge.to] = new_cost;
+                    came_from[edge.to] = Some((node, edge.link));
+                    open.push(OpenNode {
+                        estimate: new_cost + self.nodes[edge.to].position.distance(goal_position),
+                        node: edge.to,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, link: NavLink, cost: f32) {
+        self.nodes[from].edges.push(NavEdge { to, link, cost });
+    }
+
+    /// 平台上离`x`最近的路点
+    fn closest_on_surface(&self, surface: usize, x: f32) -> usize {
+        *self.surfaces[surface].nodes.iter().min_by(|a, b| {
+            let da = (self.nodes[**a].position.x - x).abs();
+            let db = (self.nodes[**b].position.x - x).abs();
+            da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+        }).unwrap()
+    }
+}
+
+/// 碰撞体的包围盒
+fn collider_bounds(object: &LevelObject) -> (Vec2, Vec2) {
+    if object.points.is_empty() {
+        return (object.position - object.size / 2., object.position + object.size / 2.);
+    }
+    object.points.iter().fold(
+        (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+        |(min, max), p| (min.min(*p), max.max(*p)),
+    )
+}
+
+/// 从区间列表中去掉被遮挡的部分
+fn subtract(spans: Vec<(f32, f32)>, cut: (f32, f32)) -> Vec<(f32, f32)> {
+    let mut result = vec![];
+    for (left, right) in spans {
+        if cut.1 <= left || cut.0 >= right {
+            result.push((left, right));
+            continue;
+        }
+        if cut.0 > left {
+            result.push((left, cut.0));
+        }
+        if cut.1 < right {
+            result.push((cut.1, right));
+        }
+    }
+    result
+}
+
+/// 根据碰撞层生成导航图
+fn build_nav_graph(level: Res<LevelObjects>, mut graph: ResMut<NavGraph>) {
+    let bounds: Vec<(Vec2, Vec2)> = level.objects.iter()
+        .filter(|o| o.layer == COLLIDER_LAYER)
+        .map(collider_bounds)
+        .collect();
+    *graph = NavGraph::from_bounds(&bounds);
+}
+
+impl NavGraph {
+    /// 由碰撞体包围盒生成导航图
+    pub fn from_bounds(bounds: &[(Vec2, Vec2)]) -> Self {
+        let mut graph = NavGraph::default();
+
+        // 平台：碰撞体顶部没有被其他碰撞体压住的部分
+        for (i, (min, max)) in bounds.iter().enumerate() {
+            let y = max.y;
+            let mut spans = vec![(min.x, max.x)];
+            for (j, (other_min, other_max)) in bounds.iter().enumerate() {
+                if i == j { continue; }
+                if other_min.y < y + CLEARANCE && other_max.y > y + 0.5 {
+                    spans = subtract(spans, (other_min.x, other_max.x));
+                }
+            }
+            for (left, right) in spans {
+                if right - left >= MIN_SURFACE_WIDTH {
+                    graph.surfaces.push(Surface { left, right, y, nodes: vec![] });
+                }
+            }
+        }
+
+        // 路点与行走连接
+        for s in 0..graph.surfaces.len() {
+            let (left, right, y) = (graph.surfaces[s].left, graph.surfaces[s].right, graph.surfaces[s].y);
+            let inner = (right - left - 2. * EDGE_INSET).max(0.);
+            let count = (inner / NODE_SPACING).ceil() as usize + 1;
+            let mut previous: Option<usize> = None;
+            for k in 0..count {
+                let x = if count == 1 {
+                    (left + right) / 2.
+                } else {
+                    left + EDGE_INSET + inner * k as f32 / (count - 1) as f32
+                };
+                let node = graph.nodes.len();
+                graph.nodes.push(NavNode { position: Vec2::new(x, y), surface: s, edges: vec![] });
+                graph.surfaces[s].nodes.push(node);
+                if let Some(previous) = previous {
+                    let cost = graph.nodes[node].position.distance(graph.nodes[previous].position);
+                    graph.add_edge(previous, node, NavLink::Walk, cost);
+                    graph.add_edge(node, previous, NavLink::Walk, cost);
+                }
+                previous = Some(node);
+            }
+        }
+
+        // 平台边缘的落下与跳跃连接
+        for s in 0..graph.surfaces.len() {
+            let surface = graph.surfaces[s].clone();
+            let ends = [
+                (*surface.nodes.first().unwrap(), surface.left, -1.),
+                (*surface.nodes.last().unwrap(), surface.right, 1.),
+            ];
+            for (end, edge_x, dir) in ends {
+                // 走出边缘后落到正下方最高的平台
+                let drop_x = edge_x + dir * DROP_OFFSET;
+                let landing = graph.surfaces.iter().enumerate()
+                    .filter(|(t, other)| {
+                        *t != s
+                            && other.left <= drop_x
+                            && drop_x <= other.right
+                            && other.y < surface.y
+                            && surface.y - other.y <= MAX_DROP
+                    })
+                    .max_by(|(_, a), (_, b)| a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal))
+                    .map(|(t, _)| t);
+                if let Some(t) = landing {
+                    let target = graph.closest_on_surface(t, drop_x);
+                    let delta = graph.nodes[target].position - graph.nodes[end].position;
+                    graph.add_edge(end, target, NavLink::Drop, delta.x.abs() + delta.y.abs() * 0.5);
+                }
+
+                // 跳到边缘外侧一定距离内的平台
+                for t in 0..graph.surfaces.len() {
+                    if t == s { continue; }
+                    let other = &graph.surfaces[t];
+                    let gap = if dir > 0. { other.left - surface.right } else { surface.left - other.right };
+                    let rise = other.y - surface.y;
+                    if gap <= 0. || gap > MAX_JUMP_DISTANCE || rise > MAX_JUMP_HEIGHT || rise < -MAX_DROP {
+                        continue;
+                    }
+                    let target = if dir > 0. { *other.nodes.first().unwrap() } else { *other.nodes.last().unwrap() };
+                    let cost = gap + rise.abs() * 2. + JUMP_PENALTY;
+                    graph.add_edge(end, target, NavLink::Jump, cost);
+                }
+
+                // 跳上叠在下方平台之上的平台：从边缘外侧起跳，反向落下
+                for t in 0..graph.surfaces.len() {
+                    if t == s { continue; }
+                    let below = &graph.surfaces[t];
+                    let rise = surface.y - below.y;
+                    if rise <= 0. || rise > MAX_JUMP_HEIGHT || drop_x < below.left || drop_x > below.right {
+                        continue;
+                    }
+                    let takeoff = graph.closest_on_surface(t, drop_x);
+                    let delta = graph.nodes[end].position - graph.nodes[takeoff].position;
+                    graph.add_edge(takeoff, end, NavLink::Jump, delta.x.abs() + rise * 2. + JUMP_PENALTY);
+                    if !graph.nodes[end].edges.iter().any(|edge| edge.to == takeoff) {
+                        graph.add_edge(end, takeoff, NavLink::Drop, delta.x.abs() + rise * 0.5);
+                    }
+                }
+            }
+        }
+        graph
+    }
+}
+
+pub struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavGraph>();
+        // 地图对象在Startup中读取
+        app.add_systems(PostStartup, build_nav_graph);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 地面上叠放一块跳跃高度以内的平台
+    fn stacked_graph() -> NavGraph {
+        NavGraph::from_bounds(&[
+            (Vec2::new(0., -16.), Vec2::new(400., 0.)),
+            (Vec2::new(150., 24.), Vec2::new(250., 40.)),
+        ])
+    }
+
+    #[test]
+    fn jumps_onto_stacked_platform() {
+        let graph = stacked_graph();
+        let path = graph.find_path(Vec2::new(20., 0.), Vec2::new(200., 40.)).unwrap();
+        assert!(path.iter().any(|step| step.link == NavLink::Jump));
+        assert_eq!(path.last().unwrap().position.y, 40.);
+    }
+
+    #[test]
+    fn drops_from_stacked_platform() {
+        let graph = stacked_graph();
+        let path = graph.find_path(Vec2::new(200., 40.), Vec2::new(380., 0.)).unwrap();
+        assert!(path.iter().any(|step| step.link == NavLink::Drop));
+        assert_eq!(path.last().unwrap().position.y, 0.);
+    }
+}
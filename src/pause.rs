@@ -1,4 +1,7 @@
 use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::controls::MenuAction;
 use crate::PausedState;
 
 /// 菜单选项
@@ -75,7 +78,7 @@ fn spawn_box(
         ..default()
     };
 
-    let exit_node = Node {
+    let controls_node = Node {
         position_type: PositionType::Absolute,
         width: Val::Percent(100.),
         height: Val::Percent(10.),
@@ -84,8 +87,19 @@ fn spawn_box(
         //padding: UiRect::left(Val::Px(5.)).with_bottom(Val::Px(5.)),
         ..default()
     };
+
+    let exit_node = Node {
+        position_type: PositionType::Absolute,
+        width: Val::Percent(100.),
+        height: Val::Percent(10.),
+        top: Val::Percent(65.),
+        justify_content: JustifyContent::Center,
+        //padding: UiRect::left(Val::Px(5.)).with_bottom(Val::Px(5.)),
+        ..default()
+    };
     let start_text = Text::new("[ Continue ]");
     let bag_text = Text::new("Open Bag");
+    let controls_text = Text::new("Controls");
     let exit_text = Text::new("Game Exit");
     let font = TextFont {
         font: asset_server.load("UI/Fonts/m5x7.ttf"),
@@ -125,11 +139,19 @@ fn spawn_box(
         ));
     }).id();
 
+    let controls_node_entity = commands.spawn((
+        controls_node,
+    )).with_children(|parent| {
+        parent.spawn((
+            controls_text, font.clone(), Label, MenuItem { id: 2, is_selected : false }
+        ));
+    }).id();
+
     let exit_node_entity = commands.spawn((
         exit_node,
     )).with_children(|parent| {
         parent.spawn((
-            exit_text, font.clone(), Label, MenuItem { id: 2, is_selected : false }
+            exit_text, font.clone(), Label, MenuItem { id: 3, is_selected : false }
         ));
     }).id();
 
@@ -138,16 +160,16 @@ fn spawn_box(
         .add_children(&[title_entity, choice_entity]);
     commands
         .entity(choice_entity)
-        .add_children(&[start_node_entity, bag_node_entity, exit_node_entity]);
+        .add_children(&[start_node_entity, bag_node_entity, controls_node_entity, exit_node_entity]);
     //commands.entity(text_node_entity).add_children(&[text_entity]);
 }
 
 /// 处理上下选择
 fn handle_choice(
     mut items: Query<(&mut Text, &mut MenuItem)>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    menu_input: Res<ActionState<MenuAction>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+    if menu_input.just_pressed(&MenuAction::Down) {
         let mut selected  = -1;
         let mut switched = false;
         for (_, item) in &mut items {
@@ -173,7 +195,7 @@ fn handle_choice(
             }
         }
     }        
-    else if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+    else if menu_input.just_pressed(&MenuAction::Up) {
         let mut selected  = -1;
         let mut switched = false;
         for (_, item) in &mut items {
@@ -203,14 +225,14 @@ fn handle_choice(
 
 /// 处理确认输入
 fn handle_enter(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    menu_input: Res<ActionState<MenuAction>>,
     items: Query<&MenuItem>,
     mut commands: Commands,
     ui: Query<Entity, With<UI>>,
     mut next_state: ResMut<NextState<PausedState>>,
     mut exit_events: EventWriter<AppExit>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Enter) {
+    if menu_input.just_pressed(&MenuAction::Confirm) {
         for item in &items {
             if item.is_selected {
                 if item.id == 0 {
@@ -224,12 +246,17 @@ fn handle_enter(
                     }
                     next_state.set(PausedState::BagUI);
                 } else if item.id == 2 {
+                    if let Ok(entity) = ui.single() {
+                        commands.entity(entity).despawn();
+                    }
+                    next_state.set(PausedState::Controls);
+                } else if item.id == 3 {
                     exit_events.write(AppExit::Success);
                 }
                 break;
             }
         }
-    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+    } else if menu_input.just_pressed(&MenuAction::Back) || menu_input.just_pressed(&MenuAction::Pause) {
         if let Ok(entity) = ui.single() {
             commands.entity(entity).despawn();
         }
@@ -239,10 +266,11 @@ fn handle_enter(
 
 /// 处理进入菜单画面
 fn handle_pause(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    menu_input: Res<ActionState<MenuAction>>,
     mut next_state: ResMut<NextState<PausedState>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    // 手柄的返回键同时是跑步键，只用暂停键打开菜单
+    if menu_input.just_pressed(&MenuAction::Pause) {
         next_state.set(PausedState::Paused);
     }
 }
@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use avian2d::prelude::*;
 
 use crate::game_layer::GameLayer;
+use crate::gravity::GravityDirection;
 
 /// 物理组件包
 #[derive(Bundle)]
@@ -11,6 +12,7 @@ pub struct PhysicsBundle {
     pub ccd: SweptCcd,
     pub mass: Mass,
     pub gravity: GravityScale,
+    pub gravity_direction: GravityDirection,
     pub collider: Collider,
     pub collision_margin: CollisionMargin,
     pub layer: CollisionLayers,
@@ -25,6 +27,7 @@ impl Default for PhysicsBundle {
             ccd: SweptCcd::default(),
             mass: Mass(1.0),
             gravity: GravityScale(30.0),
+            gravity_direction: GravityDirection::Down,
             collider: Collider::rectangle(0., 0.),
             collision_margin: CollisionMargin(0.1),
             layer: CollisionLayers::new(GameLayer::Default, [GameLayer::Default]),
@@ -10,13 +10,19 @@ use game_derive::enter;
 use game_derive::exit;
 use std::collections::HashMap;
 
+use crate::abilities::{ability_states, add_ability_params, air_transitions, Abilities};
 use crate::animator::Condition;
 use crate::animator::*;
+use crate::combo::{on_combo_enter, on_combo_exit, AIR_COMBO, COMBOS, CROUCH_COMBO, GROUND_COMBO};
 use crate::damagable::*;
+use crate::enemy::perception::Noise;
 use crate::game_layer::GameLayer;
 use crate::input;
 use crate::input::*;
+use crate::input_buffer::InputBuffer;
 use crate::controller::*;
+use crate::controls::ControlSettings;
+use crate::items::AbilityType;
 use crate::items::ActiveItems;
 use crate::items::HasItem;
 use crate::items::ItemBag;
@@ -24,7 +30,7 @@ use crate::items::ItemList;
 use crate::items::ItemOf;
 use crate::items::UseItemTrigger;
 use crate::physics::*;
-use crate::save::TransformData;
+use crate::save::{load, TransformData};
 
 /// 玩家标识组件
 #[derive(Component, Reflect)]
@@ -37,6 +43,7 @@ fn setup_player(
     asset_server: Res<AssetServer>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     transform_data: ResMut<TransformData>,
+    controls: Res<ControlSettings>,
 ) {
     // 初始化素材
     let texture = asset_server.load("Art/Adventurer/adventurer-sheet.png");
@@ -73,7 +80,7 @@ fn setup_player(
             ..default()
         },
         Transform::from_translation(translation).with_scale(scale),
-        PlayerInputBundle::default(),
+        PlayerInputBundle::new(controls.input_map()),
         animator,
         ControllerBundle::new(11.8),
         PhysicsBundle {
@@ -84,13 +91,15 @@ fn setup_player(
         },
         ItemBag { slots: HashMap::new() },
         ActiveItems { items: vec![], current: 0 },
+        InputBuffer::default(),
+        Abilities::from_save(load()),
         transform_data.damagable.clone(),
     ));
 }
 
 /// 动画状态类型 
 #[derive(Reflect)]
-enum AnimationType {
+pub(crate) enum AnimationType {
     AirAttack1,
     AirAttack2,
     AirAttack3Loop,
@@ -143,7 +152,7 @@ enum AnimationType {
 
 impl AnimationType {
     /// 每种动画状态对应的动画帧起始、末尾
-    fn config_index(&self) -> (usize, usize) {
+    pub(crate) const fn config_index(&self) -> (usize, usize) {
         match self {
             Self::AirAttack3End => (0, 2),
             Self::AirAttack1 => (3, 6),
@@ -245,16 +254,7 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
                 has_exit_time: true,
                 exit_time: 0.5,
             },
-            Transition {
-                conditions: vec![Condition {
-                    param_name: "attack".to_string(),
-                    operator: ConditionOperator::Equals,
-                    value: AnimatorParam::Trigger(true),
-                }],
-                target_state: "Attack1Prep".to_string(),
-                has_exit_time: false,
-                exit_time: 0.0,
-            },
+            GROUND_COMBO.entry_transition(),
             Transition {
                 conditions: vec![Condition {
                     param_name: "defense".to_string(),
@@ -365,16 +365,7 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
                 has_exit_time: false,
                 exit_time: 0.0,
             },
-            Transition {
-                conditions: vec![Condition {
-                    param_name: "attack".to_string(),
-                    operator: ConditionOperator::Equals,
-                    value: AnimatorParam::Trigger(true),
-                }],
-                target_state: "Attack1Prep".to_string(),
-                has_exit_time: false,
-                exit_time: 0.0,
-            },
+            GROUND_COMBO.entry_transition(),
             Transition {
                 conditions: vec![Condition {
                     param_name: "slide".to_string(),
@@ -486,16 +477,7 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
                 has_exit_time: false,
                 exit_time: 0.0,
             },
-            Transition {
-                conditions: vec![Condition {
-                    param_name: "attack".to_string(),
-                    operator: ConditionOperator::Equals,
-                    value: AnimatorParam::Trigger(true),
-                }],
-                target_state: "Attack1Prep".to_string(),
-                has_exit_time: false,
-                exit_time: 0.0,
-            },
+            GROUND_COMBO.entry_transition(),
             Transition {
                 conditions: vec![Condition {
                     param_name: "slide".to_string(),
@@ -557,6 +539,7 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
         first_index: AnimationType::Crouch.config_index().0,
         last_index: AnimationType::Crouch.config_index().1,
         transitions: vec![
+            CROUCH_COMBO.entry_transition(),
             Transition {
                 conditions: vec![Condition {
                     param_name: "is_moving".to_string(),
@@ -647,6 +630,7 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
         first_index: AnimationType::CrouchWalk.config_index().0,
         last_index: AnimationType::CrouchWalk.config_index().1,
         transitions: vec![
+            CROUCH_COMBO.entry_transition(),
             Transition {
                 conditions: vec![Condition {
                     param_name: "is_moving".to_string(),
@@ -733,11 +717,12 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
         ..default()
     };
 
-    let rise_state = AnimationState {
+    let mut rise_state = AnimationState {
         name: "Rise".to_string(),
         first_index: AnimationType::Rise.config_index().0,
         last_index: AnimationType::Rise.config_index().1,
         transitions: vec![
+            AIR_COMBO.entry_transition(),
             Transition {
                 conditions: vec![Condition {
                     param_name: "velocity_y".to_string(),
@@ -803,11 +788,12 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
         ..default()
     };
 
-    let fall_state = AnimationState {
+    let mut fall_state = AnimationState {
         name: "Fall".to_string(),
         first_index: AnimationType::Fall.config_index().0,
         last_index: AnimationType::Fall.config_index().1,
         transitions: vec![
+            AIR_COMBO.entry_transition(),
             Transition {
                 conditions: vec![Condition {
                     param_name: "velocity_y".to_string(),
@@ -879,6 +865,7 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
         first_index: AnimationType::Jump.config_index().0,
         last_index: AnimationType::Jump.config_index().1,
         transitions: vec![
+            AIR_COMBO.entry_transition(),
             Transition {
                 conditions: vec![],
                 target_state: "Rise".to_string(),
@@ -917,126 +904,6 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
         ..default()
     };
 
-    let attack1_prep_state = AnimationState {
-        name: "Attack1Prep".to_string(),
-        first_index: AnimationType::Attack1Prep.config_index().0,
-        last_index: AnimationType::Attack1Prep.config_index().1,
-        transitions: vec![
-            Transition {
-                conditions: vec![],
-                target_state: "Attack1".to_string(),
-                has_exit_time: true,
-                exit_time: 1.0,
-            },
-        ],
-        loop_animation: false,
-        on_enter: Some(__stun_enter_handler),
-        on_exit: None,
-        ..default()
-    };
-
-    let attack1_state = AnimationState {
-        name: "Attack1".to_string(),
-        first_index: AnimationType::Attack1.config_index().0,
-        last_index: AnimationType::Attack1.config_index().1,
-        transitions: vec![
-            Transition {
-                conditions: vec![],
-                target_state: "Attack1End".to_string(),
-                has_exit_time: true,
-                exit_time: 1.0,
-            },
-        ],
-        loop_animation: false,
-        on_enter: Some(__attack_enter_handler),
-        on_exit: Some(__attack_exit_handler),
-        ..default()
-    };
-
-    let attack1_end_state = AnimationState {
-        name: "Attack1End".to_string(),
-        first_index: AnimationType::Attack1End.config_index().0,
-        last_index: AnimationType::Attack1End.config_index().1,
-        transitions: vec![
-            Transition {
-                conditions: vec![Condition {
-                    param_name: "attack".to_string(),
-                    operator: ConditionOperator::Equals,
-                    value: AnimatorParam::Trigger(true),
-                }],
-                target_state: "Attack2Prep".to_string(),
-                has_exit_time: true,
-                exit_time: 1.0,
-            },
-            Transition {
-                conditions: vec![],
-                target_state: "Idle".to_string(),
-                has_exit_time: true,
-                exit_time: 1.1,
-            },
-        ],
-        loop_animation: false,
-        on_enter: None,
-        on_exit: Some(__stun_exit_handler),
-        audio_path: Some("Audio/SFX/10_Battle_SFX/39_Block_03.wav".to_string()),
-        ..default()
-    };
-
-    let attack2_prep_state = AnimationState {
-        name: "Attack2Prep".to_string(),
-        first_index: AnimationType::Attack2Prep.config_index().0,
-        last_index: AnimationType::Attack2Prep.config_index().1,
-        transitions: vec![
-            Transition {
-                conditions: vec![],
-                target_state: "Attack2".to_string(),
-                has_exit_time: true,
-                exit_time: 1.0,
-            },
-        ],
-        loop_animation: false,
-        on_enter: Some(__stun_enter_handler),
-        on_exit: None,
-        ..default()
-    };
-
-    let attack2_state = AnimationState {
-        name: "Attack2".to_string(),
-        first_index: AnimationType::Attack2.config_index().0,
-        last_index: AnimationType::Attack2.config_index().1,
-        transitions: vec![
-            Transition {
-                conditions: vec![],
-                target_state: "Attack2End".to_string(),
-                has_exit_time: true,
-                exit_time: 1.0,
-            },
-        ],
-        loop_animation: false,
-        on_enter: Some(__attack_enter_handler),
-        on_exit: Some(__attack_exit_handler),
-        audio_path: Some("Audio/SFX/10_Battle_SFX/39_Block_03.wav".to_string()),
-        ..default()
-    };
-
-    let attack2_end_state = AnimationState {
-        name: "Attack2End".to_string(),
-        first_index: AnimationType::Attack2End.config_index().0,
-        last_index: AnimationType::Attack2End.config_index().1,
-        transitions: vec![
-            Transition {
-                conditions: vec![],
-                target_state: "Idle".to_string(),
-                has_exit_time: true,
-                exit_time: 1.0,
-            },
-        ],
-        loop_animation: false,
-        on_enter: None,
-        on_exit: Some(__stun_exit_handler),
-        ..default()
-    };
-
     let hit_state = AnimationState {
         name: "Hit".to_string(),
         first_index: AnimationType::Hit.config_index().0,
@@ -1181,18 +1048,12 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
                 exit_time: 0.0,
             },
             Transition {
-                conditions: vec![
-                    Condition {
-                        param_name: "can_wall_jump".to_string(),
-                        operator: ConditionOperator::Equals,
-                        value: AnimatorParam::Bool(true),
-                    },
-                    Condition {
-                        param_name: "jump".to_string(),
-                        operator: ConditionOperator::Equals,
-                        value: AnimatorParam::Trigger(true),
-                    }
-                ],
+                // 是否拥有蹬墙跳能力由`input_buffer`判断
+                conditions: vec![Condition {
+                    param_name: "jump".to_string(),
+                    operator: ConditionOperator::Equals,
+                    value: AnimatorParam::Trigger(true),
+                }],
                 target_state: "Jump".to_string(),
                 has_exit_time: false,
                 exit_time: 0.0,
@@ -1244,9 +1105,12 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
         animator.add_parameter("impulse_x", AnimatorParam::Float(0.0));
         animator.add_parameter("revival", AnimatorParam::Trigger(false));
         animator.add_parameter("is_on_wall", AnimatorParam::Bool(false));
-        animator.add_parameter("can_wall_jump", AnimatorParam::Bool(false));
-        animator.add_parameter("can_reverse_gravity", AnimatorParam::Bool(false));
     }
+    // 旧存档中没有的参数
+    add_ability_params(&mut animator);
+    // 空中使用能力，见`abilities`
+    rise_state.transitions.splice(0..0, air_transitions());
+    fall_state.transitions.splice(0..0, air_transitions());
 
     animator.add_state(idle_state);
     animator.add_state(walk_state);
@@ -1257,12 +1121,11 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
     animator.add_state(fall_state);
     animator.add_state(jump_state);
     animator.add_state(slide_state);
-    animator.add_state(attack1_state);
-    animator.add_state(attack2_state);
-    animator.add_state(attack1_prep_state);
-    animator.add_state(attack2_prep_state);
-    animator.add_state(attack1_end_state);
-    animator.add_state(attack2_end_state);
+    for chain in COMBOS {
+        for state in chain.states() {
+            animator.add_state(state);
+        }
+    }
     animator.add_state(die_state);
     animator.add_state(hit_state);
     animator.add_state(lie_state);
@@ -1270,6 +1133,9 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
     animator.add_state(defense_state);
     animator.add_state(items_state);
     animator.add_state(wall_slide_state);
+    for state in ability_states() {
+        animator.add_state(state);
+    }
     
     // 初始状态为倒下
     animator.set_initial_state(
@@ -1282,40 +1148,6 @@ fn setup_animator(params: HashMap<String, AnimatorParam>) -> Animator {
     animator
 }
 
-/// 进入攻击状态触发
-#[enter("attack")]
-fn on_attack_enter(
-    mut commands: Commands
-) {
-    let entity = trigger.entity;
-    // 生成hitbox
-    commands.spawn((
-            Collider::rectangle(30., 10.),
-            Transform::from_xyz(10., 0., 0.),
-            Sensor,
-            HitBox { damage: 200. },
-            CollisionLayers::new(GameLayer::PlayerHitBox, [GameLayer::Enemy]),
-            CollisionEventsEnabled,
-            ChildOf(entity),
-            HitboxOf(entity),
-        )).observe(check_hitbox);
-}
-
-/// 退出攻击状态
-#[exit("attack")]
-fn on_attack_exit(
-    mut commands: Commands,
-    player: Query<&HasHitbox, With<Player>>,
-) {
-    let entity = trigger.entity;
-    let hitboxes = player.get(entity).unwrap();
-    let vec = (**hitboxes).clone();
-    // 删除hitbox
-    for hitbox in vec {
-        commands.entity(hitbox).despawn();
-    }
-}
-
 /// 进入硬直状态
 #[enter("stun")]
 fn on_stun_enter(
@@ -1386,26 +1218,37 @@ const JUMP_IMPULSE: f32 = 600.0;
 /// 进入跳跃状态
 #[enter("jump")]
 fn on_jump_enter(
-    mut player: Query<(&Animator, &mut TnuaController), With<Player>>,
+    mut player: Query<(&Animator, &Abilities, &mut TnuaController, &mut InputBuffer), With<Player>>,
     asset_server: Res<AssetServer>, 
     audio: Res<Audio>
 ) {
     let entity = trigger.entity;
     audio.play(asset_server.load(
         "Audio/SFX/12_Player_Movement_SFX/30_Jump_03.wav"));
-    let (animator, mut controller) = player.get_mut(entity).unwrap();
+    let (animator, abilities, mut controller, mut buffer) = player.get_mut(entity).unwrap();
+    let wall_jump = animator.get_bool("is_on_wall") && abilities.has(AbilityType::WallJump);
     controller.action(TnuaBuiltinJump {
         height: JUMP_IMPULSE,
-        allow_in_air: animator.get_bool("is_on_wall") && animator.get_bool("can_wall_jump"),
+        allow_in_air: wall_jump || buffer.air_jump,
         ..Default::default()
     });
+    buffer.start_jump();
 }
 
 /// 退出跳跃状态
 #[exit("fall")]
-fn on_fall_exit(asset_server: Res<AssetServer>, audio: Res<Audio>) {
+fn on_fall_exit(
+    mut commands: Commands,
+    player: Query<&Transform, With<Player>>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+) {
     audio.play(asset_server.load(
         "Audio/SFX/12_Player_Movement_SFX/45_Landing_01.wav"));
+    // 落地声会惊动附近的敌人
+    if let Ok(transform) = player.get(trigger.entity) {
+        commands.trigger(Noise::landing(transform.translation.truncate()));
+    }
 }
 
 /// 进入滑行状态
@@ -1441,11 +1284,11 @@ fn on_slide_exit() {
 
 /// 碰撞检查，检查是否触地、触墙
 fn check_contact(
-    player: Single<(&mut Animator, &Transform, &Collider), With<Player>>,
+    player: Single<(&mut Animator, &Abilities, &Transform, &Collider), With<Player>>,
     controller: Single<&TnuaController, With<Player>>,
     spatial_query: SpatialQuery,
 ) {
-    let (mut animator, transform, collider) = player.into_inner();
+    let (mut animator, abilities, transform, collider) = player.into_inner();
     let is_grounded = !controller.is_airborne().unwrap();
     animator.set_bool("is_grounded", is_grounded);
     let origin = Vec2::new(transform.translation.x, transform.translation.y);
@@ -1468,7 +1311,8 @@ fn check_contact(
         &config_x,
         &filter,
     );
-    animator.set_bool("is_on_wall", hits_wall.length() > 0 && !is_grounded);
+    // 没有滑墙或蹬墙跳能力时不会贴在墙上
+    animator.set_bool("is_on_wall", hits_wall.length() > 0 && !is_grounded && abilities.can_cling());
 }
 
 pub struct PlayerPlugin<S: States> {
@@ -1480,8 +1324,8 @@ impl<S: States> Plugin for PlayerPlugin<S> {
         app.add_plugins(input::PlayerInputPlugin { state: self.state.clone() });
         app.add_systems(OnEnter(self.state.clone()), setup_player.run_if(in_state(self.state.clone())));
         app.add_systems(FixedUpdate, check_contact.run_if(in_state(self.state.clone())));
-        app.add_observer(on_attack_enter);
-        app.add_observer(on_attack_exit);
+        app.add_observer(on_combo_enter);
+        app.add_observer(on_combo_exit);
         app.add_observer(on_stun_enter);
         app.add_observer(on_stun_exit);
         app.add_observer(on_item_enter);
@@ -0,0 +1,218 @@
+//! 投射物
+//! 投射物带有`HitBox`，命中时与近战判定一样通过`check_hitbox`造成伤害；
+//! 支持重力、存在时间、穿透与追踪，撞到地面消失，玩家在弹反窗口内命中时会被反弹回去。
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::damagable::{check_hitbox, Damagable, HitBox};
+use crate::game_layer::GameLayer;
+use crate::player::Player;
+
+/// 投射物配置
+#[derive(Debug, Clone, Copy)]
+pub struct ProjectileConfig {
+    /// 贴图路径
+    pub image: &'static str,
+    /// 贴图颜色
+    pub color: Color,
+    /// 碰撞体尺寸
+    pub size: Vec2,
+    /// 初速度
+    pub speed: f32,
+    /// 重力加速度
+    pub gravity: f32,
+    /// 存在时间
+    pub lifetime: f32,
+    /// 伤害
+    pub damage: f32,
+    /// 可以穿透的目标数量
+    pub pierce: u32,
+    /// 追踪时每秒最大转向角度（弧度），为0时不追踪
+    pub homing: f32,
+    /// 能否被弹反
+    pub deflectable: bool,
+}
+
+/// 投射物所属阵营
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectileTeam {
+    Player,
+    Enemy,
+}
+
+impl ProjectileTeam {
+    /// 阵营对应的碰撞图层
+    fn layers(&self) -> CollisionLayers {
+        match self {
+            Self::Player => CollisionLayers::new(GameLayer::PlayerHitBox, [GameLayer::Enemy, GameLayer::Interactive]),
+            Self::Enemy => CollisionLayers::new(GameLayer::EnemyHitBox, [GameLayer::Player]),
+        }
+    }
+
+    fn opposite(&self) -> Self {
+        match self {
+            Self::Player => Self::Enemy,
+            Self::Enemy => Self::Player,
+        }
+    }
+}
+
+/// 投射物组件
+#[derive(Component, Debug)]
+pub struct Projectile {
+    /// 发射者
+    pub owner: Entity,
+    /// 阵营
+    pub team: ProjectileTeam,
+    pub gravity: f32,
+    /// 剩余存在时间
+    pub lifetime: f32,
+    /// 剩余穿透数量
+    pub pierce: u32,
+    pub homing: f32,
+    /// 追踪目标，为空时敌人的投射物追踪玩家
+    pub target: Option<Entity>,
+    pub deflectable: bool,
+    /// 已经命中过的实体
+    pub hit: Vec<Entity>,
+}
+
+/// 发射投射物
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    config: &ProjectileConfig,
+    owner: Entity,
+    team: ProjectileTeam,
+    position: Vec2,
+    direction: Vec2,
+) -> Entity {
+    let velocity = direction.normalize_or_zero() * config.speed;
+    commands.spawn((
+        Sprite {
+            image: asset_server.load(config.image),
+            color: config.color,
+            custom_size: Some(config.size),
+            ..default()
+        },
+        Transform::from_xyz(position.x, position.y, 1.)
+            .with_rotation(Quat::from_rotation_z(velocity.to_angle())),
+        RigidBody::Kinematic,
+        LinearVelocity(velocity),
+        Collider::circle(config.size.x.min(config.size.y) / 2.),
+        Sensor,
+        team.layers(),
+        CollisionEventsEnabled,
+        HitBox { damage: config.damage },
+        Projectile {
+            owner,
+            team,
+            gravity: config.gravity,
+            lifetime: config.lifetime,
+            pierce: config.pierce,
+            homing: config.homing,
+            target: None,
+            deflectable: config.deflectable,
+            hit: vec![],
+        },
+    )).observe(check_hitbox).observe(projectile_hit).id()
+}
+
+/// 投射物命中：弹反、穿透或消失
+fn projectile_hit(
+    trigger: Trigger<OnCollisionStart>,
+    mut commands: Commands,
+    mut projectiles: Query<(&mut Projectile, &mut LinearVelocity, &mut CollisionLayers, &Transform)>,
+    targets: Query<(&Damagable, &GlobalTransform, Has<Player>)>,
+) {
+    let entity = trigger.target();
+    let other = trigger.collider;
+    let Ok((mut projectile, mut velocity, mut layers, transform)) = projectiles.get_mut(entity) else { return; };
+    let Ok((damagable, _, is_player)) = targets.get(other) else { return; };
+    if projectile.hit.contains(&other) {
+        return;
+    }
+
+    // 玩家弹反：反弹回发射者
+    if is_player && projectile.deflectable && damagable.is_parrying() {
+        let speed = velocity.length();
+        let back = targets
+            .get(projectile.owner)
+            .map(|(_, owner, _)| owner.translation().truncate() - transform.translation.truncate())
+            .unwrap_or(-velocity.0);
+        velocity.0 = back.normalize_or_zero() * speed;
+        projectile.team = projectile.team.opposite();
+        *layers = projectile.team.layers();
+        projectile.target = Some(projectile.owner);
+        projectile.owner = other;
+        projectile.hit.clear();
+        return;
+    }
+
+    projectile.hit.push(other);
+    if projectile.pierce == 0 {
+        commands.entity(entity).despawn();
+    } else {
+        projectile.pierce -= 1;
+    }
+}
+
+/// 更新投射物：重力、追踪、存在时间与地面碰撞
+fn projectile_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    mut projectiles: Query<(Entity, &mut Projectile, &mut LinearVelocity, &mut Transform, &Collider)>,
+    targets: Query<&GlobalTransform>,
+    player: Single<Entity, With<Player>>,
+) {
+    let player = player.into_inner();
+    let delta = time.delta_secs();
+    let filter = SpatialQueryFilter::default().with_mask(GameLayer::Ground);
+    for (entity, mut projectile, mut velocity, mut transform, collider) in &mut projectiles {
+        projectile.lifetime -= delta;
+        if projectile.lifetime <= 0. {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        if projectile.homing > 0. {
+            let target = match (projectile.target, projectile.team) {
+                (Some(target), _) => Some(target),
+                (None, ProjectileTeam::Enemy) => Some(player),
+                (None, ProjectileTeam::Player) => None,
+            };
+            if let Some(target) = target.and_then(|t| targets.get(t).ok()) {
+                let desired = target.translation().truncate() - position;
+                let angle = velocity.angle_to(desired);
+                let max_turn = projectile.homing * delta;
+                velocity.0 = Vec2::from_angle(angle.clamp(-max_turn, max_turn)).rotate(velocity.0);
+            }
+        } else {
+            velocity.y -= projectile.gravity * delta;
+        }
+        transform.rotation = Quat::from_rotation_z(velocity.to_angle());
+
+        // 沿运动方向检测地面
+        let Ok(direction) = Dir2::new(velocity.0) else { continue; };
+        let distance = velocity.length() * delta;
+        if spatial_query
+            .cast_shape(collider, position, 0., direction, &ShapeCastConfig::from_max_distance(distance), &filter)
+            .is_some()
+        {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub struct ProjectilePlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for ProjectilePlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, projectile_system.run_if(in_state(self.state.clone())));
+    }
+}
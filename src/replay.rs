@@ -0,0 +1,297 @@
+//! 输入录制与回放
+//! 以`--record <文件>`启动时，逐帧记录玩家与菜单的动作状态，连同随机数种子与初始存档在退出时写入录像文件；
+//! 以`--replay <文件>`启动时，恢复录像中的种子与存档，把录下的动作逐帧写回`ActionState<Action>`与`ActionState<MenuAction>`，
+//! 回放结束后退出游戏。两种模式都跳过主菜单直接进入游戏，并使用与固定更新相同的时间步长，每帧正好执行一次固定更新，
+//! 保证同一份录像每次回放的结果相同。回放时清空玩家与菜单的键位表，真实输入不会混进录像的输入。
+//! 录像的第一帧要等掉落表、过场动画与敌人的AI配置都加载完成，在此之前暂停游戏时间并忽略输入。
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::{prelude::*, scene::ron};
+use bevy::time::TimeUpdateStrategy;
+use leafwing_input_manager::plugin::InputManagerSystem;
+use leafwing_input_manager::prelude::{ActionState, InputMap};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use big_brain::thinker::HasThinker;
+
+use crate::controls::MenuAction;
+use crate::cutscene::Cutscenes;
+use crate::enemy::profile::AiProfileHandle;
+use crate::input::Action;
+use crate::loot::{LootRng, LootTables};
+use crate::player::Player;
+use crate::save::{load, set_save_path, write, TransformData};
+use crate::AppState;
+
+/// 录制与回放的时间步长，与默认的固定更新频率（64Hz）一致
+const TIMESTEP: f64 = 1. / 64.;
+/// 回放时使用的存档文件，不覆盖玩家自己的存档
+const REPLAY_SAVE_PATH: &str = "replay_save.ron";
+
+/// 游戏逻辑使用的随机数，录制与回放时由录像中的种子初始化
+#[derive(Resource)]
+pub struct GameRng {
+    /// 种子
+    pub seed: u64,
+    pub rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+/// 一帧的输入
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FrameInput {
+    /// 按下的动作
+    pub pressed: Vec<Action>,
+    /// 摇杆移动
+    #[serde(default)]
+    pub axis: Vec2,
+    /// 按下的菜单动作（获得道具、对话选项、背包、暂停等界面）
+    #[serde(default)]
+    pub menu: Vec<MenuAction>,
+}
+
+/// 录像
+#[derive(Serialize, Deserialize)]
+pub struct Recording {
+    /// 时间步长
+    pub timestep: f64,
+    /// 掉落随机数种子
+    pub loot_seed: u64,
+    /// 游戏逻辑随机数种子
+    pub game_seed: u64,
+    /// 开始录制时的存档，没有存档时为None
+    pub save: Option<TransformData>,
+    /// 从玩家出现开始的每一帧输入
+    pub frames: Vec<FrameInput>,
+}
+
+impl Recording {
+    /// 读取录像文件
+    fn read(path: &str) -> Option<Self> {
+        let mut contents = String::new();
+        let Ok(mut file) = File::open(path) else {
+            println!("Could not open {}", path);
+            return None;
+        };
+        if file.read_to_string(&mut contents).is_err() {
+            println!("Could not read {}", path);
+            return None;
+        }
+        match ron::from_str(&contents) {
+            Ok(recording) => Some(recording),
+            Err(e) => {
+                println!("Failed to parse {}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// 写入录像文件
+    fn write(&self, path: &PathBuf) {
+        let ron_string = ron::ser::to_string(self).unwrap();
+        let mut file = File::create(path).unwrap();
+        file.write_all(ron_string.as_bytes()).unwrap();
+    }
+}
+
+/// 录制或回放模式
+#[derive(Resource)]
+pub enum ReplayMode {
+    Off,
+    Record { path: PathBuf, recording: Recording },
+    Replay { recording: Recording, frame: usize },
+}
+
+impl ReplayMode {
+    /// 从命令行参数读取模式
+    fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let value = |flag: &str| args.iter().position(|arg| arg == flag).and_then(|i| args.get(i + 1));
+        if let Some(path) = value("--replay") {
+            return match Recording::read(path) {
+                Some(recording) => ReplayMode::Replay { recording, frame: 0 },
+                None => ReplayMode::Off,
+            };
+        }
+        if let Some(path) = value("--record") {
+            let recording = Recording {
+                timestep: TIMESTEP,
+                loot_seed: rand::random(),
+                game_seed: rand::random(),
+                save: load(),
+                frames: vec![],
+            };
+            return ReplayMode::Record { path: path.into(), recording };
+        }
+        ReplayMode::Off
+    }
+}
+
+/// 录像开始前是否还在等待资源加载
+#[derive(Resource)]
+struct LoadingHold(bool);
+
+/// 把一帧的输入写入玩家与菜单的动作状态
+fn apply_input(action_state: &mut ActionState<Action>, menu_state: &mut ActionState<MenuAction>, input: &FrameInput) {
+    for action in Action::ALL {
+        if input.pressed.contains(&action) {
+            action_state.press(&action);
+        } else {
+            action_state.release(&action);
+        }
+    }
+    action_state.set_axis_pair(&Action::Move, input.axis);
+    for action in MenuAction::ALL {
+        if input.menu.contains(&action) {
+            menu_state.press(&action);
+        } else {
+            menu_state.release(&action);
+        }
+    }
+}
+
+/// 等待玩家出现且影响游戏结果的资源加载完成，期间暂停游戏时间
+fn hold_until_loaded(
+    mut hold: ResMut<LoadingHold>,
+    mut time: ResMut<Time<Virtual>>,
+    asset_server: Res<AssetServer>,
+    loot: Res<LootTables>,
+    cutscenes: Option<Res<Cutscenes>>,
+    player: Query<(), With<Player>>,
+    pending_profiles: Query<(), (With<AiProfileHandle>, Without<HasThinker>)>,
+) {
+    if !hold.0 { return; }
+    let ready = !player.is_empty()
+        && asset_server.is_loaded_with_dependencies(&loot.0)
+        && cutscenes.is_some_and(|cutscenes| asset_server.is_loaded_with_dependencies(&cutscenes.0))
+        && pending_profiles.is_empty();
+    if ready {
+        hold.0 = false;
+        time.unpause();
+    } else {
+        time.pause();
+    }
+}
+
+/// 记录玩家这一帧的动作
+fn record_frame(
+    mut mode: ResMut<ReplayMode>,
+    hold: Res<LoadingHold>,
+    player: Single<&mut ActionState<Action>, With<Player>>,
+    mut menu_state: ResMut<ActionState<MenuAction>>,
+) {
+    let ReplayMode::Record { recording, .. } = mode.as_mut() else { return; };
+    let mut action_state = player.into_inner();
+    if hold.0 {
+        apply_input(&mut action_state, &mut menu_state, &FrameInput::default());
+        return;
+    }
+    recording.frames.push(FrameInput {
+        pressed: Action::ALL.into_iter().filter(|action| action_state.pressed(action)).collect(),
+        axis: action_state.axis_pair(&Action::Move),
+        menu: MenuAction::ALL.into_iter().filter(|action| menu_state.pressed(action)).collect(),
+    });
+}
+
+/// 退出时写入录像
+fn write_recording(mut exits: EventReader<AppExit>, mode: Res<ReplayMode>) {
+    if exits.read().next().is_none() { return; }
+    if let ReplayMode::Record { path, recording } = mode.as_ref() {
+        recording.write(path);
+        println!("Recorded {} frames to {}", recording.frames.len(), path.display());
+    }
+}
+
+/// 回放时清空玩家的键位表，不再读取真实输入，菜单的键位表在构建时清空
+fn mute_live_input(
+    mode: Res<ReplayMode>,
+    mut players: Query<&mut InputMap<Action>, (With<Player>, Changed<InputMap<Action>>)>,
+) {
+    if !matches!(mode.as_ref(), ReplayMode::Replay { .. }) { return; }
+    for mut input_map in &mut players {
+        *input_map.bypass_change_detection() = InputMap::default();
+    }
+}
+
+/// 把录像中这一帧的动作写回玩家的动作状态，覆盖真实输入
+fn replay_frame(
+    mut mode: ResMut<ReplayMode>,
+    hold: Res<LoadingHold>,
+    player: Single<&mut ActionState<Action>, With<Player>>,
+    mut menu_state: ResMut<ActionState<MenuAction>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    let ReplayMode::Replay { recording, frame } = mode.as_mut() else { return; };
+    let mut action_state = player.into_inner();
+    if hold.0 {
+        apply_input(&mut action_state, &mut menu_state, &FrameInput::default());
+        return;
+    }
+    let Some(input) = recording.frames.get(*frame) else {
+        println!("Replay finished after {} frames", frame);
+        exit_events.write(AppExit::Success);
+        return;
+    };
+    apply_input(&mut action_state, &mut menu_state, input);
+    *frame += 1;
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let mode = ReplayMode::from_args();
+        let seeds = match &mode {
+            ReplayMode::Off => None,
+            ReplayMode::Record { recording, .. } => Some((recording.loot_seed, recording.game_seed, recording.timestep)),
+            ReplayMode::Replay { recording, .. } => {
+                // 回放使用录像中的存档，写入独立的文件
+                set_save_path(REPLAY_SAVE_PATH);
+                app.insert_resource(InputMap::<MenuAction>::default());
+                match &recording.save {
+                    Some(save) => write(save),
+                    None => { let _ = std::fs::remove_file(REPLAY_SAVE_PATH); }
+                }
+                Some((recording.loot_seed, recording.game_seed, recording.timestep))
+            }
+        };
+        app.insert_resource(LoadingHold(seeds.is_some()));
+        match seeds {
+            Some((loot_seed, game_seed, timestep)) => {
+                app.insert_resource(LootRng::new(loot_seed));
+                app.insert_resource(GameRng::new(game_seed));
+                app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(timestep)));
+                app.insert_resource(Time::<Fixed>::from_seconds(timestep));
+                // 跳过主菜单，录像从玩家出现的第一帧开始
+                app.insert_state(AppState::InGame);
+            }
+            None => {
+                app.init_resource::<GameRng>();
+            }
+        }
+        app.insert_resource(mode);
+        app.add_systems(PreUpdate, mute_live_input.before(InputManagerSystem::Update));
+        app.add_systems(
+            PreUpdate,
+            (hold_until_loaded, record_frame, replay_frame).chain().in_set(InputManagerSystem::ManualControl),
+        );
+        app.add_systems(Last, write_recording);
+    }
+}
@@ -3,12 +3,25 @@
 use bevy::{prelude::*, scene::ron};
 use moonshine_save::prelude::*;
 
-use std::{collections::{HashMap}, path::{Path, PathBuf}};
+use std::{collections::{HashMap, HashSet}, path::{Path, PathBuf}, sync::OnceLock};
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::{Read, Write};
 
-use crate::{animator::{Animator, AnimatorParam}, damagable::Damagable, player::Player};
+use crate::{abilities::Abilities, animator::{Animator, AnimatorParam}, damagable::Damagable, items::AbilityType, player::Player, signal::SignalStates, world_flags::WorldFlags};
+
+/// 存档路径，回放录像时改为独立的文件，见`replay`
+static SAVE_PATH: OnceLock<String> = OnceLock::new();
+
+/// 设置存档路径，只在启动时生效一次
+pub fn set_save_path(path: &str) {
+    let _ = SAVE_PATH.set(path.to_string());
+}
+
+/// 当前存档路径
+fn save_path() -> &'static str {
+    SAVE_PATH.get().map(|path| path.as_str()).unwrap_or("save.ron")
+}
 
 /// 存档事件
 #[derive(Event)]
@@ -17,6 +30,12 @@ pub struct SaveRequest {
     path: PathBuf,
 }
 
+impl Default for SaveRequest {
+    fn default() -> Self {
+        Self { path: "save.ron".into() }
+    }
+}
+
 impl GetFilePath for SaveRequest {
     /// 获取存档路径
     fn path(&self) -> &Path {
@@ -45,6 +64,15 @@ pub struct TransformData {
     pub scale: [f32; 3],
     pub params: HashMap<String, AnimatorParam>,
     pub damagable: Damagable,
+    /// 机关信号状态
+    #[serde(default)]
+    pub signals: HashMap<String, bool>,
+    /// 世界标记
+    #[serde(default)]
+    pub flags: HashSet<String>,
+    /// 已解锁的能力
+    #[serde(default)]
+    pub abilities: HashSet<AbilityType>,
 }
 
 impl Default for TransformData {
@@ -60,6 +88,9 @@ impl Default for TransformData {
             scale:[1.0, 1.0, 1.0],
             params: HashMap::new(),
             damagable: Damagable::new(150.),
+            signals: HashMap::new(),
+            flags: HashSet::new(),
+            abilities: HashSet::new(),
         }
     }
 }
@@ -75,8 +106,12 @@ pub fn _trigger_load(mut events: EventWriter<LoadRequest>) {
 }
 
 /// 存档
-pub fn save(player: Single<(&Transform, &Animator, &Damagable), With<Player>>) {
-    let (transform, animator, dam) = player.into_inner();
+pub fn save(
+    player: Single<(&Transform, &Animator, &Damagable, &Abilities), With<Player>>,
+    signals: Res<SignalStates>,
+    flags: Res<WorldFlags>,
+) {
+    let (transform, animator, dam, abilities) = player.into_inner();
     let transform_data = TransformData {
         translation: [
             transform.translation.x,
@@ -92,20 +127,28 @@ pub fn save(player: Single<(&Transform, &Animator, &Damagable), With<Player>>) {
         scale: [transform.scale.x, transform.scale.y, transform.scale.z],
         params: animator.parameters.clone(),
         damagable: dam.clone(),
+        signals: signals.states.clone(),
+        flags: flags.flags.clone(),
+        abilities: abilities.unlocked.clone(),
     };
+    write(&transform_data);
+}
+
+/// 写入存档文件
+pub fn write(transform_data: &TransformData) {
     let config = ron::ser::PrettyConfig::default()
             .separate_tuple_members(true)
             .enumerate_arrays(true);
         
-    let ron_string = ron::ser::to_string_pretty(&transform_data, config).unwrap();
+    let ron_string = ron::ser::to_string_pretty(transform_data, config).unwrap();
     
-    let mut file = File::create("save.ron").unwrap();
+    let mut file = File::create(save_path()).unwrap();
     file.write_all(ron_string.as_bytes()).unwrap();
 }
 
 /// 读档
 pub fn load() -> Option<TransformData> {
-    let mut file = match File::open("save.ron") {
+    let mut file = match File::open(save_path()) {
         Ok(file) => file,
         Err(_) => {
             println!("Could not open scene.ron");
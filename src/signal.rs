@@ -0,0 +1,711 @@
+//! 机关联动系统
+//! 拉杆、压力板、可破坏墙体与Boss死亡等发出具名信号，门、移动平台与生成器监听信号作出反应。
+//! 在Tiled中，发送方通过`signal`属性或引用接收方的`target`对象属性连线；
+//! 接收方未设置`signal`时监听自身ID对应的信号。所有信号状态会随存档保存。
+
+use std::collections::HashMap;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    controls::Prompt, damagable::HitBox, game_layer::GameLayer, healthbar::Hint, input::Action, items::{spawn_pickup, ItemList}, level::{LevelObject, LevelObjects}, locale::Localization, player::Player, save::load,
+    world_flags::{door_flag, item_flag, SetWorldFlag, WorldFlags},
+};
+
+/// 信号触发器
+#[derive(Event, Clone, Debug)]
+pub struct SignalTrigger {
+    /// 信号名
+    pub signal: String,
+    /// 信号是否激活
+    pub active: bool,
+}
+
+impl SignalTrigger {
+    /// 激活信号
+    pub fn on(signal: &str) -> Self {
+        Self { signal: signal.to_string(), active: true }
+    }
+    /// 关闭信号
+    pub fn off(signal: &str) -> Self {
+        Self { signal: signal.to_string(), active: false }
+    }
+}
+
+/// 所有信号的当前状态
+#[derive(Resource, Default, Debug)]
+pub struct SignalStates {
+    pub states: HashMap<String, bool>,
+}
+
+impl SignalStates {
+    /// 获取信号状态，未出现过的信号视为关闭
+    pub fn is_active(&self, signal: &String) -> bool {
+        *self.states.get(signal).unwrap_or(&false)
+    }
+}
+
+/// 门，信号激活时打开（inverted时相反）
+#[derive(Component, Debug, Clone)]
+pub struct Door {
+    /// 监听的信号
+    pub signal: String,
+    /// 是否反转：信号激活时关闭
+    pub inverted: bool,
+    /// 门的尺寸
+    pub size: Vec2,
+    /// 门的贴图路径
+    pub image: String,
+    /// 当前是否打开
+    pub open: bool,
+}
+
+impl Door {
+    /// 新建门，默认信号激活时打开
+    pub fn new(signal: &str, size: Vec2, image: &str) -> Self {
+        Self {
+            signal: signal.to_string(),
+            inverted: false,
+            size,
+            image: image.to_string(),
+            open: false,
+        }
+    }
+
+    /// 设置为反转门
+    pub fn inverted(mut self) -> Self {
+        self.inverted = true;
+        self
+    }
+}
+
+/// 拉杆，玩家靠近后按拾取键切换
+#[derive(Component, Debug, Clone)]
+pub struct Lever {
+    /// 发出的信号
+    pub signal: String,
+    /// 当前是否拉下
+    pub is_on: bool,
+}
+
+/// 压力板，玩家站在上面时激活
+#[derive(Component, Debug, Clone)]
+pub struct PressurePlate {
+    /// 发出的信号
+    pub signal: String,
+    /// 当前踩在上面的实体数
+    pub pressers: u32,
+}
+
+/// 可破坏墙体，被玩家攻击破坏后激活信号
+#[derive(Component, Debug, Clone)]
+pub struct BreakableWall {
+    /// 发出的信号
+    pub signal: String,
+    /// 耐久
+    pub health: f32,
+    /// 墙体尺寸
+    pub size: Vec2,
+}
+
+/// 可破坏墙体的受击感知器
+#[derive(Component)]
+struct WallHurtbox(Entity);
+
+/// 移动平台，信号激活时移动到偏移位置，关闭时回到原位
+#[derive(Component, Debug, Clone)]
+pub struct MovingPlatform {
+    /// 监听的信号
+    pub signal: String,
+    /// 激活后的偏移
+    pub offset: Vec2,
+    /// 移动速度
+    pub speed: f32,
+    /// 平台尺寸
+    pub size: Vec2,
+    /// 初始位置
+    pub origin: Vec2,
+    /// 当前是否激活
+    pub active: bool,
+}
+
+/// 生成器，信号激活时生成道具
+#[derive(Component, Debug, Clone)]
+pub struct SignalSpawner {
+    /// 监听的信号
+    pub signal: String,
+    /// 生成的道具ID
+    pub item: String,
+    /// 生成的道具数量
+    pub num: u32,
+    /// 是否已经生成过
+    pub spawned: bool,
+}
+
+/// 信号感知区域，玩家进入时发出信号（只触发一次）
+#[derive(Component, Debug, Clone)]
+pub struct SignalSensor {
+    /// 发出的信号
+    pub signal: String,
+    /// 发出的信号状态
+    pub active: bool,
+}
+
+/// 玩家处于交互范围内的标识组件
+#[derive(Component)]
+pub struct InRange;
+
+/// 发送方的信号名：`signal`属性优先，其次为`target`引用的对象
+fn sender_signal(obj: &LevelObject) -> String {
+    obj.get_string("signal")
+        .or(obj.get_object("target").map(|id| format!("object_{}", id)))
+        .unwrap_or(format!("object_{}", obj.id))
+}
+
+/// 接收方的信号名：`signal`属性优先，其次为自身ID
+fn receiver_signal(obj: &LevelObject) -> String {
+    obj.get_string("signal").unwrap_or(format!("object_{}", obj.id))
+}
+
+/// 读取地图对象层中的机关
+fn spawn_level_signal_objects(mut commands: Commands, level: Res<LevelObjects>) {
+    for obj in &level.objects {
+        let transform = Transform::from_translation(obj.position.extend(0.));
+        match obj.class.as_str() {
+            "Door" => {
+                let door = Door::new(
+                    &receiver_signal(obj),
+                    obj.size,
+                    &obj.get_string("image").unwrap_or("Art/pixilart-drawing2.png".to_string()),
+                );
+                let door = if obj.get_bool("inverted").unwrap_or(false) { door.inverted() } else { door };
+                commands.spawn((door, transform));
+            }
+            "Lever" => {
+                commands.spawn((
+                    Lever { signal: sender_signal(obj), is_on: false },
+                    transform,
+                ));
+            }
+            "PressurePlate" => {
+                commands.spawn((
+                    PressurePlate { signal: sender_signal(obj), pressers: 0 },
+                    Collider::rectangle(obj.size.x, obj.size.y),
+                    transform,
+                ));
+            }
+            "BreakableWall" => {
+                commands.spawn((
+                    BreakableWall {
+                        signal: sender_signal(obj),
+                        health: obj.get_float("health").unwrap_or(100.),
+                        size: obj.size,
+                    },
+                    transform,
+                ));
+            }
+            "MovingPlatform" => {
+                commands.spawn((
+                    MovingPlatform {
+                        signal: receiver_signal(obj),
+                        offset: Vec2::new(
+                            obj.get_float("offset_x").unwrap_or(0.),
+                            obj.get_float("offset_y").unwrap_or(0.),
+                        ),
+                        speed: obj.get_float("speed").unwrap_or(40.),
+                        size: obj.size,
+                        origin: obj.position,
+                        active: false,
+                    },
+                    transform,
+                ));
+            }
+            "SignalSensor" => {
+                commands.spawn((
+                    SignalSensor {
+                        signal: sender_signal(obj),
+                        active: obj.get_bool("active").unwrap_or(true),
+                    },
+                    Collider::rectangle(obj.size.x, obj.size.y),
+                    transform,
+                ));
+            }
+            "SignalSpawner" => {
+                commands.spawn((
+                    SignalSpawner {
+                        signal: receiver_signal(obj),
+                        item: obj.get_string("item").unwrap_or("HealthPotion".to_string()),
+                        num: obj.get_float("num").unwrap_or(1.) as u32,
+                        spawned: false,
+                    },
+                    transform,
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 从存档恢复信号状态
+fn restore_signal_states(mut states: ResMut<SignalStates>) {
+    if let Some(data) = load() {
+        states.states = data.signals;
+    }
+}
+
+/// 记录信号状态
+fn record_signal(trigger: Trigger<SignalTrigger>, mut states: ResMut<SignalStates>) {
+    states.states.insert(trigger.signal.clone(), trigger.active);
+}
+
+/// 按开关状态更新门的碰撞体与可见性
+fn apply_door(commands: &mut Commands, entity: Entity, door: &Door) {
+    if door.open {
+        commands.entity(entity).remove::<Collider>().insert(Visibility::Hidden);
+    } else {
+        commands.entity(entity)
+            .insert(Collider::rectangle(door.size.x, door.size.y))
+            .insert(Visibility::Inherited);
+    }
+}
+
+/// 初始化门
+fn on_add_door(
+    trigger: Trigger<OnAdd, Door>,
+    mut commands: Commands,
+    mut doors: Query<&mut Door>,
+    states: Res<SignalStates>,
+    flags: Res<WorldFlags>,
+    asset_server: Res<AssetServer>,
+) {
+    let entity = trigger.target();
+    let mut door = doors.get_mut(entity).unwrap();
+    // 打开过的门保持打开
+    let opened = !door.inverted && flags.is_set(&door_flag(&door.signal));
+    door.open = opened || states.is_active(&door.signal) != door.inverted;
+    commands.entity(entity).insert((
+        Sprite {
+            image: asset_server.load(door.image.clone()),
+            custom_size: Some(door.size),
+            ..default()
+        },
+        RigidBody::Static,
+        CollisionLayers::new(
+            GameLayer::Ground,
+            [GameLayer::Default, GameLayer::Player, GameLayer::Enemy],
+        ),
+    ));
+    apply_door(&mut commands, entity, &door);
+}
+
+/// 门响应信号
+fn door_signal_observer(
+    trigger: Trigger<SignalTrigger>,
+    mut commands: Commands,
+    mut doors: Query<(Entity, &mut Door)>,
+) {
+    for (entity, mut door) in &mut doors {
+        if door.signal != trigger.signal { continue; }
+        let open = trigger.active != door.inverted;
+        if door.open == open { continue; }
+        door.open = open;
+        apply_door(&mut commands, entity, &door);
+        if open && !door.inverted {
+            commands.trigger(SetWorldFlag(door_flag(&door.signal)));
+        }
+    }
+}
+
+/// 拉杆的颜色
+fn lever_color(is_on: bool) -> Color {
+    if is_on { Color::srgb(0.9, 0.75, 0.2) } else { Color::srgb(0.45, 0.45, 0.5) }
+}
+
+/// 初始化拉杆
+fn on_add_lever(
+    trigger: Trigger<OnAdd, Lever>,
+    mut commands: Commands,
+    mut levers: Query<&mut Lever>,
+    states: Res<SignalStates>,
+) {
+    let entity = trigger.target();
+    let mut lever = levers.get_mut(entity).unwrap();
+    lever.is_on = states.is_active(&lever.signal);
+    commands.entity(entity).insert((
+        Sprite::from_color(lever_color(lever.is_on), Vec2::new(4., 12.)),
+        Collider::rectangle(24., 24.),
+        Sensor,
+        CollisionEventsEnabled,
+        CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player]),
+    )).observe(enter_range_observer).observe(exit_range_observer);
+}
+
+/// 玩家进入交互范围
+fn enter_range_observer(
+    trigger: Trigger<OnCollisionStart>,
+    mut commands: Commands,
+    locale: Res<Localization>,
+    mut prompt: Single<&mut Prompt, With<Hint>>,
+) {
+    commands.entity(trigger.target()).insert(InRange);
+    prompt.set(locale.tr("ui.pull_lever"));
+}
+
+/// 玩家离开交互范围
+fn exit_range_observer(
+    trigger: Trigger<OnCollisionEnd>,
+    mut commands: Commands,
+    mut prompt: Single<&mut Prompt, With<Hint>>,
+) {
+    commands.entity(trigger.target()).remove::<InRange>();
+    prompt.set("");
+}
+
+/// 拉动拉杆
+fn lever_system(
+    mut commands: Commands,
+    player: Single<&ActionState<Action>, With<Player>>,
+    mut levers: Query<(&mut Lever, &mut Sprite), With<InRange>>,
+) {
+    if !player.into_inner().just_pressed(&Action::PickItem) { return; }
+    for (mut lever, mut sprite) in &mut levers {
+        lever.is_on = !lever.is_on;
+        sprite.color = lever_color(lever.is_on);
+        commands.trigger(SignalTrigger { signal: lever.signal.clone(), active: lever.is_on });
+    }
+}
+
+/// 初始化压力板
+fn on_add_plate(trigger: Trigger<OnAdd, PressurePlate>, mut commands: Commands) {
+    commands.entity(trigger.target()).insert((
+        Sensor,
+        CollisionEventsEnabled,
+        CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player]),
+    )).observe(plate_pressed_observer).observe(plate_released_observer);
+}
+
+/// 踩下压力板
+fn plate_pressed_observer(
+    trigger: Trigger<OnCollisionStart>,
+    mut commands: Commands,
+    mut plates: Query<&mut PressurePlate>,
+) {
+    let mut plate = plates.get_mut(trigger.target()).unwrap();
+    plate.pressers += 1;
+    if plate.pressers == 1 {
+        commands.trigger(SignalTrigger::on(&plate.signal));
+    }
+}
+
+/// 离开压力板
+fn plate_released_observer(
+    trigger: Trigger<OnCollisionEnd>,
+    mut commands: Commands,
+    mut plates: Query<&mut PressurePlate>,
+) {
+    let Ok(mut plate) = plates.get_mut(trigger.target()) else { return; };
+    plate.pressers = plate.pressers.saturating_sub(1);
+    if plate.pressers == 0 {
+        commands.trigger(SignalTrigger::off(&plate.signal));
+    }
+}
+
+/// 初始化信号感知区域
+fn on_add_signal_sensor(trigger: Trigger<OnAdd, SignalSensor>, mut commands: Commands) {
+    commands.entity(trigger.target()).insert((
+        Sensor,
+        CollisionEventsEnabled,
+        CollisionLayers::new(GameLayer::Sensor, [GameLayer::Player]),
+    )).observe(signal_sensor_observer);
+}
+
+/// 玩家进入信号感知区域
+fn signal_sensor_observer(
+    trigger: Trigger<OnCollisionStart>,
+    mut commands: Commands,
+    sensors: Query<&SignalSensor>,
+) {
+    let sensor = sensors.get(trigger.target()).unwrap();
+    commands.trigger(SignalTrigger { signal: sensor.signal.clone(), active: sensor.active });
+    commands.entity(trigger.target()).despawn();
+}
+
+/// 初始化可破坏墙体，已破坏的墙体直接移除
+fn on_add_wall(
+    trigger: Trigger<OnAdd, BreakableWall>,
+    mut commands: Commands,
+    walls: Query<&BreakableWall>,
+    states: Res<SignalStates>,
+    asset_server: Res<AssetServer>,
+) {
+    let entity = trigger.target();
+    let wall = walls.get(entity).unwrap();
+    if states.is_active(&wall.signal) {
+        commands.entity(entity).despawn();
+        return;
+    }
+    commands.entity(entity).insert((
+        Sprite {
+            image: asset_server.load("Art/pixilart-drawing2.png"),
+            custom_size: Some(wall.size),
+            color: Color::srgb(0.8, 0.7, 0.6),
+            ..default()
+        },
+        RigidBody::Static,
+        Collider::rectangle(wall.size.x, wall.size.y),
+        CollisionLayers::new(
+            GameLayer::Ground,
+            [GameLayer::Default, GameLayer::Player, GameLayer::Enemy],
+        ),
+    ));
+    commands.spawn((
+        Collider::rectangle(wall.size.x + 2., wall.size.y),
+        Transform::default(),
+        Sensor,
+        CollisionEventsEnabled,
+        CollisionLayers::new(GameLayer::Interactive, [GameLayer::PlayerHitBox]),
+        WallHurtbox(entity),
+        ChildOf(entity),
+    )).observe(wall_hit_observer);
+}
+
+/// 墙体受到玩家攻击
+fn wall_hit_observer(
+    trigger: Trigger<OnCollisionStart>,
+    mut commands: Commands,
+    hurtboxes: Query<&WallHurtbox>,
+    hitboxes: Query<&HitBox>,
+    mut walls: Query<&mut BreakableWall>,
+) {
+    let Ok(hitbox) = hitboxes.get(trigger.collider) else { return; };
+    let wall_entity = hurtboxes.get(trigger.target()).unwrap().0;
+    let mut wall = walls.get_mut(wall_entity).unwrap();
+    wall.health -= hitbox.damage;
+    if wall.health <= 0. {
+        commands.trigger(SignalTrigger::on(&wall.signal));
+        commands.entity(wall_entity).despawn();
+    }
+}
+
+/// 初始化移动平台
+fn on_add_platform(
+    trigger: Trigger<OnAdd, MovingPlatform>,
+    mut commands: Commands,
+    mut platforms: Query<&mut MovingPlatform>,
+    states: Res<SignalStates>,
+    asset_server: Res<AssetServer>,
+) {
+    let entity = trigger.target();
+    let mut platform = platforms.get_mut(entity).unwrap();
+    platform.active = states.is_active(&platform.signal);
+    commands.entity(entity).insert((
+        Sprite {
+            image: asset_server.load("Art/pixilart-drawing2.png"),
+            custom_size: Some(platform.size),
+            ..default()
+        },
+        RigidBody::Kinematic,
+        Collider::rectangle(platform.size.x, platform.size.y),
+        CollisionLayers::new(
+            GameLayer::Ground,
+            [GameLayer::Default, GameLayer::Player, GameLayer::Enemy],
+        ),
+    ));
+}
+
+/// 移动平台响应信号
+fn platform_signal_observer(
+    trigger: Trigger<SignalTrigger>,
+    mut platforms: Query<&mut MovingPlatform>,
+) {
+    for mut platform in &mut platforms {
+        if platform.signal == trigger.signal {
+            platform.active = trigger.active;
+        }
+    }
+}
+
+/// 移动平台向目标位置移动
+fn move_platforms(
+    mut platforms: Query<(&MovingPlatform, &Transform, &mut LinearVelocity)>,
+    time: Res<Time>,
+) {
+    for (platform, transform, mut velocity) in &mut platforms {
+        let target = if platform.active {
+            platform.origin + platform.offset
+        } else {
+            platform.origin
+        };
+        let delta = target - transform.translation.truncate();
+        let step = platform.speed * time.delta_secs();
+        velocity.0 = if delta.length() <= step {
+            Vec2::ZERO
+        } else {
+            delta.normalize() * platform.speed
+        };
+    }
+}
+
+/// 初始化生成器，信号已激活或唯一道具已被拾取则视为已经生成过
+fn on_add_spawner(
+    trigger: Trigger<OnAdd, SignalSpawner>,
+    mut spawners: Query<&mut SignalSpawner>,
+    states: Res<SignalStates>,
+    flags: Res<WorldFlags>,
+) {
+    let mut spawner = spawners.get_mut(trigger.target()).unwrap();
+    spawner.spawned = states.is_active(&spawner.signal) || flags.is_set(&item_flag(&spawner.item));
+}
+
+/// 生成器响应信号
+fn spawner_signal_observer(
+    trigger: Trigger<SignalTrigger>,
+    mut commands: Commands,
+    mut spawners: Query<(&mut SignalSpawner, &Transform)>,
+    items: Res<ItemList>,
+) {
+    if !trigger.active { return; }
+    for (mut spawner, transform) in &mut spawners {
+        if spawner.signal != trigger.signal || spawner.spawned { continue; }
+        spawner.spawned = true;
+        spawn_pickup(&mut commands, &items, &spawner.item, spawner.num, transform.translation);
+    }
+}
+
+pub struct SignalPlugin<S: States> {
+    pub state: S,
+}
+
+impl<S: States> Plugin for SignalPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SignalStates>();
+        app.add_systems(Startup, restore_signal_states);
+        app.add_systems(OnEnter(self.state.clone()), spawn_level_signal_objects);
+        app.add_systems(Update, lever_system.run_if(in_state(self.state.clone())));
+        app.add_systems(FixedUpdate, move_platforms.run_if(in_state(self.state.clone())));
+        app.add_observer(record_signal);
+        app.add_observer(on_add_door);
+        app.add_observer(door_signal_observer);
+        app.add_observer(on_add_lever);
+        app.add_observer(on_add_plate);
+        app.add_observer(on_add_signal_sensor);
+        app.add_observer(on_add_wall);
+        app.add_observer(on_add_platform);
+        app.add_observer(platform_signal_observer);
+        app.add_observer(on_add_spawner);
+        app.add_observer(spawner_signal_observer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::items::{ItemInfo, ItemType, NotpickedItems};
+
+    fn signal_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<SignalStates>();
+        world.add_observer(record_signal);
+        world
+    }
+
+    fn is_active(world: &World, signal: &str) -> bool {
+        world.resource::<SignalStates>().is_active(&signal.to_string())
+    }
+
+    #[test]
+    fn pressure_plate_stays_on_until_last_presser_leaves() {
+        let mut world = signal_world();
+        world.add_observer(on_add_plate);
+        let plate = world.spawn(PressurePlate { signal: "plate".to_string(), pressers: 0 }).id();
+        let (first, second) = (world.spawn_empty().id(), world.spawn_empty().id());
+
+        world.trigger_targets(OnCollisionStart { collider: first, body: None }, plate);
+        world.trigger_targets(OnCollisionStart { collider: second, body: None }, plate);
+        assert!(is_active(&world, "plate"));
+
+        world.trigger_targets(OnCollisionEnd { collider: first, body: None }, plate);
+        assert!(is_active(&world, "plate"));
+        world.trigger_targets(OnCollisionEnd { collider: second, body: None }, plate);
+        assert!(!is_active(&world, "plate"));
+    }
+
+    #[test]
+    fn breakable_wall_sends_signal_when_destroyed() {
+        let mut world = signal_world();
+        let wall = world.spawn(BreakableWall { signal: "wall".to_string(), health: 50., size: Vec2::ONE }).id();
+        let hurtbox = world.spawn(WallHurtbox(wall)).observe(wall_hit_observer).id();
+        let hitbox = world.spawn(HitBox { damage: 30. }).id();
+
+        world.trigger_targets(OnCollisionStart { collider: hitbox, body: None }, hurtbox);
+        assert!(!is_active(&world, "wall"));
+        assert_eq!(world.get::<BreakableWall>(wall).unwrap().health, 20.);
+
+        world.trigger_targets(OnCollisionStart { collider: hitbox, body: None }, hurtbox);
+        assert!(is_active(&world, "wall"));
+        assert!(world.get_entity(wall).is_err());
+    }
+
+    #[test]
+    fn moving_platform_follows_signal() {
+        let mut world = signal_world();
+        world.init_resource::<Time>();
+        world.add_observer(platform_signal_observer);
+        let platform = world.spawn((
+            MovingPlatform {
+                signal: "platform".to_string(),
+                offset: Vec2::new(100., 0.),
+                speed: 40.,
+                size: Vec2::ONE,
+                origin: Vec2::ZERO,
+                active: false,
+            },
+            Transform::default(),
+            LinearVelocity::ZERO,
+        )).id();
+
+        world.trigger(SignalTrigger::on("platform"));
+        world.run_system_once(move_platforms).unwrap();
+        assert!(world.get::<MovingPlatform>(platform).unwrap().active);
+        assert_eq!(world.get::<LinearVelocity>(platform).unwrap().0, Vec2::new(40., 0.));
+
+        world.trigger(SignalTrigger::off("platform"));
+        world.run_system_once(move_platforms).unwrap();
+        assert!(!world.get::<MovingPlatform>(platform).unwrap().active);
+        assert_eq!(world.get::<LinearVelocity>(platform).unwrap().0, Vec2::ZERO);
+    }
+
+    #[test]
+    fn signal_spawner_spawns_once() {
+        let mut world = signal_world();
+        world.insert_resource(ItemList {
+            infos: HashMap::from([(
+                "HealthPotion".to_string(),
+                ItemInfo {
+                    name: "HealthPotion".to_string(),
+                    description: String::new(),
+                    icon: Handle::default(),
+                    max_stack: 5,
+                    item_type: ItemType::Currency,
+                },
+            )]),
+        });
+        world.add_observer(spawner_signal_observer);
+        world.spawn((
+            SignalSpawner { signal: "spawner".to_string(), item: "HealthPotion".to_string(), num: 2, spawned: false },
+            Transform::default(),
+        ));
+
+        world.trigger(SignalTrigger::off("spawner"));
+        assert_eq!(world.query::<&NotpickedItems>().iter(&world).count(), 0);
+
+        world.trigger(SignalTrigger::on("spawner"));
+        world.trigger(SignalTrigger::on("spawner"));
+        let pickups: Vec<_> = world.query::<&NotpickedItems>().iter(&world).map(|item| item.num).collect();
+        assert_eq!(pickups, vec![2]);
+    }
+}
@@ -7,13 +7,27 @@ use avian2d::prelude::*;
 
 use crate::game_layer::GameLayer;
 
+/// 地图文件路径（相对于assets目录）
+const MAP_ASSET: &str = "Tilemap/game.tmx";
+
+/// 地图文件路径（相对于工作目录），供直接解析对象层使用
+pub const MAP_PATH: &str = "assets/Tilemap/game.tmx";
+
+/// 地图实体的世界坐标，即左下角瓦片中心的位置
+pub const MAP_ORIGIN: Vec2 = Vec2::new(-180.0, -160.0);
+
 /// 导入并初始化地图
 fn setup_tilesets(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn((
         TiledMapHandle(
-            asset_server.load("Tilemap/game.tmx"),
+            asset_server.load(MAP_ASSET),
         ),
-        Transform::from_xyz(-180.0, -160.0, 0.0),
+        Transform::from_translation(MAP_ORIGIN.extend(0.0)),
+        // 只有Colliders对象层生成地形碰撞体，其余对象层交给level模块解析
+        TiledPhysicsSettings::<MyCustomAvianPhysicsBackend> {
+            objects_layer_filter: TiledNameFilter::from(vec!["Colliders"]),
+            ..default()
+        },
     )).insert(TiledMapLayerZOffset(0.1));
 }
 
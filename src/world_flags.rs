@@ -0,0 +1,68 @@
+//! 世界标记
+//! 记录一次性的世界事件（击败boss、打开的门与能力锁、拾取的唯一道具），随存档保存。
+//! 敌人与道具的生成器在生成前查询这里，已完成的事件不会因为重新启动游戏而复原。
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::save::load;
+
+/// 已设置的世界标记
+#[derive(Resource, Default, Debug)]
+pub struct WorldFlags {
+    pub flags: HashSet<String>,
+}
+
+impl WorldFlags {
+    /// 标记是否已设置
+    pub fn is_set(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+}
+
+/// 拾取唯一道具的标记名
+pub fn item_flag(item: &str) -> String {
+    format!("item_{}_collected", item)
+}
+
+/// 打开门的标记名
+pub fn door_flag(signal: &str) -> String {
+    format!("door_{}_opened", signal)
+}
+
+/// 打开能力锁的标记名
+pub fn lock_flag(id: u32) -> String {
+    format!("lock_{}_opened", id)
+}
+
+/// 播放过只播放一次的过场动画的标记名
+pub fn cutscene_flag(name: &str) -> String {
+    format!("cutscene_{}_played", name)
+}
+
+/// 设置世界标记触发器
+#[derive(Event, Clone, Debug)]
+pub struct SetWorldFlag(pub String);
+
+/// 从存档恢复世界标记
+fn restore_world_flags(mut flags: ResMut<WorldFlags>) {
+    if let Some(data) = load() {
+        flags.flags = data.flags;
+    }
+}
+
+/// 记录世界标记
+fn set_flag_observer(trigger: Trigger<SetWorldFlag>, mut flags: ResMut<WorldFlags>) {
+    flags.flags.insert(trigger.0.clone());
+}
+
+pub struct WorldFlagsPlugin;
+
+impl Plugin for WorldFlagsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldFlags>();
+        app.add_systems(Startup, restore_world_flags);
+        app.add_observer(set_flag_observer);
+    }
+}